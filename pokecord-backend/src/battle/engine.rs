@@ -0,0 +1,741 @@
+//! A turn-based battle between two teams of pokemon.
+//!
+//! Every random roll, from accuracy checks to speed ties, comes from one RNG seeded when the battle starts, so the same
+//! seed and choices always play out the same way. Battles can be replayed from their seed, and tested without mocks.
+
+use std::cmp::{self, Ordering};
+use std::collections::HashMap;
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use super::Error;
+
+/// Most moves a pokemon knows at once.
+pub const MAX_MOVES: usize = 4;
+
+/// Chance of a critical hit at each critical hit stage, as one in this many. Stages past the last are always critical.
+const CRITICAL_ODDS: [u32; 4] = [24, 8, 2, 1];
+const CRITICAL_MULTIPLIER: f64 = 1.5;
+
+/// Damage multiplier for moves of one of the attacker's own types.
+const STAB_MULTIPLIER: f64 = 1.5;
+
+/// Lowest random damage roll, in percent. Rolls are uniform from this to 100.
+const MIN_DAMAGE_ROLL: u32 = 85;
+
+/// Struggle, used once a pokemon is out of PP, is a typeless physical move that never misses and costs its user this
+/// fraction of its max HP.
+const STRUGGLE_POWER: u32 = 50;
+const STRUGGLE_RECOIL_DIVISOR: u32 = 4;
+
+/// Which stats a move uses, if it deals damage at all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DamageClass {
+    Physical,
+    Special,
+    /// Moves that don't deal damage. Their effects aren't part of battles yet, so they do nothing.
+    Status,
+}
+
+/// A pokemon's stats at its current level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BattleStats {
+    pub hp: u32,
+    pub attack: u32,
+    pub defense: u32,
+    pub special_attack: u32,
+    pub special_defense: u32,
+    pub speed: u32,
+}
+
+/// A move a pokemon knows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BattleMove {
+    /// PokeAPI name of the move.
+    pub name: String,
+    /// PokeAPI name of the move's type, or `None` for typeless moves.
+    pub type_name: Option<String>,
+    pub damage_class: DamageClass,
+    pub power: Option<u32>,
+    /// Percent chance of hitting, or `None` if the move never misses.
+    pub accuracy: Option<u8>,
+    /// Moves with higher priority go first, regardless of speed.
+    pub priority: i8,
+    /// Uses left.
+    pub pp: u8,
+    /// Critical hit stage the move adds.
+    pub critical_stage: u8,
+}
+
+impl BattleMove {
+    fn struggle() -> BattleMove {
+        BattleMove {
+            name: "struggle".to_string(),
+            type_name: None,
+            damage_class: DamageClass::Physical,
+            power: Some(STRUGGLE_POWER),
+            accuracy: None,
+            priority: 0,
+            pp: 0,
+            critical_stage: 0,
+        }
+    }
+}
+
+/// A pokemon taking part in a battle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Combatant {
+    /// Name to show, e.g. the pokemon's nickname.
+    pub name: String,
+    pub level: u32,
+    /// PokeAPI names of the pokemon's types.
+    pub types: Vec<String>,
+    /// Stats with full HP.
+    pub stats: BattleStats,
+    /// HP left. The pokemon has fainted at 0.
+    pub hp: u32,
+    pub moves: Vec<BattleMove>,
+}
+
+impl Combatant {
+    /// A pokemon with full HP.
+    pub fn new(name: String, level: u32, types: Vec<String>, stats: BattleStats, moves: Vec<BattleMove>) -> Combatant {
+        Combatant {
+            name,
+            level,
+            types,
+            hp: stats.hp,
+            stats,
+            moves,
+        }
+    }
+
+    pub fn is_fainted(&self) -> bool {
+        self.hp == 0
+    }
+}
+
+/// How effective each attacking type is against each defending type. Pairs that aren't listed deal normal damage.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TypeChart {
+    multipliers: HashMap<(String, String), f64>,
+}
+
+impl TypeChart {
+    pub fn new() -> TypeChart {
+        TypeChart::default()
+    }
+
+    /// Sets the damage multiplier of moves of type `attacking` against pokemon of type `defending`.
+    pub fn insert(&mut self, attacking: &str, defending: &str, multiplier: f64) {
+        self.multipliers
+            .insert((attacking.to_string(), defending.to_string()), multiplier);
+    }
+
+    /// Damage multiplier of a move of type `attacking` against a pokemon with `defending` types.
+    pub fn effectiveness(&self, attacking: &str, defending: &[String]) -> f64 {
+        defending
+            .iter()
+            .map(|defending| {
+                self.multipliers
+                    .get(&(attacking.to_string(), defending.clone()))
+                    .copied()
+                    .unwrap_or(1.0)
+            })
+            .product()
+    }
+}
+
+/// What a side does on its turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Uses the active pokemon's move in this slot.
+    Move(usize),
+    /// Uses Struggle, which is only allowed once every move is out of PP.
+    Struggle,
+}
+
+/// Something that happened during a turn. Sides are numbered 0 and 1, in the order the teams were passed to
+/// [`Battle::new`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    MoveUsed {
+        side: usize,
+        pokemon: String,
+        move_name: String,
+    },
+    /// `side`'s move missed.
+    Missed { side: usize },
+    /// `side`'s move did nothing, because the target is immune to its type or it has no damage.
+    NoEffect { side: usize },
+    /// `side`'s active pokemon took damage from the opponent's move.
+    Damaged {
+        side: usize,
+        damage: u32,
+        critical: bool,
+        effectiveness: f64,
+        hp: u32,
+    },
+    /// `side`'s active pokemon took recoil damage from its own move.
+    Recoil { side: usize, damage: u32, hp: u32 },
+    Fainted { side: usize, pokemon: String },
+    /// `side` sent out its next pokemon after the last one fainted.
+    SwitchedIn { side: usize, pokemon: String },
+    Won { side: usize },
+    /// Both sides ran out of pokemon in the same turn.
+    Draw,
+}
+
+/// A battle between two teams, each fighting with one active pokemon at a time.
+#[derive(Debug, Clone)]
+pub struct Battle {
+    teams: [Vec<Combatant>; 2],
+    /// Index of each side's active pokemon in its team.
+    active: [usize; 2],
+    chart: TypeChart,
+    rng: ChaCha8Rng,
+    turn: u32,
+}
+
+impl Battle {
+    /// A battle between two teams, which start with their first pokemon. Every random roll is drawn from an RNG
+    /// seeded with `seed`.
+    pub fn new(first: Vec<Combatant>, second: Vec<Combatant>, chart: TypeChart, seed: u64) -> Battle {
+        Battle {
+            teams: [first, second],
+            active: [0, 0],
+            chart,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            turn: 0,
+        }
+    }
+
+    /// Number of turns played so far.
+    pub fn turn(&self) -> u32 {
+        self.turn
+    }
+
+    pub fn team(&self, side: usize) -> &[Combatant] {
+        &self.teams[side]
+    }
+
+    pub fn active(&self, side: usize) -> &Combatant {
+        &self.teams[side][self.active[side]]
+    }
+
+    /// Whether either side has no pokemon left.
+    pub fn is_over(&self) -> bool {
+        (0..2).any(|side| self.is_defeated(side))
+    }
+
+    /// The side that won, once the battle is over. `None` while it's still going and after a draw.
+    pub fn winner(&self) -> Option<usize> {
+        match (self.is_defeated(0), self.is_defeated(1)) {
+            (false, true) => Some(0),
+            (true, false) => Some(1),
+            _ => None,
+        }
+    }
+
+    /// The action `side` would take if it picked the move expected to deal the most damage. Moves are compared by
+    /// power, accuracy, STAB and type effectiveness, so this doesn't use up any random rolls.
+    pub fn best_action(&self, side: usize) -> Action {
+        let attacker = self.active(side);
+        let defender = self.active(1 - side);
+        let expected = |mov: &BattleMove| match (mov.damage_class, mov.power) {
+            (DamageClass::Status, _) | (_, None) => 0.0,
+            (_, Some(power)) => {
+                power as f64
+                    * mov.accuracy.map_or(100.0, f64::from)
+                    * self.multiplier(attacker, defender, mov)
+            }
+        };
+        attacker
+            .moves
+            .iter()
+            .enumerate()
+            .filter(|(_, mov)| mov.pp > 0)
+            .fold(None, |best: Option<(usize, f64)>, (slot, mov)| {
+                let value = expected(mov);
+                match best {
+                    Some((_, best_value)) if best_value >= value => best,
+                    _ => Some((slot, value)),
+                }
+            })
+            .map_or(Action::Struggle, |(slot, _)| Action::Move(slot))
+    }
+
+    /// Plays one turn, in which both sides' active pokemon act. Moves with higher priority go first, then faster
+    /// pokemon, with speed ties decided at random. Pokemon that faint are replaced by the next in their team at the
+    /// end of the turn.
+    pub fn play_turn(&mut self, actions: [Action; 2]) -> Result<Vec<Event>, Error> {
+        if self.is_over() {
+            return Err(Error::Finished);
+        }
+        let moves = [self.chosen_move(0, actions[0])?, self.chosen_move(1, actions[1])?];
+        for (side, action) in actions.iter().enumerate() {
+            if let Action::Move(slot) = *action {
+                let active = self.active[side];
+                self.teams[side][active].moves[slot].pp -= 1;
+            }
+        }
+        self.turn += 1;
+
+        let order = match self.compare_order(&moves[0], &moves[1]) {
+            Ordering::Less => [1, 0],
+            _ => [0, 1],
+        };
+        let mut events = Vec::new();
+        for &side in &order {
+            // A pokemon that fainted before its turn came doesn't get to act
+            if !self.active(side).is_fainted() && !self.active(1 - side).is_fainted() {
+                self.use_move(side, &moves[side], actions[side] == Action::Struggle, &mut events);
+            }
+        }
+
+        for side in 0..2 {
+            if !self.active(side).is_fainted() {
+                continue;
+            }
+            if let Some(next) = self.teams[side].iter().position(|pokemon| !pokemon.is_fainted()) {
+                self.active[side] = next;
+                events.push(Event::SwitchedIn {
+                    side,
+                    pokemon: self.active(side).name.clone(),
+                });
+            }
+        }
+        if self.is_over() {
+            events.push(match self.winner() {
+                Some(side) => Event::Won { side },
+                None => Event::Draw,
+            });
+        }
+        Ok(events)
+    }
+
+    fn is_defeated(&self, side: usize) -> bool {
+        self.teams[side].iter().all(Combatant::is_fainted)
+    }
+
+    /// The move `action` uses, checking that `side` is allowed to use it.
+    fn chosen_move(&self, side: usize, action: Action) -> Result<BattleMove, Error> {
+        let active = self.active(side);
+        match action {
+            Action::Move(slot) => match active.moves.get(slot) {
+                Some(mov) if mov.pp == 0 => Err(Error::NoPp(mov.name.clone())),
+                Some(mov) => Ok(mov.clone()),
+                None => Err(Error::InvalidMove(slot)),
+            },
+            Action::Struggle if active.moves.iter().any(|mov| mov.pp > 0) => Err(Error::MovesLeft),
+            Action::Struggle => Ok(BattleMove::struggle()),
+        }
+    }
+
+    /// Compares side 0's move to side 1's, where [`Ordering::Greater`] means side 0 goes first. Speed ties are decided
+    /// at random.
+    fn compare_order(&mut self, first: &BattleMove, second: &BattleMove) -> Ordering {
+        let by_priority = first.priority.cmp(&second.priority);
+        let by_speed = self.active(0).stats.speed.cmp(&self.active(1).stats.speed);
+        match by_priority.then(by_speed) {
+            Ordering::Equal if self.rng.gen_bool(0.5) => Ordering::Less,
+            Ordering::Equal => Ordering::Greater,
+            order => order,
+        }
+    }
+
+    fn use_move(&mut self, side: usize, mov: &BattleMove, struggle: bool, events: &mut Vec<Event>) {
+        let target = 1 - side;
+        events.push(Event::MoveUsed {
+            side,
+            pokemon: self.active(side).name.clone(),
+            move_name: mov.name.clone(),
+        });
+        if let Some(accuracy) = mov.accuracy {
+            if self.rng.gen_range(1..=100) > u32::from(accuracy) {
+                events.push(Event::Missed { side });
+                return;
+            }
+        }
+        let power = match (mov.damage_class, mov.power) {
+            (DamageClass::Status, _) | (_, None) => {
+                events.push(Event::NoEffect { side });
+                return;
+            }
+            (_, Some(power)) => power,
+        };
+        let effectiveness = self.effectiveness(self.active(target), mov);
+        if effectiveness <= 0.0 {
+            events.push(Event::NoEffect { side });
+            return;
+        }
+
+        let stage = cmp::min(mov.critical_stage as usize, CRITICAL_ODDS.len() - 1);
+        let critical = self.rng.gen_ratio(1, CRITICAL_ODDS[stage]);
+        let roll = self.rng.gen_range(MIN_DAMAGE_ROLL..=100);
+        let damage = {
+            let attacker = self.active(side);
+            let defender = self.active(target);
+            let (attack, defense) = match mov.damage_class {
+                DamageClass::Special => (attacker.stats.special_attack, defender.stats.special_defense),
+                _ => (attacker.stats.attack, defender.stats.defense),
+            };
+            let base = (2 * attacker.level / 5 + 2) * power * attack / defense.max(1) / 50 + 2;
+            let mut multiplier = self.multiplier(attacker, defender, mov) * roll as f64 / 100.0;
+            if critical {
+                multiplier *= CRITICAL_MULTIPLIER;
+            }
+            cmp::max(1, (base as f64 * multiplier) as u32)
+        };
+
+        let defender = &mut self.teams[target][self.active[target]];
+        defender.hp = defender.hp.saturating_sub(damage);
+        events.push(Event::Damaged {
+            side: target,
+            damage,
+            critical,
+            effectiveness,
+            hp: defender.hp,
+        });
+        if defender.is_fainted() {
+            events.push(Event::Fainted {
+                side: target,
+                pokemon: defender.name.clone(),
+            });
+        }
+
+        if struggle {
+            let attacker = &mut self.teams[side][self.active[side]];
+            let recoil = cmp::max(1, attacker.stats.hp / STRUGGLE_RECOIL_DIVISOR);
+            attacker.hp = attacker.hp.saturating_sub(recoil);
+            events.push(Event::Recoil {
+                side,
+                damage: recoil,
+                hp: attacker.hp,
+            });
+            if attacker.is_fainted() {
+                events.push(Event::Fainted {
+                    side,
+                    pokemon: attacker.name.clone(),
+                });
+            }
+        }
+    }
+
+    fn effectiveness(&self, defender: &Combatant, mov: &BattleMove) -> f64 {
+        match &mov.type_name {
+            Some(type_name) => self.chart.effectiveness(type_name, &defender.types),
+            None => 1.0,
+        }
+    }
+
+    /// Damage multiplier from STAB and type effectiveness.
+    fn multiplier(&self, attacker: &Combatant, defender: &Combatant, mov: &BattleMove) -> f64 {
+        let stab = match &mov.type_name {
+            Some(type_name) if attacker.types.contains(type_name) => STAB_MULTIPLIER,
+            _ => 1.0,
+        };
+        stab * self.effectiveness(defender, mov)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn move_of(name: &str, type_name: Option<&str>, power: Option<u32>) -> BattleMove {
+        BattleMove {
+            name: name.to_string(),
+            type_name: type_name.map(str::to_string),
+            damage_class: if power.is_some() {
+                DamageClass::Physical
+            } else {
+                DamageClass::Status
+            },
+            power,
+            accuracy: None,
+            priority: 0,
+            pp: 10,
+            critical_stage: 0,
+        }
+    }
+
+    fn tackle() -> BattleMove {
+        move_of("tackle", Some("normal"), Some(40))
+    }
+
+    /// A move that does nothing, for the side a test isn't about.
+    fn growl() -> BattleMove {
+        move_of("growl", Some("normal"), None)
+    }
+
+    fn combatant(name: &str, types: &[&str], hp: u32, speed: u32, moves: Vec<BattleMove>) -> Combatant {
+        let stats = BattleStats {
+            hp,
+            attack: 50,
+            defense: 50,
+            special_attack: 50,
+            special_defense: 50,
+            speed,
+        };
+        Combatant::new(
+            name.to_string(),
+            50,
+            types.iter().map(|t| t.to_string()).collect(),
+            stats,
+            moves,
+        )
+    }
+
+    fn chart() -> TypeChart {
+        let mut chart = TypeChart::new();
+        chart.insert("water", "fire", 2.0);
+        chart.insert("fire", "water", 0.5);
+        chart.insert("normal", "ghost", 0.0);
+        chart
+    }
+
+    fn one_on_one(first: Combatant, second: Combatant, seed: u64) -> Battle {
+        Battle::new(vec![first], vec![second], chart(), seed)
+    }
+
+    /// Sides in the order they used moves.
+    fn move_order(events: &[Event]) -> Vec<usize> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                Event::MoveUsed { side, .. } => Some(*side),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Damage the first hit of a battle does to side 1, and its effectiveness. The hit is always critical, and side
+    /// 1 doesn't roll anything, so hits from battles with the same seed only differ by their multipliers.
+    fn first_hit(attacker_types: &[&str], mov: BattleMove, defender_types: &[&str]) -> (u32, f64) {
+        let mov = BattleMove {
+            critical_stage: CRITICAL_ODDS.len() as u8,
+            ..mov
+        };
+        let attacker = combatant("attacker", attacker_types, 500, 100, vec![mov]);
+        let defender = combatant("defender", defender_types, 500, 10, vec![growl()]);
+        let mut battle = one_on_one(attacker, defender, 3);
+        let events = battle.play_turn([Action::Move(0), Action::Move(0)]).unwrap();
+        events
+            .into_iter()
+            .find_map(|event| match event {
+                Event::Damaged {
+                    side: 1,
+                    damage,
+                    effectiveness,
+                    ..
+                } => Some((damage, effectiveness)),
+                _ => None,
+            })
+            .expect("the attacker hit")
+    }
+
+    /// Asserts that `damage` is `base` scaled by `multiplier`, give or take rounding.
+    fn assert_scaled(damage: u32, base: u32, multiplier: f64) {
+        let expected = base as f64 * multiplier;
+        assert!(
+            (damage as f64 - expected).abs() <= multiplier.max(1.0),
+            "{} is not {} times {}",
+            damage,
+            multiplier,
+            base
+        );
+    }
+
+    fn play_out(seed: u64) -> Vec<Event> {
+        let bite = BattleMove {
+            accuracy: Some(75),
+            ..move_of("bite", Some("dark"), Some(60))
+        };
+        let first = vec![
+            combatant("charmander", &["fire"], 120, 65, vec![tackle(), bite.clone()]),
+            combatant(
+                "squirtle",
+                &["water"],
+                130,
+                43,
+                vec![move_of("water-gun", Some("water"), Some(40))],
+            ),
+        ];
+        let second = vec![combatant("eevee", &["normal"], 150, 55, vec![tackle(), bite])];
+        let mut battle = Battle::new(first, second, chart(), seed);
+        let mut events = Vec::new();
+        while !battle.is_over() && battle.turn() < 100 {
+            let actions = [battle.best_action(0), battle.best_action(1)];
+            events.extend(battle.play_turn(actions).unwrap());
+        }
+        events
+    }
+
+    #[test]
+    fn the_same_seed_plays_out_the_same() {
+        assert_eq!(play_out(42), play_out(42));
+    }
+
+    #[test]
+    fn faster_pokemon_move_first() {
+        let slow = combatant("slowpoke", &["water"], 200, 15, vec![tackle()]);
+        let fast = combatant("jolteon", &["electric"], 200, 130, vec![tackle()]);
+        let mut battle = one_on_one(slow, fast, 0);
+        let events = battle.play_turn([Action::Move(0), Action::Move(0)]).unwrap();
+        assert_eq!(move_order(&events), vec![1, 0]);
+    }
+
+    #[test]
+    fn priority_goes_before_speed() {
+        let quick_attack = BattleMove {
+            priority: 1,
+            ..move_of("quick-attack", Some("normal"), Some(40))
+        };
+        let slow = combatant("slowpoke", &["water"], 200, 15, vec![quick_attack]);
+        let fast = combatant("jolteon", &["electric"], 200, 130, vec![tackle()]);
+        let mut battle = one_on_one(slow, fast, 0);
+        let events = battle.play_turn([Action::Move(0), Action::Move(0)]).unwrap();
+        assert_eq!(move_order(&events), vec![0, 1]);
+    }
+
+    #[test]
+    fn moves_failing_their_accuracy_roll_miss() {
+        let never_hits = BattleMove {
+            accuracy: Some(0),
+            ..tackle()
+        };
+        let attacker = combatant("attacker", &["normal"], 200, 100, vec![never_hits]);
+        let defender = combatant("defender", &["normal"], 200, 10, vec![growl()]);
+        let mut battle = one_on_one(attacker, defender, 0);
+        let events = battle.play_turn([Action::Move(0), Action::Move(0)]).unwrap();
+        assert!(events.contains(&Event::Missed { side: 0 }));
+        assert_eq!(battle.active(1).hp, 200);
+    }
+
+    #[test]
+    fn immune_pokemon_take_no_damage() {
+        let attacker = combatant("attacker", &["normal"], 200, 100, vec![tackle()]);
+        let defender = combatant("gastly", &["ghost", "poison"], 200, 10, vec![growl()]);
+        let mut battle = one_on_one(attacker, defender, 0);
+        let events = battle.play_turn([Action::Move(0), Action::Move(0)]).unwrap();
+        assert!(events.contains(&Event::NoEffect { side: 0 }));
+        assert_eq!(battle.active(1).hp, 200);
+    }
+
+    #[test]
+    fn stab_and_effectiveness_multiply_damage() {
+        let (neutral, effectiveness) = first_hit(&["normal"], move_of("strike", None, Some(80)), &["fire"]);
+        assert_eq!(effectiveness, 1.0);
+        let surf = || move_of("surf", Some("water"), Some(80));
+
+        let (stab, _) = first_hit(&["water"], surf(), &["normal"]);
+        assert_scaled(stab, neutral, STAB_MULTIPLIER);
+        let (super_effective, effectiveness) = first_hit(&["normal"], surf(), &["fire"]);
+        assert_eq!(effectiveness, 2.0);
+        assert_scaled(super_effective, neutral, 2.0);
+        let (both, _) = first_hit(&["water"], surf(), &["fire"]);
+        assert_scaled(both, neutral, STAB_MULTIPLIER * 2.0);
+        let (resisted, effectiveness) = first_hit(&["normal"], move_of("ember", Some("fire"), Some(80)), &["water"]);
+        assert_eq!(effectiveness, 0.5);
+        assert_scaled(resisted, neutral, 0.5);
+    }
+
+    #[test]
+    fn pokemon_out_of_pp_struggle_and_take_recoil() {
+        let last_tackle = BattleMove { pp: 1, ..tackle() };
+        let attacker = combatant("attacker", &["normal"], 200, 100, vec![last_tackle]);
+        let defender = combatant("defender", &["normal"], 500, 10, vec![growl()]);
+        let mut battle = one_on_one(attacker, defender, 0);
+
+        assert_eq!(
+            battle.play_turn([Action::Struggle, Action::Move(0)]),
+            Err(Error::MovesLeft)
+        );
+        assert_eq!(battle.best_action(0), Action::Move(0));
+        battle.play_turn([Action::Move(0), Action::Move(0)]).unwrap();
+        assert_eq!(battle.active(0).moves[0].pp, 0);
+        let no_pp = Err(Error::NoPp("tackle".to_string()));
+        assert_eq!(battle.play_turn([Action::Move(0), Action::Move(0)]), no_pp);
+
+        assert_eq!(battle.best_action(0), Action::Struggle);
+        let events = battle.play_turn([Action::Struggle, Action::Move(0)]).unwrap();
+        assert!(events.iter().any(|event| matches!(
+            event,
+            Event::MoveUsed { side: 0, move_name, .. } if move_name == "struggle"
+        )));
+        assert!(events.contains(&Event::Recoil {
+            side: 0,
+            damage: 50,
+            hp: 150
+        }));
+    }
+
+    #[test]
+    fn fainted_pokemon_are_replaced_by_the_next() {
+        let attacker = combatant("attacker", &["normal"], 200, 100, vec![tackle()]);
+        let team = vec![
+            combatant("magikarp", &["water"], 1, 10, vec![growl()]),
+            combatant("gyarados", &["water", "flying"], 200, 10, vec![growl()]),
+        ];
+        let mut battle = Battle::new(vec![attacker], team, chart(), 0);
+        let events = battle.play_turn([Action::Move(0), Action::Move(0)]).unwrap();
+
+        let fainted = Event::Fainted {
+            side: 1,
+            pokemon: "magikarp".to_string(),
+        };
+        let switched = Event::SwitchedIn {
+            side: 1,
+            pokemon: "gyarados".to_string(),
+        };
+        let position = |wanted: &Event| events.iter().position(|event| event == wanted);
+        assert!(position(&fainted).unwrap() < position(&switched).unwrap());
+        // Magikarp fainted before its turn came, so it didn't get to move
+        assert_eq!(move_order(&events), vec![0]);
+        assert_eq!(battle.active(1).name, "gyarados");
+        assert!(!battle.is_over());
+    }
+
+    #[test]
+    fn knocking_out_the_last_pokemon_wins() {
+        let attacker = combatant("attacker", &["normal"], 200, 100, vec![tackle()]);
+        let defender = combatant("magikarp", &["water"], 1, 10, vec![growl()]);
+        let mut battle = one_on_one(attacker, defender, 0);
+        let events = battle.play_turn([Action::Move(0), Action::Move(0)]).unwrap();
+
+        assert_eq!(events.last(), Some(&Event::Won { side: 0 }));
+        assert!(battle.is_over());
+        assert_eq!(battle.winner(), Some(0));
+        assert_eq!(
+            battle.play_turn([Action::Move(0), Action::Move(0)]),
+            Err(Error::Finished)
+        );
+    }
+
+    #[test]
+    fn both_sides_fainting_together_is_a_draw() {
+        // Struggling knocks out the target, and the recoil knocks out the user
+        let struggler = combatant("struggler", &["normal"], 1, 100, Vec::new());
+        let defender = combatant("magikarp", &["water"], 1, 10, vec![growl()]);
+        let mut battle = one_on_one(struggler, defender, 0);
+        let events = battle.play_turn([Action::Struggle, Action::Move(0)]).unwrap();
+
+        assert_eq!(events.last(), Some(&Event::Draw));
+        assert!(battle.is_over());
+        assert_eq!(battle.winner(), None);
+    }
+
+    #[test]
+    fn moves_must_exist() {
+        let attacker = combatant("attacker", &["normal"], 200, 100, vec![tackle()]);
+        let defender = combatant("defender", &["normal"], 200, 10, vec![growl()]);
+        let mut battle = one_on_one(attacker, defender, 0);
+        assert_eq!(
+            battle.play_turn([Action::Move(4), Action::Move(0)]),
+            Err(Error::InvalidMove(4))
+        );
+        assert_eq!(battle.turn(), 0);
+    }
+}
@@ -0,0 +1,27 @@
+use pyo3::prelude::*;
+use pyo3::PyErrArguments;
+
+/// Battle error
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    #[error("The active pokemon has no move in slot {0}")]
+    InvalidMove(usize),
+    #[error("{0} has no PP left")]
+    NoPp(String),
+    #[error("Struggle can only be used once every move is out of PP")]
+    MovesLeft,
+    #[error("The battle is already over")]
+    Finished,
+}
+
+impl PyErrArguments for Error {
+    fn arguments(self, py: Python) -> PyObject {
+        self.to_string().into_py(py)
+    }
+}
+
+impl From<Error> for PyErr {
+    fn from(err: Error) -> PyErr {
+        pyo3::exceptions::PyValueError::new_err(err)
+    }
+}
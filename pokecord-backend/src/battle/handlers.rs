@@ -0,0 +1,269 @@
+use std::collections::BTreeSet;
+
+use super::engine::{Battle, BattleMove, BattleStats, Combatant, DamageClass, Event, TypeChart, MAX_MOVES};
+use crate::database::{
+    operations::party_pokemon,
+    records::OwnedPokemon,
+    repository::Repository,
+    Error as DatabaseError,
+};
+use crate::error::Error;
+use crate::models;
+use crate::pokedex::{Move, NamedResource, Nature, PokedexSource, Pokemon, Type};
+
+/// Turns after which a battle is stopped as a draw, in case neither side can do any damage.
+const MAX_TURNS: u32 = 200;
+
+/// PokeAPI name of the move learn method for moves learned by leveling up.
+const LEVEL_UP_METHOD: &str = "level-up";
+
+/// Battles the parties of two players, with each side always using the move expected to deal the most damage. `seed`
+/// decides every random roll, so the same parties and seed always give the same battle.
+pub async fn simulate_battle<R: Repository, P: PokedexSource>(
+    repo: &R,
+    pokedex: &mut P,
+    (first_key, first_player): (&str, &str),
+    (second_key, second_player): (&str, &str),
+    seed: u64,
+) -> Result<models::BattleResult, Error> {
+    let first = battle_party(repo, first_key).await?;
+    let second = battle_party(repo, second_key).await?;
+
+    let mut teams = [Vec::new(), Vec::new()];
+    for (team, party) in teams.iter_mut().zip(&[first, second]) {
+        for pokemon in party {
+            team.push(combatant(pokedex, pokemon).await?);
+        }
+    }
+    let chart = type_chart(pokedex, &teams).await?;
+    let [first_team, second_team] = teams;
+    let mut battle = Battle::new(first_team, second_team, chart, seed);
+
+    let mut events = Vec::new();
+    while !battle.is_over() && battle.turn() < MAX_TURNS {
+        let actions = [battle.best_action(0), battle.best_action(1)];
+        let turn = battle.play_turn(actions)?;
+        events.extend(turn.into_iter().map(|event| battle_event(battle.turn(), event)));
+    }
+
+    let players = [first_player, second_player];
+    Ok(models::BattleResult {
+        seed,
+        turns: battle.turn(),
+        winner: battle.winner().map(|side| players[side].to_string()),
+        events,
+    })
+}
+
+/// The pokemon in the party of the player stored under `key`, or their selected pokemon if their party is empty.
+/// Fails with [`DatabaseError::InvalidParty`] if there are none to battle with.
+async fn battle_party<R: Repository>(repo: &R, key: &str) -> Result<Vec<OwnedPokemon>, DatabaseError> {
+    let player = repo
+        .get_player(key)
+        .await?
+        .ok_or_else(|| DatabaseError::NotRegistered(key.to_string()))?;
+    let ids = if player.party.is_empty() {
+        player.selected.into_iter().collect()
+    } else {
+        player.party
+    };
+    let party = party_pokemon(repo, key, &ids).await?;
+    if party.is_empty() {
+        return Err(DatabaseError::InvalidParty(format!("{} has no pokemon to battle with", key)));
+    }
+    Ok(party)
+}
+
+/// An owned pokemon at full HP, knowing the last moves it would have learned by leveling up.
+async fn combatant<P: PokedexSource>(pokedex: &mut P, pokemon: &OwnedPokemon) -> Result<Combatant, Error> {
+    let variety: Pokemon = pokedex.get_by_id(pokemon.variety_id as usize).await?;
+    let nature: Nature = pokedex.get_by_name(&pokemon.nature).await?;
+    let mut moves = Vec::new();
+    for reference in learned_moves(&variety, pokemon.level) {
+        moves.push(battle_move(&pokedex.get_by_ref(reference).await?));
+    }
+    Ok(Combatant::new(
+        pokemon.nickname.clone().unwrap_or_else(|| pokemon.species_name.clone()),
+        pokemon.level,
+        pokemon.types.clone(),
+        stats(&variety, &nature, pokemon),
+        moves,
+    ))
+}
+
+/// Stats from the variety's base stats and the pokemon's IVs, level and nature, as calculated in the games for
+/// pokemon without EVs.
+fn stats(variety: &Pokemon, nature: &Nature, pokemon: &OwnedPokemon) -> BattleStats {
+    let level = pokemon.level;
+    let scaled = |name: &str, iv: u8| {
+        let base = variety
+            .stats
+            .iter()
+            .find(|stat| stat.stat.name == name)
+            .map_or(0, |stat| stat.base_stat);
+        (2 * base + iv as u32) * level / 100
+    };
+    let raised = nature.increased_stat.as_ref().map(|stat| stat.name.as_str());
+    let lowered = nature.decreased_stat.as_ref().map(|stat| stat.name.as_str());
+    let stat = |name: &str, iv: u8| {
+        let value = scaled(name, iv) + 5;
+        if raised == lowered {
+            value
+        } else if raised == Some(name) {
+            value * 11 / 10
+        } else if lowered == Some(name) {
+            value * 9 / 10
+        } else {
+            value
+        }
+    };
+    let ivs = &pokemon.ivs;
+    BattleStats {
+        hp: scaled("hp", ivs.hp) + level + 10,
+        attack: stat("attack", ivs.attack),
+        defense: stat("defense", ivs.defense),
+        special_attack: stat("special-attack", ivs.special_attack),
+        special_defense: stat("special-defense", ivs.special_defense),
+        speed: stat("speed", ivs.speed),
+    }
+}
+
+/// The moves a pokemon of `variety` knows at `level`: the last [`MAX_MOVES`] it learned by leveling up.
+fn learned_moves(variety: &Pokemon, level: u32) -> Vec<&NamedResource<Move>> {
+    let mut learned: Vec<(u32, &NamedResource<Move>)> = variety
+        .moves
+        .iter()
+        .filter_map(|learnable| {
+            let learned_at = learnable
+                .version_group_details
+                .iter()
+                .filter(|details| details.move_learn_method.name == LEVEL_UP_METHOD)
+                .map(|details| details.level_learned_at)
+                .min()?;
+            if learned_at <= level {
+                Some((learned_at, &learnable.mov))
+            } else {
+                None
+            }
+        })
+        .collect();
+    learned.sort_by_key(|(learned_at, _)| *learned_at);
+    let skip = learned.len().saturating_sub(MAX_MOVES);
+    learned.into_iter().skip(skip).map(|(_, mov)| mov).collect()
+}
+
+fn battle_move(mov: &Move) -> BattleMove {
+    BattleMove {
+        name: mov.name.clone(),
+        type_name: Some(mov.typ.name.clone()),
+        damage_class: match mov.damage_class.as_ref().map(|class| class.name.as_str()) {
+            Some("physical") => DamageClass::Physical,
+            Some("special") => DamageClass::Special,
+            _ => DamageClass::Status,
+        },
+        power: mov.power,
+        accuracy: mov.accuracy,
+        priority: mov.priority,
+        pp: mov.pp.unwrap_or(0),
+        critical_stage: mov.meta.as_ref().map_or(0, |meta| meta.crit_rate),
+    }
+}
+
+/// Effectiveness of every type the teams' moves have, from PokeAPI's damage relations.
+async fn type_chart<P: PokedexSource>(pokedex: &mut P, teams: &[Vec<Combatant>; 2]) -> Result<TypeChart, Error> {
+    let attacking: BTreeSet<&str> = teams
+        .iter()
+        .flatten()
+        .flat_map(|pokemon| &pokemon.moves)
+        .filter_map(|mov| mov.type_name.as_deref())
+        .collect();
+    let mut chart = TypeChart::new();
+    for type_name in attacking {
+        let typ: Type = pokedex.get_by_name(type_name).await?;
+        let relations = &typ.damage_relations;
+        let multipliers = [
+            (&relations.no_damage_to, 0.0),
+            (&relations.half_damage_to, 0.5),
+            (&relations.double_damage_to, 2.0),
+        ];
+        for (defending_types, multiplier) in multipliers.iter() {
+            for defending in defending_types.iter() {
+                chart.insert(type_name, &defending.name, *multiplier);
+            }
+        }
+    }
+    Ok(chart)
+}
+
+fn battle_event(turn: u32, event: Event) -> models::BattleEvent {
+    let base = models::BattleEvent {
+        turn,
+        ..models::BattleEvent::default()
+    };
+    match event {
+        Event::MoveUsed {
+            side,
+            pokemon,
+            move_name,
+        } => models::BattleEvent {
+            kind: "move-used".to_string(),
+            side: Some(side),
+            pokemon: Some(pokemon),
+            move_name: Some(move_name),
+            ..base
+        },
+        Event::Missed { side } => models::BattleEvent {
+            kind: "missed".to_string(),
+            side: Some(side),
+            ..base
+        },
+        Event::NoEffect { side } => models::BattleEvent {
+            kind: "no-effect".to_string(),
+            side: Some(side),
+            ..base
+        },
+        Event::Damaged {
+            side,
+            damage,
+            critical,
+            effectiveness,
+            hp,
+        } => models::BattleEvent {
+            kind: "damaged".to_string(),
+            side: Some(side),
+            damage: Some(damage),
+            critical,
+            effectiveness: Some(effectiveness),
+            hp: Some(hp),
+            ..base
+        },
+        Event::Recoil { side, damage, hp } => models::BattleEvent {
+            kind: "recoil".to_string(),
+            side: Some(side),
+            damage: Some(damage),
+            hp: Some(hp),
+            ..base
+        },
+        Event::Fainted { side, pokemon } => models::BattleEvent {
+            kind: "fainted".to_string(),
+            side: Some(side),
+            pokemon: Some(pokemon),
+            ..base
+        },
+        Event::SwitchedIn { side, pokemon } => models::BattleEvent {
+            kind: "switched-in".to_string(),
+            side: Some(side),
+            pokemon: Some(pokemon),
+            ..base
+        },
+        Event::Won { side } => models::BattleEvent {
+            kind: "won".to_string(),
+            side: Some(side),
+            ..base
+        },
+        Event::Draw => models::BattleEvent {
+            kind: "draw".to_string(),
+            ..base
+        },
+    }
+}
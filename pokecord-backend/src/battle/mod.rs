@@ -0,0 +1,61 @@
+//! The `battle` module contains the turn-based battle engine, and code for
+//! battling players' parties against each other.
+
+use crate::database::{self, MongoRepository};
+use crate::guilds;
+use crate::pokedex::Pokedex;
+use pyo3::prelude::*;
+use pyo3_asyncio::tokio as pytokio;
+use rand::Rng;
+
+pub mod engine;
+mod error;
+mod handlers;
+
+pub use error::Error;
+
+// Adds all required functions into the module.
+pub fn init_submodule(module: &PyModule) -> PyResult<()> {
+    module.add_function(pyo3::wrap_pyfunction!(simulate_battle, module)?)?;
+    Ok(())
+}
+
+/// Battles two players' parties against each other. Each side always uses
+/// the move expected to deal the most damage.
+///
+/// # Arguments
+///
+/// * `seed` - Optional seed for every random roll in the battle. Battling the
+///   same parties with the same seed always plays out the same way. A random
+///   seed is used if none is given.
+///
+/// # Returns
+///
+/// A `BattleResult` with the seed used and everything that happened. Raises
+/// `KeyError` if either player isn't registered and `ValueError` if either
+/// has an empty party.
+#[pyfunction]
+#[text_signature = "(player_id, opponent_id, seed=None, guild_id=None, /)"]
+fn simulate_battle(
+    py: Python,
+    player_id: String,
+    opponent_id: String,
+    seed: Option<u64>,
+    guild_id: Option<String>,
+) -> PyResult<PyObject> {
+    let seed = seed.unwrap_or_else(|| rand::thread_rng().gen());
+    pytokio::into_coroutine(py, async move {
+        let repo = MongoRepository::new(database::get()?);
+        let player_key = guilds::player_key(&repo, guild_id.as_deref(), &player_id).await?;
+        let opponent_key = guilds::player_key(&repo, guild_id.as_deref(), &opponent_id).await?;
+        let result = handlers::simulate_battle(
+            &repo,
+            &mut Pokedex::new(),
+            (&player_key, &player_id),
+            (&opponent_key, &opponent_id),
+            seed,
+        )
+        .await?;
+        Ok(Python::with_gil(|py| result.into_py(py)))
+    })
+}
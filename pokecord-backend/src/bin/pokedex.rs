@@ -0,0 +1,239 @@
+//! Command-line access to PokeAPI data and the `.pokecache`, for debugging without going through the Discord bot.
+//!
+//! Run `pokedex --help` for usage.
+
+use std::{path::PathBuf, process, time::SystemTime};
+
+use serde::Serialize;
+use structopt::StructOpt;
+use url::Url;
+
+use pokecord_backend::pokedex::{
+    cache::Cache,
+    fixtures::{Recorder, ReplayServer},
+    warmup::Target,
+    ApiResource, Error, Generation, GrowthRate, Move, Nature, Pokedex, Pokemon, PokemonForm, PokemonSpecies, Type,
+};
+
+#[derive(Debug, StructOpt)]
+#[structopt(name = "pokedex", about = "Query PokeAPI and maintain the pokecord cache")]
+struct Opt {
+    /// Directory the cache is stored in
+    #[structopt(long, default_value = ".pokecache", parse(from_os_str))]
+    cache_dir: PathBuf,
+    /// Save every API response into this directory as a test fixture
+    #[structopt(long, parse(from_os_str))]
+    record: Option<PathBuf>,
+    /// Send API requests here instead of PokeAPI, e.g. a `replay` server
+    #[structopt(long)]
+    api_base: Option<Url>,
+    #[structopt(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Fetch a resource by name or ID and pretty-print it
+    Get {
+        /// Resource type, e.g. `pokemon-species`
+        resource: Target,
+        /// Resource name or ID
+        name: String,
+    },
+    /// List the names of every resource of a type
+    List {
+        /// Resource type, e.g. `pokemon-species`
+        resource: Target,
+    },
+    /// Inspect and maintain the cache
+    Cache(CacheCommand),
+    /// Serve a directory of recorded fixtures as a local PokeAPI until interrupted
+    Replay {
+        #[structopt(parse(from_os_str))]
+        fixtures: PathBuf,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+enum CacheCommand {
+    /// Show the caching state of an API URL
+    Inspect { url: Url },
+    /// Check that every cache entry is readable and holds valid JSON
+    Verify {
+        /// Remove entries that fail verification
+        #[structopt(long)]
+        fix: bool,
+    },
+    /// Print the cached response body for an API URL
+    Export {
+        url: Url,
+        /// File to write to instead of stdout
+        #[structopt(short, long, parse(from_os_str))]
+        output: Option<PathBuf>,
+    },
+    /// Show the number and total size of cache entries
+    Stats,
+    /// Move entries stored by older versions to the current cache layout
+    Migrate,
+    /// Remove cache entries
+    Purge {
+        /// Only remove entries that would need revalidation
+        #[structopt(long)]
+        stale: bool,
+    },
+}
+
+#[tokio::main]
+async fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+    let opt = Opt::from_args();
+
+    let cache_store = Cache::with_dir(opt.cache_dir);
+    let mut pokedex = Pokedex::with_cache(cache_store.clone());
+    if let Some(dir) = opt.record {
+        pokedex = pokedex.recording(Recorder::new(dir));
+    }
+    if let Some(api_base) = opt.api_base {
+        pokedex = pokedex.with_api_base(api_base);
+    }
+
+    let result = match opt.command {
+        Command::Get { resource, name } => get(pokedex, resource, &name).await,
+        Command::List { resource } => list(pokedex, resource).await,
+        Command::Cache(command) => cache(cache_store, command).await,
+        Command::Replay { fixtures } => replay(fixtures).await,
+    };
+
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
+}
+
+async fn get(mut pokedex: Pokedex, resource: Target, name: &str) -> Result<(), Error> {
+    match resource {
+        Target::Pokemon => print_resource::<Pokemon>(&mut pokedex, name).await,
+        Target::PokemonSpecies => print_resource::<PokemonSpecies>(&mut pokedex, name).await,
+        Target::PokemonForm => print_resource::<PokemonForm>(&mut pokedex, name).await,
+        Target::Type => print_resource::<Type>(&mut pokedex, name).await,
+        Target::GrowthRate => print_resource::<GrowthRate>(&mut pokedex, name).await,
+        Target::Generation => print_resource::<Generation>(&mut pokedex, name).await,
+        Target::Move => print_resource::<Move>(&mut pokedex, name).await,
+        Target::Nature => print_resource::<Nature>(&mut pokedex, name).await,
+    }
+}
+
+async fn print_resource<T: ApiResource>(pokedex: &mut Pokedex, name: &str) -> Result<(), Error> {
+    let value: T = match name.parse() {
+        Ok(id) => pokedex.get_by_id(id).await?,
+        Err(_) => pokedex.get_by_name(name).await?,
+    };
+    print_json(&value)
+}
+
+async fn list(mut pokedex: Pokedex, resource: Target) -> Result<(), Error> {
+    let names: Vec<String> = match resource {
+        Target::Pokemon => pokedex.list::<Pokemon>().await?.into_iter().map(|r| r.name).collect(),
+        Target::PokemonSpecies => pokedex.list::<PokemonSpecies>().await?.into_iter().map(|r| r.name).collect(),
+        Target::PokemonForm => pokedex.list::<PokemonForm>().await?.into_iter().map(|r| r.name).collect(),
+        Target::Type => pokedex.list::<Type>().await?.into_iter().map(|r| r.name).collect(),
+        Target::GrowthRate => pokedex.list::<GrowthRate>().await?.into_iter().map(|r| r.name).collect(),
+        Target::Generation => pokedex.list::<Generation>().await?.into_iter().map(|r| r.name).collect(),
+        Target::Move => pokedex.list::<Move>().await?.into_iter().map(|r| r.name).collect(),
+        Target::Nature => pokedex.list::<Nature>().await?.into_iter().map(|r| r.name).collect(),
+    };
+    for name in names {
+        println!("{}", name);
+    }
+    Ok(())
+}
+
+async fn cache(mut cache: Cache, command: CacheCommand) -> Result<(), Error> {
+    let now = SystemTime::now();
+    match command {
+        CacheCommand::Inspect { url } => {
+            let key = cache.cache_key(&url);
+            println!("key: {}", key);
+            match cache.get(&key).await? {
+                Some(entry) => {
+                    println!("body: {} bytes", entry.body.len());
+                    println!("age: {:?}", entry.cache_policy.age(now));
+                    println!("ttl: {:?}", entry.cache_policy.time_to_live(now));
+                    println!("stale: {}", entry.cache_policy.is_stale(now));
+                }
+                None => println!("not cached"),
+            }
+        }
+        CacheCommand::Verify { fix } => {
+            let mut bad = 0;
+            for key in cache.keys().await? {
+                let problem = match cache.get(&key).await {
+                    Ok(Some(entry)) => serde_json::from_slice::<serde_json::Value>(&entry.body)
+                        .err()
+                        .map(|err| err.to_string()),
+                    Ok(None) => None,
+                    Err(err) => Some(err.to_string()),
+                };
+                if let Some(problem) = problem {
+                    bad += 1;
+                    println!("{}: {}", key, problem);
+                    if fix {
+                        cache.remove(&key).await?;
+                    }
+                }
+            }
+            println!("{} bad entries{}", bad, if fix && bad > 0 { " removed" } else { "" });
+        }
+        CacheCommand::Export { url, output } => {
+            let key = cache.cache_key(&url);
+            let entry = match cache.get(&key).await? {
+                Some(entry) => entry,
+                None => {
+                    eprintln!("{} is not cached", url);
+                    process::exit(1);
+                }
+            };
+            match output {
+                Some(path) => tokio::fs::write(path, &entry.body).await?,
+                None => println!("{}", String::from_utf8_lossy(&entry.body)),
+            }
+        }
+        CacheCommand::Stats => {
+            let (entries, bytes) = cache.disk_usage().await?;
+            println!("{} entries, {} bytes", entries, bytes);
+        }
+        CacheCommand::Migrate => {
+            let migrated = cache.migrate().await?;
+            println!("Migrated {} entries", migrated);
+        }
+        CacheCommand::Purge { stale } => {
+            let mut removed = 0;
+            for key in cache.keys().await? {
+                let remove = !stale
+                    || match cache.get(&key).await {
+                        Ok(Some(entry)) => entry.cache_policy.is_stale(now),
+                        // Unreadable entries are useless, so they count as stale
+                        Ok(None) | Err(_) => true,
+                    };
+                if remove {
+                    cache.remove(&key).await?;
+                    removed += 1;
+                }
+            }
+            println!("Removed {} entries", removed);
+        }
+    }
+    Ok(())
+}
+
+async fn replay(fixtures: PathBuf) -> Result<(), Error> {
+    let server = ReplayServer::start(fixtures).await?;
+    println!("Serving fixtures at {}", server.api_base());
+    tokio::signal::ctrl_c().await?;
+    Ok(())
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<(), Error> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
@@ -0,0 +1,39 @@
+//! Prefetches PokeAPI resources into the `.pokecache` in the working directory, e.g. while building a deployment.
+//!
+//! Usage: `warm_cache [RESOURCE...]`, where each resource is an API endpoint name like `pokemon-species`. With no
+//! arguments, everything gameplay needs is warmed. An interrupted run resumes where it left off. Set `WARMUP_SHARD`
+//! to keep a separate checkpoint when several warm-ups share a cache.
+
+use std::process;
+
+use pokecord_backend::pokedex::{warmup, Pokedex};
+
+#[tokio::main]
+async fn main() {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    let targets = match std::env::args()
+        .skip(1)
+        .map(|arg| arg.parse())
+        .collect::<Result<Vec<warmup::Target>, _>>()
+    {
+        Ok(targets) if targets.is_empty() => warmup::DEFAULT_TARGETS.to_vec(),
+        Ok(targets) => targets,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(2);
+        }
+    };
+
+    let shard = std::env::var("WARMUP_SHARD").ok();
+    let mut pokedex = Pokedex::new();
+    let result = warmup::warm_up(&mut pokedex, &targets, shard.as_deref(), |progress| {
+        println!("{}: {}/{}", progress.target, progress.done, progress.total);
+    })
+    .await;
+
+    if let Err(err) = result {
+        eprintln!("Warm-up failed: {}", err);
+        process::exit(1);
+    }
+}
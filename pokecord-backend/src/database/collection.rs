@@ -0,0 +1,168 @@
+use std::marker::PhantomData;
+
+use futures::stream::TryStreamExt;
+use mongodb::{
+    bson::{self, Document},
+    options::{FindOptions, ReplaceOptions},
+    results::UpdateResult,
+    ClientSession,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::Error;
+
+/// A type stored as the documents of one collection.
+pub trait Record: Serialize + DeserializeOwned + Send + Sync {
+    /// Name of the collection holding this type.
+    const COLLECTION: &'static str;
+}
+
+/// A collection whose documents are all `T`, converting to and from BSON at the edges.
+#[derive(Debug, Clone)]
+pub struct TypedCollection<T: Record> {
+    inner: mongodb::Collection<Document>,
+    _record: PhantomData<fn() -> T>,
+}
+
+impl<T: Record> TypedCollection<T> {
+    pub(super) fn new(inner: mongodb::Collection<Document>) -> TypedCollection<T> {
+        TypedCollection {
+            inner,
+            _record: PhantomData,
+        }
+    }
+
+    /// The underlying untyped collection, for operations this wrapper doesn't cover.
+    pub fn inner(&self) -> &mongodb::Collection<Document> {
+        &self.inner
+    }
+
+    pub async fn find_one(&self, filter: Document) -> Result<Option<T>, Error> {
+        match self.inner.find_one(filter, None).await? {
+            Some(document) => Ok(Some(bson::from_document(document)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn find(&self, filter: Document, options: impl Into<Option<FindOptions>>) -> Result<Vec<T>, Error> {
+        let mut cursor = self.inner.find(filter, options).await?;
+        let mut records = Vec::new();
+        while let Some(document) = cursor.try_next().await? {
+            records.push(bson::from_document(document)?);
+        }
+        Ok(records)
+    }
+
+    pub async fn insert_one(&self, record: &T) -> Result<(), Error> {
+        self.inner.insert_one(bson::to_document(record)?, None).await?;
+        Ok(())
+    }
+
+    /// Replaces the document matching `filter` with `record`, inserting it if there is none.
+    pub async fn upsert(&self, filter: Document, record: &T) -> Result<(), Error> {
+        let options = ReplaceOptions::builder().upsert(true).build();
+        self.inner
+            .replace_one(filter, bson::to_document(record)?, options)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn update_one(&self, filter: Document, update: Document) -> Result<UpdateResult, Error> {
+        Ok(self.inner.update_one(filter, update, None).await?)
+    }
+
+    /// Deletes the document matching `filter`, returning whether there was one.
+    pub async fn delete_one(&self, filter: Document) -> Result<bool, Error> {
+        Ok(self.inner.delete_one(filter, None).await?.deleted_count > 0)
+    }
+
+    /// Deletes the document matching `filter` and returns it, atomically.
+    pub async fn find_one_and_delete(&self, filter: Document) -> Result<Option<T>, Error> {
+        match self.inner.find_one_and_delete(filter, None).await? {
+            Some(document) => Ok(Some(bson::from_document(document)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn count(&self, filter: Document) -> Result<u64, Error> {
+        Ok(self.inner.count_documents(filter, None).await?)
+    }
+
+    pub async fn find_one_with_session(
+        &self,
+        filter: Document,
+        session: &mut ClientSession,
+    ) -> Result<Option<T>, Error> {
+        match self.inner.find_one_with_session(filter, None, session).await? {
+            Some(document) => Ok(Some(bson::from_document(document)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn find_with_session(
+        &self,
+        filter: Document,
+        options: impl Into<Option<FindOptions>>,
+        session: &mut ClientSession,
+    ) -> Result<Vec<T>, Error> {
+        let mut cursor = self.inner.find_with_session(filter, options, session).await?;
+        let mut stream = cursor.stream(session);
+        let mut records = Vec::new();
+        while let Some(document) = stream.try_next().await? {
+            records.push(bson::from_document(document)?);
+        }
+        Ok(records)
+    }
+
+    pub async fn insert_one_with_session(&self, record: &T, session: &mut ClientSession) -> Result<(), Error> {
+        self.inner
+            .insert_one_with_session(bson::to_document(record)?, None, session)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn upsert_with_session(
+        &self,
+        filter: Document,
+        record: &T,
+        session: &mut ClientSession,
+    ) -> Result<(), Error> {
+        let options = ReplaceOptions::builder().upsert(true).build();
+        self.inner
+            .replace_one_with_session(filter, bson::to_document(record)?, options, session)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn update_one_with_session(
+        &self,
+        filter: Document,
+        update: Document,
+        session: &mut ClientSession,
+    ) -> Result<UpdateResult, Error> {
+        Ok(self
+            .inner
+            .update_one_with_session(filter, update, None, session)
+            .await?)
+    }
+
+    pub async fn delete_one_with_session(&self, filter: Document, session: &mut ClientSession) -> Result<bool, Error> {
+        let result = self.inner.delete_one_with_session(filter, None, session).await?;
+        Ok(result.deleted_count > 0)
+    }
+
+    pub async fn find_one_and_delete_with_session(
+        &self,
+        filter: Document,
+        session: &mut ClientSession,
+    ) -> Result<Option<T>, Error> {
+        match self
+            .inner
+            .find_one_and_delete_with_session(filter, None, session)
+            .await?
+        {
+            Some(document) => Ok(Some(bson::from_document(document)?)),
+            None => Ok(None),
+        }
+    }
+}
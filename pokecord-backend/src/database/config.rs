@@ -0,0 +1,51 @@
+use std::{env, time::Duration};
+
+use super::Error;
+
+const DEFAULT_URI: &str = "mongodb://localhost:27017";
+/// Matches `MONGO_INITDB_DATABASE` in docker-compose.
+const DEFAULT_DATABASE: &str = "master";
+const DEFAULT_TIMEOUT_SECS: u64 = 5;
+
+/// How to connect to MongoDB.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// MongoDB connection string. Credentials may be given here or separately.
+    pub uri: String,
+    /// Name of the database holding all pokecord collections.
+    pub database: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// How long to look for a reachable server before giving up on a request, including the startup health check.
+    pub timeout: Duration,
+}
+
+impl Config {
+    /// Reads the configuration from the environment:
+    ///
+    /// * `POKECORD_DB_URI` - connection string, defaulting to a local server
+    /// * `POKECORD_DB_NAME` - database name, defaulting to `master`
+    /// * `POKECORD_DB_USERNAME` and `POKECORD_DB_PASSWORD` - credentials, if not in the URI
+    /// * `POKECORD_DB_TIMEOUT_SECS` - server selection timeout, defaulting to 5 seconds
+    pub fn from_env() -> Result<Config, Error> {
+        let timeout = match env::var("POKECORD_DB_TIMEOUT_SECS") {
+            Ok(secs) => secs
+                .parse()
+                .map_err(|_| Error::InvalidConfig(format!("POKECORD_DB_TIMEOUT_SECS is not a number: {}", secs)))?,
+            Err(_) => DEFAULT_TIMEOUT_SECS,
+        };
+        let config = Config {
+            uri: env::var("POKECORD_DB_URI").unwrap_or_else(|_| DEFAULT_URI.to_string()),
+            database: env::var("POKECORD_DB_NAME").unwrap_or_else(|_| DEFAULT_DATABASE.to_string()),
+            username: env::var("POKECORD_DB_USERNAME").ok(),
+            password: env::var("POKECORD_DB_PASSWORD").ok(),
+            timeout: Duration::from_secs(timeout),
+        };
+        if config.password.is_some() && config.username.is_none() {
+            return Err(Error::InvalidConfig(
+                "POKECORD_DB_PASSWORD is set without POKECORD_DB_USERNAME".to_string(),
+            ));
+        }
+        Ok(config)
+    }
+}
@@ -0,0 +1,116 @@
+use mongodb::error::{ErrorKind, WriteFailure};
+use pyo3::prelude::*;
+use pyo3::PyErrArguments;
+
+/// Server error code for creating a collection that already exists.
+pub const NAMESPACE_EXISTS: i32 = 48;
+/// Server error code for a unique index violation.
+pub const DUPLICATE_KEY: i32 = 11000;
+/// Server error code for a transaction writing a document another transaction has written since it started.
+pub const WRITE_CONFLICT: i32 = 112;
+/// Label the server and driver put on errors that abort a transaction but that retrying the whole transaction can fix.
+const TRANSIENT_TRANSACTION_ERROR: &str = "TransientTransactionError";
+
+/// Database error
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error("MongoDB request failed")]
+    Mongo(#[source] mongodb::error::Error),
+    #[error("Could not reach MongoDB at {host}")]
+    Unreachable {
+        host: String,
+        #[source]
+        source: mongodb::error::Error,
+    },
+    #[error("Database is not connected, connect() must be awaited first")]
+    NotConnected,
+    #[error("Invalid database configuration: {0}")]
+    InvalidConfig(String),
+    #[error("{0} already exists")]
+    AlreadyExists(String),
+    #[error("{0} is not registered")]
+    NotRegistered(String),
+    #[error("{0} is not a valid ID")]
+    InvalidId(String),
+    #[error("Invalid setting: {0}")]
+    InvalidSetting(String),
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+    #[error("Invalid party: {0}")]
+    InvalidParty(String),
+    #[error("Invalid purchase: {0}")]
+    InvalidPurchase(String),
+    #[error("{0} is not in the inventory")]
+    MissingItem(String),
+    #[error("Cannot evolve: {0}")]
+    CannotEvolve(String),
+    #[error("{owner} does not own pokemon {pokemon}")]
+    NotOwned { owner: String, pokemon: String },
+    #[error("Costs {cost} but the balance is only {balance}")]
+    InsufficientFunds { balance: i64, cost: i64 },
+    #[error("A concurrent transaction changed the same documents")]
+    WriteConflict,
+    #[error("Migration failed: {0}")]
+    Migration(String),
+    #[error("Document serialization failed")]
+    Serialization(#[from] mongodb::bson::ser::Error),
+    #[error("Document deserialization failed")]
+    Deserialization(#[from] mongodb::bson::de::Error),
+}
+
+/// Checking MongoDB errors for specific server error codes.
+pub trait ServerErrorExt {
+    /// Whether this is an error the server reported with `code`.
+    fn is_server_error(&self, code: i32) -> bool;
+
+    /// Whether this error aborted a transaction that can succeed if it's run again, usually because a concurrent
+    /// transaction wrote the same documents.
+    fn is_transient_transaction_error(&self) -> bool;
+}
+
+impl ServerErrorExt for mongodb::error::Error {
+    fn is_server_error(&self, code: i32) -> bool {
+        match self.kind.as_ref() {
+            ErrorKind::Command(err) => err.code == code,
+            ErrorKind::Write(WriteFailure::WriteError(err)) => err.code == code,
+            _ => false,
+        }
+    }
+
+    fn is_transient_transaction_error(&self) -> bool {
+        self.is_server_error(WRITE_CONFLICT) || self.contains_label(TRANSIENT_TRANSACTION_ERROR)
+    }
+}
+
+impl From<mongodb::error::Error> for Error {
+    fn from(err: mongodb::error::Error) -> Error {
+        if err.is_transient_transaction_error() {
+            Error::WriteConflict
+        } else {
+            Error::Mongo(err)
+        }
+    }
+}
+
+impl PyErrArguments for Error {
+    fn arguments(self, py: Python) -> PyObject {
+        self.to_string().into_py(py)
+    }
+}
+
+impl From<Error> for PyErr {
+    fn from(err: Error) -> PyErr {
+        match err {
+            Error::NotRegistered(_) => pyo3::exceptions::PyKeyError::new_err(err),
+            Error::InvalidSetting(_)
+            | Error::InvalidQuery(_)
+            | Error::InvalidId(_)
+            | Error::InvalidParty(_)
+            | Error::InvalidPurchase(_)
+            | Error::MissingItem(_)
+            | Error::CannotEvolve(_)
+            | Error::NotOwned { .. } => pyo3::exceptions::PyValueError::new_err(err),
+            err => crate::DatabaseError::new_err(err),
+        }
+    }
+}
@@ -0,0 +1,280 @@
+//! Versioned schema changes and index management.
+//!
+//! Each [`Migration`] runs once per database. Applied migrations are recorded in the `migrations` collection, and a
+//! migration is claimed before it runs so that several bot processes starting together don't run it twice. Processes
+//! that find a migration claimed wait for it to finish, and take over claims older than [`CLAIM_TIMEOUT`], which were
+//! left behind by a process that died mid-migration. Indexes are declared separately in [`indexes()`] and created on
+//! every startup, which MongoDB treats as a no-op when they already exist.
+
+use std::time::Duration;
+
+use futures::future::BoxFuture;
+use mongodb::bson::{doc, Bson, DateTime, Document};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    records::{GuildSettings, Inventory, OwnedPokemon, Player, Spawn},
+    error::{ServerErrorExt, DUPLICATE_KEY, NAMESPACE_EXISTS},
+    Database, Error, Record,
+};
+use crate::pokedex::{self, Pokedex, Pokemon, PokemonSpecies};
+
+/// How long a migration may run before other processes assume its process died and take it over.
+const CLAIM_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// How often to check whether a migration claimed by another process has finished.
+const CLAIM_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// A schema change.
+struct Migration {
+    /// Migrations run in increasing version order. Versions must never be reused.
+    version: i32,
+    name: &'static str,
+    run: fn(&mongodb::Database) -> BoxFuture<'_, Result<(), Error>>,
+}
+
+/// Every migration, oldest first.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "create-collections",
+        run: create_collections,
+    },
+    Migration {
+        version: 2,
+        name: "create-guilds",
+        run: create_guilds,
+    },
+    Migration {
+        version: 3,
+        name: "backfill-pokemon-types",
+        run: backfill_pokemon_types,
+    },
+];
+
+/// An index that must exist.
+struct Index {
+    collection: &'static str,
+    name: &'static str,
+    keys: Document,
+    unique: bool,
+}
+
+/// Every index. Players are keyed by Discord ID through `_id`, which MongoDB always indexes uniquely.
+fn indexes() -> Vec<Index> {
+    vec![
+        Index {
+            collection: OwnedPokemon::COLLECTION,
+            name: "owner_caught_at",
+            keys: doc! { "owner": 1, "caught_at": 1 },
+            unique: false,
+        },
+        Index {
+            collection: Spawn::COLLECTION,
+            name: "guild_id",
+            keys: doc! { "guild_id": 1 },
+            unique: false,
+        },
+    ]
+}
+
+/// An applied, or currently running, migration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AppliedMigration {
+    #[serde(rename = "_id")]
+    version: i32,
+    name: String,
+    /// When the migration was claimed. Only missing from claims made before claims could expire.
+    #[serde(default)]
+    claimed_at: Option<DateTime>,
+    /// When the migration finished, or `None` while it is running.
+    applied_at: Option<DateTime>,
+}
+
+impl AppliedMigration {
+    /// Whether this is a claim that has been running for so long that its process must have died.
+    fn is_stale_claim(&self) -> bool {
+        if self.applied_at.is_some() {
+            return false;
+        }
+        match self.claimed_at {
+            Some(claimed_at) => (chrono::Utc::now() - claimed_at.to_chrono())
+                .to_std()
+                .is_ok_and(|age| age > CLAIM_TIMEOUT),
+            None => true,
+        }
+    }
+}
+
+impl Record for AppliedMigration {
+    const COLLECTION: &'static str = "migrations";
+}
+
+/// What [`claim`] found.
+enum Claim {
+    /// This process claimed the migration and must run it.
+    Claimed(AppliedMigration),
+    /// The migration has been applied, by this process or another.
+    Applied,
+}
+
+/// Runs every migration that hasn't been applied yet, returning how many ran.
+pub async fn run_pending(db: &Database) -> Result<usize, Error> {
+    let applied = db.collection::<AppliedMigration>();
+    let mut ran = 0;
+    for migration in MIGRATIONS {
+        let claim = match claim(db, migration).await? {
+            Claim::Claimed(claim) => claim,
+            Claim::Applied => continue,
+        };
+
+        log::info!("Running migration {} ({})", migration.version, migration.name);
+        if let Err(err) = (migration.run)(db.inner()).await {
+            // Release the claim so the migration is retried on the next startup
+            applied.delete_one(doc! { "_id": migration.version }).await?;
+            return Err(err);
+        }
+        let done = AppliedMigration {
+            applied_at: Some(chrono::Utc::now().into()),
+            ..claim
+        };
+        applied.upsert(doc! { "_id": migration.version }, &done).await?;
+        ran += 1;
+    }
+    Ok(ran)
+}
+
+/// Claims `migration` for this process, waiting while another process runs it.
+async fn claim(db: &Database, migration: &Migration) -> Result<Claim, Error> {
+    let applied = db.collection::<AppliedMigration>();
+    let mut waiting = false;
+    loop {
+        let claim = AppliedMigration {
+            version: migration.version,
+            name: migration.name.to_string(),
+            claimed_at: Some(chrono::Utc::now().into()),
+            applied_at: None,
+        };
+        match applied.insert_one(&claim).await {
+            Ok(()) => return Ok(Claim::Claimed(claim)),
+            Err(Error::Mongo(err)) if err.is_server_error(DUPLICATE_KEY) => {}
+            Err(err) => return Err(err),
+        }
+
+        let existing = match applied.find_one(doc! { "_id": migration.version }).await? {
+            Some(existing) => existing,
+            // The other claim was released after a failure, so try to claim it again
+            None => continue,
+        };
+        if existing.applied_at.is_some() {
+            return Ok(Claim::Applied);
+        }
+        if existing.is_stale_claim() {
+            log::warn!(
+                "Migration {} ({}) was claimed but never finished, running it again",
+                migration.version,
+                migration.name
+            );
+            // Only release the claim we judged stale, in case another process already took it over. Null also
+            // matches claims made before claims could expire, which have no `claimed_at` at all.
+            let claimed_at = match existing.claimed_at {
+                Some(claimed_at) => Bson::DateTime(claimed_at),
+                None => Bson::Null,
+            };
+            applied
+                .delete_one(doc! { "_id": migration.version, "claimed_at": claimed_at, "applied_at": null })
+                .await?;
+            continue;
+        }
+
+        if !waiting {
+            log::info!(
+                "Waiting for another process to finish migration {} ({})",
+                migration.version,
+                migration.name
+            );
+            waiting = true;
+        }
+        tokio::time::sleep(CLAIM_POLL_INTERVAL).await;
+    }
+}
+
+/// Creates every index that doesn't exist yet.
+pub async fn ensure_indexes(db: &Database) -> Result<(), Error> {
+    let indexes = indexes();
+    for index in &indexes {
+        let command = doc! {
+            "createIndexes": index.collection,
+            "indexes": [{ "key": index.keys.clone(), "name": index.name, "unique": index.unique }],
+        };
+        db.inner().run_command(command, None).await?;
+    }
+    log::debug!("Ensured {} indexes", indexes.len());
+    Ok(())
+}
+
+/// Creates `name` unless it already exists.
+async fn create_collection(db: &mongodb::Database, name: &str) -> Result<(), Error> {
+    match db.create_collection(name, None).await {
+        Err(err) if err.is_server_error(NAMESPACE_EXISTS) => Ok(()),
+        result => Ok(result?),
+    }
+}
+
+/// Creates the game collections explicitly, since MongoDB before 4.4 can't create them implicitly inside the
+/// transactions that later write to them.
+fn create_collections(db: &mongodb::Database) -> BoxFuture<'_, Result<(), Error>> {
+    Box::pin(async move {
+        let names = [
+            Player::COLLECTION,
+            OwnedPokemon::COLLECTION,
+            Inventory::COLLECTION,
+            Spawn::COLLECTION,
+        ];
+        for name in names.iter().copied() {
+            create_collection(db, name).await?;
+        }
+        Ok(())
+    })
+}
+
+fn create_guilds(db: &mongodb::Database) -> BoxFuture<'_, Result<(), Error>> {
+    Box::pin(create_collection(db, GuildSettings::COLLECTION))
+}
+
+/// Copies types and legendary status from PokeAPI onto pokemon caught before they were stored, so everything reading
+/// them can rely on them being there.
+fn backfill_pokemon_types(db: &mongodb::Database) -> BoxFuture<'_, Result<(), Error>> {
+    Box::pin(async move {
+        let pokemon = db.collection::<Document>(OwnedPokemon::COLLECTION);
+        let untyped = doc! { "$or": [{ "types": { "$exists": false } }, { "types": { "$size": 0 } }] };
+        let varieties = pokemon.distinct("variety_id", untyped.clone(), None).await?;
+        log::info!("Backfilling types of {} pokemon varieties", varieties.len());
+
+        let mut pokedex = Pokedex::new();
+        for variety in varieties {
+            let id = match variety {
+                Bson::Int32(id) => id as usize,
+                Bson::Int64(id) => id as usize,
+                ref other => return Err(Error::Migration(format!("{} is not a variety ID", other))),
+            };
+            let (types, legendary) = variety_types(&mut pokedex, id)
+                .await
+                .map_err(|err| Error::Migration(format!("couldn't look up variety {}: {}", id, err)))?;
+            let mut filter = untyped.clone();
+            filter.insert("variety_id", variety);
+            pokemon
+                .update_many(filter, doc! { "$set": { "types": types, "legendary": legendary } }, None)
+                .await?;
+        }
+        Ok(())
+    })
+}
+
+/// The types of a pokemon variety, and whether its species is legendary or mythical.
+async fn variety_types(pokedex: &mut Pokedex, id: usize) -> Result<(Vec<String>, bool), pokedex::Error> {
+    let variety: Pokemon = pokedex.get_by_id(id).await?;
+    let species: PokemonSpecies = pokedex.get_by_ref(&variety.species).await?;
+    let types = variety.types.iter().map(|t| t.typ.name.clone()).collect();
+    Ok((types, species.is_legendary || species.is_mythical))
+}
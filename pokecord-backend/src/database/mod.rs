@@ -0,0 +1,135 @@
+//! Persistent game state in MongoDB.
+//!
+//! A single [`Database`] is connected when the Python module is imported and shared by everything in the process. The
+//! MongoDB client pools its own connections, so sharing one handle is all the pooling needed.
+
+use std::time::{Duration, Instant};
+
+use mongodb::{
+    bson::{doc, oid::ObjectId},
+    options::ClientOptions,
+    Client,
+};
+use once_cell::sync::OnceCell;
+
+mod collection;
+mod config;
+mod error;
+mod migrations;
+pub mod operations;
+pub mod query;
+pub mod records;
+pub mod repository;
+#[cfg(test)]
+pub mod test_data;
+
+pub use collection::{Record, TypedCollection};
+pub use config::Config;
+pub use error::Error;
+pub use repository::{MongoRepository, Repository, Transactional, UnitOfWork};
+
+/// The process-wide database, set by [`init`].
+static DATABASE: OnceCell<Database> = OnceCell::new();
+
+/// A connected pokecord database.
+#[derive(Debug, Clone)]
+pub struct Database {
+    client: Client,
+    database: mongodb::Database,
+}
+
+/// Result of a [`Database::health_check`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Health {
+    /// Round trip time of a `ping` command.
+    pub latency: Duration,
+}
+
+impl Database {
+    /// Connects to MongoDB and checks that it is reachable, so misconfiguration shows up immediately rather than on
+    /// the first player command.
+    pub async fn connect(config: &Config) -> Result<Database, Error> {
+        let mut options = ClientOptions::parse(&config.uri).await?;
+        options.app_name = Some("pokecord".to_string());
+        options.server_selection_timeout = Some(config.timeout);
+        if let Some(username) = &config.username {
+            let mut credential = options.credential.take().unwrap_or_default();
+            credential.username = Some(username.clone());
+            credential.password = config.password.clone();
+            options.credential = Some(credential);
+        }
+        let host = options
+            .hosts
+            .iter()
+            .map(|host| host.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let client = Client::with_options(options)?;
+        let db = Database {
+            database: client.database(&config.database),
+            client,
+        };
+        let health = db
+            .health_check()
+            .await
+            .map_err(|err| match err {
+                Error::Mongo(source) => Error::Unreachable { host, source },
+                err => err,
+            })?;
+        log::info!(
+            "Connected to database {} ({}ms)",
+            config.database,
+            health.latency.as_millis()
+        );
+        Ok(db)
+    }
+
+    /// The collection holding `T`.
+    pub fn collection<T: Record>(&self) -> TypedCollection<T> {
+        TypedCollection::new(self.database.collection(T::COLLECTION))
+    }
+
+    /// Pings the server.
+    pub async fn health_check(&self) -> Result<Health, Error> {
+        let start = Instant::now();
+        self.database.run_command(doc! { "ping": 1 }, None).await?;
+        Ok(Health {
+            latency: start.elapsed(),
+        })
+    }
+
+    /// The underlying database, for operations without a typed wrapper.
+    pub fn inner(&self) -> &mongodb::Database {
+        &self.database
+    }
+
+    /// The underlying client, e.g. for starting sessions.
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+}
+
+/// Connects the process-wide database and brings its schema up to date. Later calls keep the first connection.
+pub async fn init(config: &Config) -> Result<&'static Database, Error> {
+    if let Some(db) = DATABASE.get() {
+        return Ok(db);
+    }
+    let db = Database::connect(config).await?;
+    let ran = migrations::run_pending(&db).await?;
+    if ran > 0 {
+        log::info!("Applied {} database migrations", ran);
+    }
+    migrations::ensure_indexes(&db).await?;
+    Ok(DATABASE.get_or_init(|| db))
+}
+
+/// The process-wide database.
+pub fn get() -> Result<&'static Database, Error> {
+    DATABASE.get().ok_or(Error::NotConnected)
+}
+
+/// Parses a document ID given to a command, e.g. a pokemon ID.
+pub fn parse_id(id: &str) -> Result<ObjectId, Error> {
+    ObjectId::parse_str(id).map_err(|_| Error::InvalidId(id.to_string()))
+}
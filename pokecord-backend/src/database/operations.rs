@@ -0,0 +1,214 @@
+//! Game operations that change several documents, each run as one [`UnitOfWork`]. Operations that lose a race with a
+//! concurrent transaction are retried a few times before failing with [`Error::WriteConflict`].
+
+use std::future::Future;
+
+use mongodb::bson::oid::ObjectId;
+
+use super::{
+    records::OwnedPokemon,
+    repository::{InventoryRepository, PlayerRepository, PokemonRepository, Transactional, UnitOfWork},
+    Error,
+};
+
+/// How many times an operation is attempted while concurrent transactions keep conflicting with it.
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Runs `op`, running it again from the start if it fails with [`Error::WriteConflict`], up to [`MAX_ATTEMPTS`] times.
+pub async fn retry_on_conflict<T, F, Fut>(mut op: F) -> Result<T, Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    let mut attempt = 1;
+    loop {
+        match op().await {
+            Err(Error::WriteConflict) if attempt < MAX_ATTEMPTS => {
+                log::debug!("Write conflict on attempt {}, retrying", attempt);
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Swaps the owners of two pokemon. Fails with [`Error::NotOwned`] if either player no longer owns the pokemon they
+/// offered, e.g. because they released it while the trade was pending. Traded pokemon leave their old owner's party.
+pub async fn trade_pokemon<R: Transactional>(
+    repo: &R,
+    (first_owner, first_pokemon): (&str, ObjectId),
+    (second_owner, second_pokemon): (&str, ObjectId),
+) -> Result<(), Error> {
+    retry_on_conflict(|| try_trade_pokemon(repo, (first_owner, first_pokemon), (second_owner, second_pokemon))).await
+}
+
+async fn try_trade_pokemon<R: Transactional>(
+    repo: &R,
+    (first_owner, first_pokemon): (&str, ObjectId),
+    (second_owner, second_pokemon): (&str, ObjectId),
+) -> Result<(), Error> {
+    let work = repo.begin().await?;
+    let mut first = owned_pokemon(&work, first_owner, first_pokemon).await?;
+    let mut second = owned_pokemon(&work, second_owner, second_pokemon).await?;
+    first.owner = second_owner.to_string();
+    second.owner = first_owner.to_string();
+    work.update_pokemon(&first).await?;
+    work.update_pokemon(&second).await?;
+    leave_party(&work, first_owner, first_pokemon).await?;
+    leave_party(&work, second_owner, second_pokemon).await?;
+    work.commit().await
+}
+
+/// Takes a pokemon out of its owner's party, if it's in it.
+async fn leave_party<R: PlayerRepository>(repo: &R, owner: &str, id: ObjectId) -> Result<(), Error> {
+    if let Some(mut player) = repo.get_player(owner).await? {
+        if player.party.contains(&id) {
+            player.party.retain(|member| *member != id);
+            repo.update_player(&player).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Buys `count` of an item at `unit_price` each. Fails with [`Error::InsufficientFunds`] if the player can't afford it,
+/// and with [`Error::InvalidPurchase`] unless both the count and price are positive.
+pub async fn purchase_item<R: Transactional>(
+    repo: &R,
+    owner: &str,
+    item: &str,
+    count: u32,
+    unit_price: i64,
+) -> Result<(), Error> {
+    if count == 0 {
+        return Err(Error::InvalidPurchase("must buy at least one item".to_string()));
+    }
+    if unit_price <= 0 {
+        return Err(Error::InvalidPurchase(format!("{} is not a valid price", unit_price)));
+    }
+    let cost = unit_price
+        .checked_mul(i64::from(count))
+        .ok_or_else(|| Error::InvalidPurchase(format!("{} of {} cost too much", count, item)))?;
+    retry_on_conflict(|| try_purchase_item(repo, owner, item, count, cost)).await
+}
+
+async fn try_purchase_item<R: Transactional>(
+    repo: &R,
+    owner: &str,
+    item: &str,
+    count: u32,
+    cost: i64,
+) -> Result<(), Error> {
+    let work = repo.begin().await?;
+    let mut inventory = work.get_inventory(owner).await?;
+    if inventory.currency < cost {
+        return Err(Error::InsufficientFunds {
+            balance: inventory.currency,
+            cost,
+        });
+    }
+    let held = inventory.items.entry(item.to_string()).or_insert(0);
+    *held = held
+        .checked_add(count)
+        .ok_or_else(|| Error::InvalidPurchase(format!("can't hold that many {}", item)))?;
+    inventory.currency -= cost;
+    work.save_inventory(&inventory).await?;
+    work.commit().await
+}
+
+/// A pokemon belonging to `owner`, failing with [`Error::NotOwned`] if it belongs to someone else or doesn't exist.
+pub async fn owned_pokemon<R: PokemonRepository>(repo: &R, owner: &str, id: ObjectId) -> Result<OwnedPokemon, Error> {
+    match repo.get_pokemon(id).await? {
+        Some(pokemon) if pokemon.owner == owner => Ok(pokemon),
+        _ => Err(Error::NotOwned {
+            owner: owner.to_string(),
+            pokemon: id.to_hex(),
+        }),
+    }
+}
+
+/// The pokemon in `party` that `owner` still owns, in party order.
+pub async fn party_pokemon<R: PokemonRepository>(
+    repo: &R,
+    owner: &str,
+    party: &[ObjectId],
+) -> Result<Vec<OwnedPokemon>, Error> {
+    let mut pokemon = Vec::with_capacity(party.len());
+    for &id in party {
+        match repo.get_pokemon(id).await? {
+            Some(member) if member.owner == owner => pokemon.push(member),
+            _ => {}
+        }
+    }
+    Ok(pokemon)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::database::test_data::repo_with_currency;
+
+    #[tokio::test]
+    async fn purchases_are_paid_for() {
+        let repo = repo_with_currency(1000).await;
+        purchase_item(&repo, "ash", "poke-ball", 3, 200).await.unwrap();
+        purchase_item(&repo, "ash", "poke-ball", 1, 200).await.unwrap();
+
+        let inventory = repo.get_inventory("ash").await.unwrap();
+        assert_eq!(inventory.currency, 200);
+        assert_eq!(inventory.items["poke-ball"], 4);
+    }
+
+    #[tokio::test]
+    async fn purchases_need_enough_currency() {
+        let repo = repo_with_currency(100).await;
+        let result = purchase_item(&repo, "ash", "poke-ball", 1, 200).await;
+        assert!(matches!(result, Err(Error::InsufficientFunds { balance: 100, cost: 200 })));
+        assert!(repo.get_inventory("ash").await.unwrap().items.is_empty());
+    }
+
+    #[tokio::test]
+    async fn invalid_purchases_are_rejected() {
+        let repo = repo_with_currency(1000).await;
+        for &(count, unit_price) in &[(0, 200), (1, 0), (1, -200), (2, i64::MAX)] {
+            let result = purchase_item(&repo, "ash", "poke-ball", count, unit_price).await;
+            assert!(
+                matches!(result, Err(Error::InvalidPurchase(_))),
+                "{} at {} was allowed",
+                count,
+                unit_price
+            );
+        }
+        assert_eq!(repo.get_inventory("ash").await.unwrap().currency, 1000);
+    }
+
+    #[tokio::test]
+    async fn conflicts_are_retried() {
+        let attempts = Cell::new(0);
+        let result = retry_on_conflict(|| {
+            let attempt = attempts.get() + 1;
+            attempts.set(attempt);
+            async move {
+                match attempt {
+                    1 => Err(Error::WriteConflict),
+                    attempt => Ok(attempt),
+                }
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn conflicts_are_only_retried_a_few_times() {
+        let attempts = Cell::new(0);
+        let result: Result<(), _> = retry_on_conflict(|| {
+            attempts.set(attempts.get() + 1);
+            async { Err(Error::WriteConflict) }
+        })
+        .await;
+        assert!(matches!(result, Err(Error::WriteConflict)));
+        assert_eq!(attempts.get(), MAX_ATTEMPTS);
+    }
+}
@@ -0,0 +1,526 @@
+//! Filtering, sorting and cursor-based pagination over a player's pokemon.
+//!
+//! A cursor is the position of the last pokemon on a page: its value of the sort key, and its ID to break ties. The
+//! next page starts strictly after that position, so pages stay consistent while pokemon are caught or released.
+//! Cursors also name the sort order they were made for, since a position means nothing in any other order.
+
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use mongodb::bson::oid::ObjectId;
+
+use super::{
+    records::{OwnedPokemon, MAX_IV},
+    Error,
+};
+
+/// Most pokemon returned in one page.
+pub const MAX_PAGE_SIZE: usize = 100;
+
+/// What to sort pokemon by. Every key is an integer, which keeps cursors simple.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortKey {
+    Level,
+    /// Sum of IVs.
+    Iv,
+    /// National dex number.
+    DexNumber,
+    #[default]
+    CatchDate,
+}
+
+impl SortKey {
+    /// The stored field this key sorts by. [`SortKey::Iv`] sorts by a field computed while querying.
+    pub fn field(self) -> &'static str {
+        match self {
+            SortKey::Level => "level",
+            SortKey::Iv => "iv_total",
+            SortKey::DexNumber => "species_id",
+            SortKey::CatchDate => "caught_at",
+        }
+    }
+
+    /// The name this key is parsed from.
+    pub fn name(self) -> &'static str {
+        match self {
+            SortKey::Level => "level",
+            SortKey::Iv => "iv",
+            SortKey::DexNumber => "dex-number",
+            SortKey::CatchDate => "catch-date",
+        }
+    }
+
+    /// The value of this key for `pokemon`. Catch dates are in milliseconds since the Unix epoch.
+    pub fn value(self, pokemon: &OwnedPokemon) -> i64 {
+        match self {
+            SortKey::Level => pokemon.level as i64,
+            SortKey::Iv => pokemon.ivs.total() as i64,
+            SortKey::DexNumber => pokemon.species_id as i64,
+            SortKey::CatchDate => pokemon.caught_at.timestamp_millis(),
+        }
+    }
+}
+
+impl FromStr for SortKey {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<SortKey, Error> {
+        match s {
+            "level" => Ok(SortKey::Level),
+            "iv" => Ok(SortKey::Iv),
+            "dex-number" => Ok(SortKey::DexNumber),
+            "catch-date" => Ok(SortKey::CatchDate),
+            _ => Err(Error::InvalidQuery(format!(
+                "unknown sort `{}`, expected level, iv, dex-number or catch-date",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for SortKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Which pokemon to include. Every condition that is set must match.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PokemonFilter {
+    /// API name of the species.
+    pub species: Option<String>,
+    /// API name of a type the pokemon must have.
+    pub type_name: Option<String>,
+    pub shiny: Option<bool>,
+    pub legendary: Option<bool>,
+    pub min_level: Option<u32>,
+    pub max_level: Option<u32>,
+    /// Text the nickname must contain, ignoring case.
+    pub nickname: Option<String>,
+    /// Lowest IV percentage, from 0 to 100.
+    pub min_iv: Option<f64>,
+    /// Highest IV percentage, from 0 to 100.
+    pub max_iv: Option<f64>,
+}
+
+impl PokemonFilter {
+    /// The lowest IV total matching `min_iv`.
+    pub fn min_iv_total(&self) -> Option<u32> {
+        self.min_iv.map(|p| (p * max_iv_total() / 100.0).ceil() as u32)
+    }
+
+    /// The highest IV total matching `max_iv`.
+    pub fn max_iv_total(&self) -> Option<u32> {
+        self.max_iv.map(|p| (p * max_iv_total() / 100.0).floor() as u32)
+    }
+
+    pub fn matches(&self, pokemon: &OwnedPokemon) -> bool {
+        let iv_total = pokemon.ivs.total();
+        allows(&self.species, |s| *s == pokemon.species_name)
+            && allows(&self.type_name, |t| pokemon.types.contains(t))
+            && allows(&self.shiny, |&s| s == pokemon.shiny)
+            && allows(&self.legendary, |&l| l == pokemon.legendary)
+            && allows(&self.min_level, |&l| pokemon.level >= l)
+            && allows(&self.max_level, |&l| pokemon.level <= l)
+            && allows(&self.nickname, |n| match &pokemon.nickname {
+                Some(nickname) => nickname.to_lowercase().contains(&n.to_lowercase()),
+                None => false,
+            })
+            && allows(&self.min_iv_total(), |&min| iv_total >= min)
+            && allows(&self.max_iv_total(), |&max| iv_total <= max)
+    }
+}
+
+/// Whether an optional condition is unset or `test` passes.
+fn allows<T>(condition: &Option<T>, test: impl FnOnce(&T) -> bool) -> bool {
+    match condition {
+        Some(value) => test(value),
+        None => true,
+    }
+}
+
+fn max_iv_total() -> f64 {
+    (6 * MAX_IV as u32) as f64
+}
+
+/// A page request.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PokemonQuery {
+    pub filter: PokemonFilter,
+    pub sort: SortKey,
+    pub descending: bool,
+    /// Where the previous page ended, or `None` for the first page.
+    pub after: Option<Cursor>,
+    /// Page size, at most [`MAX_PAGE_SIZE`].
+    pub limit: usize,
+}
+
+impl PokemonQuery {
+    /// Fails with [`Error::InvalidQuery`] if the cursor was made for a different sort order than this query's.
+    pub fn validate(&self) -> Result<(), Error> {
+        match &self.after {
+            Some(cursor) if (cursor.sort, cursor.descending) != (self.sort, self.descending) => {
+                Err(Error::InvalidQuery(format!(
+                    "cursor `{}` is for a different sort order than `{}{}`",
+                    cursor,
+                    if self.descending { "-" } else { "" },
+                    self.sort
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The position of `pokemon` in this query's sort order.
+    pub fn cursor_at(&self, pokemon: &OwnedPokemon) -> Cursor {
+        Cursor {
+            sort: self.sort,
+            descending: self.descending,
+            value: self.sort.value(pokemon),
+            id: pokemon.id,
+        }
+    }
+
+    /// Whether `pokemon` sorts after the cursor.
+    pub fn is_after_cursor(&self, pokemon: &OwnedPokemon) -> bool {
+        match &self.after {
+            Some(cursor) => self.compare(pokemon, cursor) == Ordering::Greater,
+            None => true,
+        }
+    }
+
+    /// Compares a pokemon's position with a cursor in this query's sort order.
+    fn compare(&self, pokemon: &OwnedPokemon, cursor: &Cursor) -> Ordering {
+        let ordering = self
+            .sort
+            .value(pokemon)
+            .cmp(&cursor.value)
+            .then_with(|| pokemon.id.bytes().cmp(&cursor.id.bytes()));
+        if self.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    }
+
+    /// Compares two pokemon in this query's sort order.
+    pub fn order(&self, a: &OwnedPokemon, b: &OwnedPokemon) -> Ordering {
+        self.compare(a, &self.cursor_at(b))
+    }
+}
+
+/// Position of a pokemon in a sort order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cursor {
+    /// The sort order this position is in.
+    pub sort: SortKey,
+    pub descending: bool,
+    pub value: i64,
+    pub id: ObjectId,
+}
+
+/// Written as the sort order the way it's requested, e.g. `-level`, then the value and the ID, separated by `_`.
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let direction = if self.descending { "-" } else { "" };
+        write!(f, "{}{}_{}_{}", direction, self.sort, self.value, self.id.to_hex())
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Cursor, Error> {
+        let invalid = || Error::InvalidQuery(format!("invalid cursor `{}`", s));
+        let mut parts = s.splitn(3, '_');
+        let (sort, value, id) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(sort), Some(value), Some(id)) => (sort, value, id),
+            _ => return Err(invalid()),
+        };
+        Ok(Cursor {
+            sort: sort.trim_start_matches('-').parse().map_err(|_| invalid())?,
+            descending: sort.starts_with('-'),
+            value: value.parse().map_err(|_| invalid())?,
+            id: ObjectId::parse_str(id).map_err(|_| invalid())?,
+        })
+    }
+}
+
+/// One page of results.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Cursor to pass to get the next page, or `None` if this is the last page.
+    pub next: Option<Cursor>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{
+        records::Stats,
+        repository::{MemoryRepository, PokemonQueryRepository, PokemonRepository},
+    };
+
+    /// A pokemon owned by `owner` whose IVs are all `iv`.
+    fn pokemon(owner: &str, species_id: u32, species_name: &str, level: u32, iv: u8) -> OwnedPokemon {
+        OwnedPokemon {
+            ivs: Stats {
+                hp: iv,
+                attack: iv,
+                defense: iv,
+                special_attack: iv,
+                special_defense: iv,
+                speed: iv,
+            },
+            ..OwnedPokemon::generate(
+                owner,
+                species_id,
+                species_name,
+                species_id,
+                level,
+                &mut rand::thread_rng(),
+            )
+        }
+    }
+
+    async fn repo_with(pokemon: &[OwnedPokemon]) -> MemoryRepository {
+        let repo = MemoryRepository::new();
+        for p in pokemon {
+            repo.insert_pokemon(p).await.unwrap();
+        }
+        repo
+    }
+
+    /// IDs of ash's pokemon matching `filter`, in catch order.
+    async fn matching(repo: &MemoryRepository, filter: PokemonFilter) -> Vec<ObjectId> {
+        let query = PokemonQuery {
+            filter,
+            limit: MAX_PAGE_SIZE,
+            ..PokemonQuery::default()
+        };
+        let page = repo.query_pokemon("ash", &query).await.unwrap();
+        page.items.iter().map(|p| p.id).collect()
+    }
+
+    /// Every page of ash's pokemon for `query`, following cursors through their string form.
+    async fn all_pages(repo: &MemoryRepository, mut query: PokemonQuery) -> Vec<OwnedPokemon> {
+        let mut items = Vec::new();
+        loop {
+            let page = repo.query_pokemon("ash", &query).await.unwrap();
+            assert!(page.items.len() <= query.limit);
+            items.extend(page.items);
+            match page.next {
+                Some(cursor) => query.after = Some(cursor.to_string().parse().unwrap()),
+                None => return items,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn filters() {
+        let pikachu = OwnedPokemon {
+            nickname: Some("Sparky".to_string()),
+            shiny: true,
+            types: vec!["electric".to_string()],
+            ..pokemon("ash", 25, "pikachu", 5, 10)
+        };
+        let charmander = OwnedPokemon {
+            shiny: false,
+            types: vec!["fire".to_string()],
+            ..pokemon("ash", 4, "charmander", 20, 10)
+        };
+        let mewtwo = OwnedPokemon {
+            shiny: false,
+            legendary: true,
+            types: vec!["psychic".to_string()],
+            ..pokemon("ash", 150, "mewtwo", 70, 10)
+        };
+        let staryu = pokemon("misty", 120, "staryu", 20, 10);
+        let repo = repo_with(&[pikachu.clone(), charmander.clone(), mewtwo.clone(), staryu]).await;
+
+        let all = matching(&repo, PokemonFilter::default()).await;
+        assert_eq!(all.len(), 3);
+        let species = PokemonFilter {
+            species: Some("pikachu".to_string()),
+            ..PokemonFilter::default()
+        };
+        assert_eq!(matching(&repo, species).await, vec![pikachu.id]);
+        let type_name = PokemonFilter {
+            type_name: Some("fire".to_string()),
+            ..PokemonFilter::default()
+        };
+        assert_eq!(matching(&repo, type_name).await, vec![charmander.id]);
+        let shiny = PokemonFilter {
+            shiny: Some(true),
+            ..PokemonFilter::default()
+        };
+        assert_eq!(matching(&repo, shiny).await, vec![pikachu.id]);
+        let legendary = PokemonFilter {
+            legendary: Some(true),
+            ..PokemonFilter::default()
+        };
+        assert_eq!(matching(&repo, legendary).await, vec![mewtwo.id]);
+        let levels = PokemonFilter {
+            min_level: Some(20),
+            max_level: Some(69),
+            ..PokemonFilter::default()
+        };
+        assert_eq!(matching(&repo, levels).await, vec![charmander.id]);
+        let nickname = PokemonFilter {
+            nickname: Some("SPARK".to_string()),
+            ..PokemonFilter::default()
+        };
+        assert_eq!(matching(&repo, nickname).await, vec![pikachu.id]);
+        let nothing = PokemonFilter {
+            species: Some("pikachu".to_string()),
+            legendary: Some(true),
+            ..PokemonFilter::default()
+        };
+        assert!(matching(&repo, nothing).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn iv_percentage_bounds_are_inclusive() {
+        // Half of the highest IV total, 186, is 93
+        let half = OwnedPokemon {
+            ivs: Stats {
+                hp: 18,
+                ..pokemon("ash", 1, "bulbasaur", 5, 15).ivs
+            },
+            ..pokemon("ash", 1, "bulbasaur", 5, 15)
+        };
+        let below_half = OwnedPokemon {
+            ivs: Stats { hp: 17, ..half.ivs },
+            ..pokemon("ash", 1, "bulbasaur", 5, 15)
+        };
+        let perfect = pokemon("ash", 1, "bulbasaur", 5, MAX_IV);
+        let repo = repo_with(&[half.clone(), below_half.clone(), perfect.clone()]).await;
+
+        let at_least_half = PokemonFilter {
+            min_iv: Some(50.0),
+            ..PokemonFilter::default()
+        };
+        assert_eq!(matching(&repo, at_least_half).await, vec![half.id, perfect.id]);
+        let at_most_half = PokemonFilter {
+            max_iv: Some(50.0),
+            ..PokemonFilter::default()
+        };
+        assert_eq!(matching(&repo, at_most_half).await, vec![half.id, below_half.id]);
+        let only_perfect = PokemonFilter {
+            min_iv: Some(100.0),
+            ..PokemonFilter::default()
+        };
+        assert_eq!(matching(&repo, only_perfect).await, vec![perfect.id]);
+    }
+
+    #[tokio::test]
+    async fn ties_are_broken_by_id_across_pages() {
+        let team: Vec<_> = (0..7).map(|_| pokemon("ash", 25, "pikachu", 10, 10)).collect();
+        let repo = repo_with(&team).await;
+        let query = PokemonQuery {
+            sort: SortKey::Level,
+            limit: 2,
+            ..PokemonQuery::default()
+        };
+
+        let mut expected: Vec<_> = team.iter().map(|p| p.id).collect();
+        expected.sort_by_key(|id| id.bytes());
+        let ids: Vec<_> = all_pages(&repo, query).await.iter().map(|p| p.id).collect();
+        assert_eq!(ids, expected);
+    }
+
+    #[tokio::test]
+    async fn descending_order() {
+        let team: Vec<_> = [12, 5, 40, 5, 33, 1]
+            .iter()
+            .map(|&level| pokemon("ash", 25, "pikachu", level, 10))
+            .collect();
+        let repo = repo_with(&team).await;
+        let query = PokemonQuery {
+            sort: SortKey::Level,
+            descending: true,
+            limit: 4,
+            ..PokemonQuery::default()
+        };
+
+        let listed = all_pages(&repo, query).await;
+        let levels: Vec<_> = listed.iter().map(|p| p.level).collect();
+        assert_eq!(levels, vec![40, 33, 12, 5, 5, 1]);
+        // Ties are broken by ID in descending order too
+        assert!(listed[3].id.bytes() > listed[4].id.bytes());
+    }
+
+    #[tokio::test]
+    async fn the_last_page_has_no_cursor() {
+        let team: Vec<_> = (0..4)
+            .map(|level| pokemon("ash", 25, "pikachu", level + 1, 10))
+            .collect();
+        let repo = repo_with(&team).await;
+        let query = PokemonQuery {
+            sort: SortKey::Level,
+            limit: 2,
+            ..PokemonQuery::default()
+        };
+
+        let first = repo.query_pokemon("ash", &query).await.unwrap();
+        let next = first.next.expect("Only the first of two pages");
+        let query = PokemonQuery {
+            after: Some(next),
+            ..query
+        };
+        let last = repo.query_pokemon("ash", &query).await.unwrap();
+        assert_eq!(last.items.len(), 2);
+        assert_eq!(last.next, None);
+    }
+
+    #[test]
+    fn cursors_round_trip_through_strings() {
+        let pikachu = pokemon("ash", 25, "pikachu", 10, 10);
+        let query = PokemonQuery {
+            sort: SortKey::DexNumber,
+            descending: true,
+            ..PokemonQuery::default()
+        };
+        let cursor = query.cursor_at(&pikachu);
+        assert_eq!(cursor.to_string(), format!("-dex-number_25_{}", pikachu.id.to_hex()));
+        assert_eq!(cursor.to_string().parse::<Cursor>().unwrap(), cursor);
+
+        for invalid in &[
+            "",
+            "25",
+            "level_25",
+            "level_x_0",
+            "age_25_0",
+            &format!("25_{}", pikachu.id.to_hex()),
+        ] {
+            assert!(invalid.parse::<Cursor>().is_err(), "{} parsed", invalid);
+        }
+    }
+
+    #[test]
+    fn cursors_are_only_valid_for_their_sort_order() {
+        let pikachu = pokemon("ash", 25, "pikachu", 10, 10);
+        let by_level = PokemonQuery {
+            sort: SortKey::Level,
+            ..PokemonQuery::default()
+        };
+        let cursor = by_level.cursor_at(&pikachu);
+        let next_page = |sort, descending| PokemonQuery {
+            sort,
+            descending,
+            after: Some(cursor),
+            ..PokemonQuery::default()
+        };
+
+        assert!(next_page(SortKey::Level, false).validate().is_ok());
+        assert!(matches!(
+            next_page(SortKey::Level, true).validate(),
+            Err(Error::InvalidQuery(_))
+        ));
+        assert!(matches!(
+            next_page(SortKey::Iv, false).validate(),
+            Err(Error::InvalidQuery(_))
+        ));
+        assert!(by_level.validate().is_ok());
+    }
+}
@@ -0,0 +1,373 @@
+//! The documents stored in each collection.
+//!
+//! Field names are the stored names, so renaming a field needs a migration.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use mongodb::bson::{oid::ObjectId, DateTime};
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+use super::Record;
+
+/// Highest possible individual value of a stat.
+pub const MAX_IV: u8 = 31;
+
+/// Chance of a newly generated pokemon being shiny.
+const SHINY_CHANCE: f64 = 1.0 / 4096.0;
+
+/// Most pokemon a player can have in their party.
+pub const MAX_PARTY_SIZE: usize = 6;
+
+/// Friendship of a newly caught pokemon, which is the base happiness of most species.
+pub const BASE_FRIENDSHIP: u8 = 70;
+
+/// Highest possible friendship.
+pub const MAX_FRIENDSHIP: u8 = 255;
+
+/// Highest level a pokemon can reach.
+pub const MAX_LEVEL: u32 = 100;
+
+/// Highest level wild pokemon spawn at.
+const MAX_SPAWN_LEVEL: u32 = 40;
+
+/// All natures, in the order of their PokeAPI IDs.
+pub const NATURES: [&str; 25] = [
+    "hardy", "bold", "modest", "calm", "timid", "lonely", "docile", "mild", "gentle", "hasty", "adamant", "impish",
+    "bashful", "careful", "rash", "jolly", "naughty", "lax", "quirky", "naive", "brave", "relaxed", "quiet", "sassy",
+    "serious",
+];
+
+/// A registered player.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Player {
+    /// The player's key from [`GuildSettings::player_key`], which is their Discord user ID unless they play in a guild
+    /// with its own progress.
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub registered_at: DateTime,
+    /// The pokemon chosen at registration, if any.
+    pub starter: Option<ObjectId>,
+    /// Number of wild pokemon caught, including any since traded or released.
+    #[serde(default)]
+    pub total_caught: u32,
+    /// IDs of every species the player has encountered.
+    #[serde(default)]
+    pub seen_species: BTreeSet<u32>,
+    /// IDs of every species the player has owned.
+    #[serde(default)]
+    pub caught_species: BTreeSet<u32>,
+    /// The pokemon the player is currently using, which gains experience as they chat.
+    #[serde(default)]
+    pub selected: Option<ObjectId>,
+    #[serde(default)]
+    pub favorites: Vec<ObjectId>,
+    /// The pokemon the player battles with, lead first. Holds at most [`MAX_PARTY_SIZE`] different pokemon.
+    #[serde(default)]
+    pub party: Vec<ObjectId>,
+    /// Names of earned badges.
+    #[serde(default)]
+    pub badges: Vec<String>,
+    /// When the player last earned experience from chatting, for rate limiting.
+    #[serde(default)]
+    pub last_experience_at: Option<DateTime>,
+}
+
+impl Record for Player {
+    const COLLECTION: &'static str = "players";
+}
+
+impl Player {
+    /// A player registering now.
+    pub fn new(id: &str) -> Player {
+        Player {
+            id: id.to_string(),
+            registered_at: chrono::Utc::now().into(),
+            starter: None,
+            total_caught: 0,
+            seen_species: BTreeSet::new(),
+            caught_species: BTreeSet::new(),
+            selected: None,
+            favorites: Vec::new(),
+            party: Vec::new(),
+            badges: Vec::new(),
+            last_experience_at: None,
+        }
+    }
+}
+
+/// Values of each of a pokemon's stats.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Stats {
+    pub hp: u8,
+    pub attack: u8,
+    pub defense: u8,
+    pub special_attack: u8,
+    pub special_defense: u8,
+    pub speed: u8,
+}
+
+impl Stats {
+    /// Random individual values.
+    pub fn random_ivs<R: Rng + ?Sized>(rng: &mut R) -> Stats {
+        let mut iv = || rng.gen_range(0..=MAX_IV);
+        Stats {
+            hp: iv(),
+            attack: iv(),
+            defense: iv(),
+            special_attack: iv(),
+            special_defense: iv(),
+            speed: iv(),
+        }
+    }
+
+    pub fn total(&self) -> u32 {
+        [self.hp, self.attack, self.defense, self.special_attack, self.special_defense, self.speed]
+            .iter()
+            .map(|&v| v as u32)
+            .sum()
+    }
+
+    /// How close these IVs are to perfect, from 0 to 100.
+    pub fn iv_percentage(&self) -> f64 {
+        self.total() as f64 * 100.0 / (6 * MAX_IV as u32) as f64
+    }
+}
+
+/// A pokemon belonging to a player.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OwnedPokemon {
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    /// [`Player::id`] of the owner.
+    pub owner: String,
+    pub species_id: u32,
+    pub species_name: String,
+    pub variety_id: u32,
+    pub nickname: Option<String>,
+    pub level: u32,
+    /// Experience points within the current level's growth rate.
+    pub experience: u32,
+    pub ivs: Stats,
+    pub nature: String,
+    pub shiny: bool,
+    pub caught_at: DateTime,
+    /// API names of the pokemon's types, copied from PokeAPI so that collections can be filtered by type.
+    #[serde(default)]
+    pub types: Vec<String>,
+    /// Whether the species is legendary or mythical, copied from PokeAPI for filtering.
+    #[serde(default)]
+    pub legendary: bool,
+    #[serde(default = "default_friendship")]
+    pub friendship: u8,
+    /// PokeAPI name of the item the pokemon is holding.
+    #[serde(default)]
+    pub held_item: Option<String>,
+    /// Set when the player cancelled evolution, so the pokemon doesn't evolve automatically as it levels up. Holding
+    /// an everstone has the same effect.
+    #[serde(default)]
+    pub evolution_blocked: bool,
+    /// Every evolution the pokemon went through, oldest first.
+    #[serde(default)]
+    pub evolutions: Vec<Evolution>,
+}
+
+fn default_friendship() -> u8 {
+    BASE_FRIENDSHIP
+}
+
+/// An evolution an owned pokemon went through.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Evolution {
+    pub from_species_id: u32,
+    pub from_species_name: String,
+    pub to_species_id: u32,
+    pub to_species_name: String,
+    /// Level the pokemon evolved at.
+    pub level: u32,
+    pub evolved_at: DateTime,
+}
+
+impl Record for OwnedPokemon {
+    const COLLECTION: &'static str = "owned_pokemon";
+}
+
+impl OwnedPokemon {
+    /// A newly caught pokemon with random IVs, nature and shininess.
+    pub fn generate<R: Rng + ?Sized>(
+        owner: &str,
+        species_id: u32,
+        species_name: &str,
+        variety_id: u32,
+        level: u32,
+        rng: &mut R,
+    ) -> OwnedPokemon {
+        OwnedPokemon {
+            id: ObjectId::new(),
+            owner: owner.to_string(),
+            species_id,
+            species_name: species_name.to_string(),
+            variety_id,
+            nickname: None,
+            level,
+            experience: 0,
+            ivs: Stats::random_ivs(rng),
+            nature: NATURES.choose(rng).unwrap().to_string(),
+            shiny: rng.gen_bool(SHINY_CHANCE),
+            caught_at: chrono::Utc::now().into(),
+            types: Vec::new(),
+            legendary: false,
+            friendship: BASE_FRIENDSHIP,
+            held_item: None,
+            evolution_blocked: false,
+            evolutions: Vec::new(),
+        }
+    }
+
+    /// A pokemon caught from `spawn`, which keeps the spawn's level and shininess.
+    pub fn caught_from<R: Rng + ?Sized>(owner: &str, spawn: &Spawn, rng: &mut R) -> OwnedPokemon {
+        OwnedPokemon {
+            shiny: spawn.shiny,
+            types: spawn.types.clone(),
+            legendary: spawn.legendary,
+            ..OwnedPokemon::generate(
+                owner,
+                spawn.species_id,
+                &spawn.species_name,
+                spawn.variety_id,
+                spawn.level,
+                rng,
+            )
+        }
+    }
+}
+
+/// A player's items and money.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Inventory {
+    /// [`Player::id`] of the owner.
+    #[serde(rename = "_id")]
+    pub owner: String,
+    pub currency: i64,
+    /// Counts of each item held, by PokeAPI item name.
+    pub items: BTreeMap<String, u32>,
+}
+
+impl Record for Inventory {
+    const COLLECTION: &'static str = "inventories";
+}
+
+impl Inventory {
+    /// An empty inventory.
+    pub fn new(owner: &str) -> Inventory {
+        Inventory {
+            owner: owner.to_string(),
+            ..Inventory::default()
+        }
+    }
+}
+
+/// Whether a guild's players share their progress with other guilds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProgressScope {
+    /// Players have one profile and collection across every guild with the same scope.
+    #[default]
+    Global,
+    /// Players start over in this guild, with a profile and collection only used here.
+    Guild,
+}
+
+/// A Discord server's configuration. Guilds that were never configured use the defaults.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GuildSettings {
+    /// Discord guild ID.
+    #[serde(rename = "_id")]
+    pub guild_id: String,
+    /// Channel wild pokemon appear in, or `None` to spawn in whichever channel is active.
+    pub spawn_channel: Option<String>,
+    /// Average number of messages between spawns.
+    pub spawn_rate: u32,
+    /// PokeAPI language name to show pokemon names and flavor text in.
+    pub language: String,
+    /// Command prefix.
+    pub prefix: String,
+    pub progress_scope: ProgressScope,
+}
+
+impl Record for GuildSettings {
+    const COLLECTION: &'static str = "guilds";
+}
+
+impl GuildSettings {
+    pub const DEFAULT_SPAWN_RATE: u32 = 20;
+    pub const DEFAULT_PREFIX: &'static str = "p!";
+
+    /// The settings of a guild that hasn't changed any.
+    pub fn new(guild_id: &str) -> GuildSettings {
+        GuildSettings {
+            guild_id: guild_id.to_string(),
+            spawn_channel: None,
+            spawn_rate: GuildSettings::DEFAULT_SPAWN_RATE,
+            language: crate::pokedex::FALLBACK_LANGUAGE.to_string(),
+            prefix: GuildSettings::DEFAULT_PREFIX.to_string(),
+            progress_scope: ProgressScope::default(),
+        }
+    }
+
+    /// The key a player's records are stored under in this guild. Players in guilds with global progress use their
+    /// Discord ID everywhere, while guild-scoped progress is kept apart by prefixing the guild ID.
+    pub fn player_key(&self, player_id: &str) -> String {
+        match self.progress_scope {
+            ProgressScope::Global => player_id.to_string(),
+            ProgressScope::Guild => format!("{}:{}", self.guild_id, player_id),
+        }
+    }
+}
+
+/// A wild pokemon waiting to be caught in a channel. Each channel has at most one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Spawn {
+    /// Discord channel ID.
+    #[serde(rename = "_id")]
+    pub channel_id: String,
+    /// Discord guild ID, or `None` in DMs.
+    pub guild_id: Option<String>,
+    pub species_id: u32,
+    pub species_name: String,
+    pub variety_id: u32,
+    pub level: u32,
+    pub shiny: bool,
+    pub spawned_at: DateTime,
+    #[serde(default)]
+    pub types: Vec<String>,
+    #[serde(default)]
+    pub legendary: bool,
+}
+
+impl Record for Spawn {
+    const COLLECTION: &'static str = "spawns";
+}
+
+impl Spawn {
+    /// A wild pokemon appearing in a channel now, with a random level and shininess.
+    pub fn new<R: Rng + ?Sized>(
+        channel_id: &str,
+        guild_id: Option<&str>,
+        pokemon: &crate::models::Pokemon,
+        rng: &mut R,
+    ) -> Spawn {
+        Spawn {
+            channel_id: channel_id.to_string(),
+            guild_id: guild_id.map(str::to_string),
+            species_id: pokemon.species_id as u32,
+            species_name: pokemon.species_name.clone(),
+            variety_id: pokemon.variety_id as u32,
+            level: rng.gen_range(1..=MAX_SPAWN_LEVEL),
+            shiny: rng.gen_bool(SHINY_CHANCE),
+            spawned_at: chrono::Utc::now().into(),
+            types: pokemon.types.clone(),
+            legendary: pokemon.is_legendary,
+        }
+    }
+}
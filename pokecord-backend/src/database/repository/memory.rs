@@ -0,0 +1,610 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use mongodb::bson::oid::ObjectId;
+
+use super::{
+    GuildRepository, InventoryRepository, PlayerRepository, PokemonQueryRepository, PokemonRepository, SpawnRepository,
+    Transactional, UnitOfWork,
+};
+use crate::database::{
+    query::{Page, PokemonQuery},
+    records::{GuildSettings, Inventory, OwnedPokemon, Player, Spawn},
+    Error,
+};
+
+/// Repository that keeps everything in memory, for running game logic without a database.
+#[derive(Debug, Default)]
+pub struct MemoryRepository {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Debug, Default, Clone)]
+struct State {
+    players: HashMap<String, Player>,
+    /// Pokemon in the order they were inserted.
+    pokemon: Vec<OwnedPokemon>,
+    inventories: HashMap<String, Inventory>,
+    spawns: HashMap<String, Spawn>,
+    guilds: HashMap<String, GuildSettings>,
+    /// How many times each document has been written. Documents that were never written are at version 0.
+    versions: HashMap<DocumentKey, u64>,
+}
+
+/// Identifies a document, for tracking its version.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum DocumentKey {
+    Player(String),
+    Pokemon(ObjectId),
+    Inventory(String),
+    Spawn(String),
+    Guild(String),
+}
+
+/// A change made in a [`MemoryUnitOfWork`], replayed onto the shared state when it commits.
+#[derive(Debug, Clone)]
+enum Change {
+    InsertPlayer(Player),
+    UpdatePlayer(Player),
+    InsertPokemon(OwnedPokemon),
+    UpdatePokemon(OwnedPokemon),
+    DeletePokemon(ObjectId),
+    SaveInventory(Inventory),
+    PutSpawn(Spawn),
+    TakeSpawn(String),
+    SaveGuildSettings(GuildSettings),
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    // A panic while holding the lock can't leave the state half-updated, so poisoning is safe to ignore
+    mutex.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+impl MemoryRepository {
+    pub fn new() -> MemoryRepository {
+        MemoryRepository::default()
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        lock(&self.state)
+    }
+}
+
+impl State {
+    fn version(&self, key: &DocumentKey) -> u64 {
+        self.versions.get(key).copied().unwrap_or(0)
+    }
+
+    /// Records a write to the document `key`.
+    fn touch(&mut self, key: DocumentKey) {
+        *self.versions.entry(key).or_insert(0) += 1;
+    }
+
+    fn insert_player(&mut self, player: &Player) -> Result<(), Error> {
+        if self.players.contains_key(&player.id) {
+            return Err(Error::AlreadyExists(player.id.clone()));
+        }
+        self.players.insert(player.id.clone(), player.clone());
+        self.touch(DocumentKey::Player(player.id.clone()));
+        Ok(())
+    }
+
+    fn update_player(&mut self, player: &Player) {
+        self.players.insert(player.id.clone(), player.clone());
+        self.touch(DocumentKey::Player(player.id.clone()));
+    }
+
+    /// The player stored under `id` with `last_experience_at` set to `now`, unless they are unregistered or it's less
+    /// than `cooldown` since it was last set.
+    fn experience_claimed(&self, id: &str, now: DateTime<Utc>, cooldown: Duration) -> Option<Player> {
+        let player = self.players.get(id)?;
+        match &player.last_experience_at {
+            Some(last) if now.signed_duration_since(last.to_chrono()) < cooldown => None,
+            _ => Some(Player {
+                last_experience_at: Some(now.into()),
+                ..player.clone()
+            }),
+        }
+    }
+
+    fn pokemon_owned_by(&self, owner: &str) -> Vec<OwnedPokemon> {
+        self.pokemon.iter().filter(|p| p.owner == owner).cloned().collect()
+    }
+
+    fn insert_pokemon(&mut self, pokemon: &OwnedPokemon) -> Result<(), Error> {
+        if self.pokemon.iter().any(|p| p.id == pokemon.id) {
+            return Err(Error::AlreadyExists(pokemon.id.to_hex()));
+        }
+        self.pokemon.push(pokemon.clone());
+        self.touch(DocumentKey::Pokemon(pokemon.id));
+        Ok(())
+    }
+
+    fn update_pokemon(&mut self, pokemon: &OwnedPokemon) {
+        match self.pokemon.iter_mut().find(|p| p.id == pokemon.id) {
+            Some(existing) => *existing = pokemon.clone(),
+            None => self.pokemon.push(pokemon.clone()),
+        }
+        self.touch(DocumentKey::Pokemon(pokemon.id));
+    }
+
+    fn delete_pokemon(&mut self, id: ObjectId) -> bool {
+        let before = self.pokemon.len();
+        self.pokemon.retain(|p| p.id != id);
+        let deleted = self.pokemon.len() != before;
+        if deleted {
+            self.touch(DocumentKey::Pokemon(id));
+        }
+        deleted
+    }
+
+    fn get_inventory(&self, owner: &str) -> Inventory {
+        self.inventories
+            .get(owner)
+            .cloned()
+            .unwrap_or_else(|| Inventory::new(owner))
+    }
+
+    fn save_inventory(&mut self, inventory: &Inventory) {
+        self.inventories.insert(inventory.owner.clone(), inventory.clone());
+        self.touch(DocumentKey::Inventory(inventory.owner.clone()));
+    }
+
+    fn put_spawn(&mut self, spawn: &Spawn) {
+        self.spawns.insert(spawn.channel_id.clone(), spawn.clone());
+        self.touch(DocumentKey::Spawn(spawn.channel_id.clone()));
+    }
+
+    fn take_spawn(&mut self, channel_id: &str) -> Option<Spawn> {
+        let taken = self.spawns.remove(channel_id);
+        if taken.is_some() {
+            self.touch(DocumentKey::Spawn(channel_id.to_string()));
+        }
+        taken
+    }
+
+    fn get_guild_settings(&self, guild_id: &str) -> GuildSettings {
+        self.guilds
+            .get(guild_id)
+            .cloned()
+            .unwrap_or_else(|| GuildSettings::new(guild_id))
+    }
+
+    fn save_guild_settings(&mut self, settings: &GuildSettings) {
+        self.guilds.insert(settings.guild_id.clone(), settings.clone());
+        self.touch(DocumentKey::Guild(settings.guild_id.clone()));
+    }
+
+    /// Applies a committed change. Inserts and removals are checked again, although conflicting writes are normally
+    /// caught by [`MemoryUnitOfWork::commit`] comparing versions first.
+    fn apply(&mut self, change: &Change) -> Result<(), Error> {
+        match change {
+            Change::InsertPlayer(player) => self.insert_player(player)?,
+            Change::UpdatePlayer(player) => self.update_player(player),
+            Change::InsertPokemon(pokemon) => self.insert_pokemon(pokemon)?,
+            Change::UpdatePokemon(pokemon) => self.update_pokemon(pokemon),
+            Change::DeletePokemon(id) => {
+                if !self.delete_pokemon(*id) {
+                    return Err(Error::WriteConflict);
+                }
+            }
+            Change::SaveInventory(inventory) => self.save_inventory(inventory),
+            Change::PutSpawn(spawn) => self.put_spawn(spawn),
+            Change::TakeSpawn(channel_id) => {
+                if self.take_spawn(channel_id).is_none() {
+                    return Err(Error::WriteConflict);
+                }
+            }
+            Change::SaveGuildSettings(settings) => self.save_guild_settings(settings),
+        }
+        Ok(())
+    }
+}
+
+impl Change {
+    /// The document this change writes.
+    fn key(&self) -> DocumentKey {
+        match self {
+            Change::InsertPlayer(player) | Change::UpdatePlayer(player) => DocumentKey::Player(player.id.clone()),
+            Change::InsertPokemon(pokemon) | Change::UpdatePokemon(pokemon) => DocumentKey::Pokemon(pokemon.id),
+            Change::DeletePokemon(id) => DocumentKey::Pokemon(*id),
+            Change::SaveInventory(inventory) => DocumentKey::Inventory(inventory.owner.clone()),
+            Change::PutSpawn(spawn) => DocumentKey::Spawn(spawn.channel_id.clone()),
+            Change::TakeSpawn(channel_id) => DocumentKey::Spawn(channel_id.clone()),
+            Change::SaveGuildSettings(settings) => DocumentKey::Guild(settings.guild_id.clone()),
+        }
+    }
+}
+
+#[async_trait]
+impl PlayerRepository for MemoryRepository {
+    async fn get_player(&self, id: &str) -> Result<Option<Player>, Error> {
+        Ok(self.state().players.get(id).cloned())
+    }
+
+    async fn insert_player(&self, player: &Player) -> Result<(), Error> {
+        self.state().insert_player(player)
+    }
+
+    async fn update_player(&self, player: &Player) -> Result<(), Error> {
+        self.state().update_player(player);
+        Ok(())
+    }
+
+    async fn claim_experience_cooldown(&self, id: &str, now: DateTime<Utc>, cooldown: Duration) -> Result<bool, Error> {
+        let mut state = self.state();
+        match state.experience_claimed(id, now, cooldown) {
+            Some(player) => {
+                state.update_player(&player);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+#[async_trait]
+impl PokemonRepository for MemoryRepository {
+    async fn get_pokemon(&self, id: ObjectId) -> Result<Option<OwnedPokemon>, Error> {
+        Ok(self.state().pokemon.iter().find(|p| p.id == id).cloned())
+    }
+
+    async fn pokemon_owned_by(&self, owner: &str) -> Result<Vec<OwnedPokemon>, Error> {
+        Ok(self.state().pokemon_owned_by(owner))
+    }
+
+    async fn insert_pokemon(&self, pokemon: &OwnedPokemon) -> Result<(), Error> {
+        self.state().insert_pokemon(pokemon)
+    }
+
+    async fn update_pokemon(&self, pokemon: &OwnedPokemon) -> Result<(), Error> {
+        self.state().update_pokemon(pokemon);
+        Ok(())
+    }
+
+    async fn delete_pokemon(&self, id: ObjectId) -> Result<bool, Error> {
+        Ok(self.state().delete_pokemon(id))
+    }
+}
+
+#[async_trait]
+impl PokemonQueryRepository for MemoryRepository {
+    async fn query_pokemon(&self, owner: &str, query: &PokemonQuery) -> Result<Page<OwnedPokemon>, Error> {
+        let mut matching: Vec<OwnedPokemon> = self
+            .state()
+            .pokemon
+            .iter()
+            .filter(|p| p.owner == owner && query.filter.matches(p) && query.is_after_cursor(p))
+            .cloned()
+            .collect();
+        matching.sort_by(|a, b| query.order(a, b));
+        let has_more = matching.len() > query.limit;
+        matching.truncate(query.limit);
+        Ok(Page {
+            next: matching.last().filter(|_| has_more).map(|p| query.cursor_at(p)),
+            items: matching,
+        })
+    }
+}
+
+#[async_trait]
+impl InventoryRepository for MemoryRepository {
+    async fn get_inventory(&self, owner: &str) -> Result<Inventory, Error> {
+        Ok(self.state().get_inventory(owner))
+    }
+
+    async fn save_inventory(&self, inventory: &Inventory) -> Result<(), Error> {
+        self.state().save_inventory(inventory);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SpawnRepository for MemoryRepository {
+    async fn active_spawn(&self, channel_id: &str) -> Result<Option<Spawn>, Error> {
+        Ok(self.state().spawns.get(channel_id).cloned())
+    }
+
+    async fn put_spawn(&self, spawn: &Spawn) -> Result<(), Error> {
+        self.state().put_spawn(spawn);
+        Ok(())
+    }
+
+    async fn take_spawn(&self, channel_id: &str) -> Result<Option<Spawn>, Error> {
+        Ok(self.state().take_spawn(channel_id))
+    }
+}
+
+#[async_trait]
+impl GuildRepository for MemoryRepository {
+    async fn get_guild_settings(&self, guild_id: &str) -> Result<GuildSettings, Error> {
+        Ok(self.state().get_guild_settings(guild_id))
+    }
+
+    async fn save_guild_settings(&self, settings: &GuildSettings) -> Result<(), Error> {
+        self.state().save_guild_settings(settings);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Transactional for MemoryRepository {
+    type UnitOfWork = MemoryUnitOfWork;
+
+    async fn begin(&self) -> Result<MemoryUnitOfWork, Error> {
+        let snapshot = self.state().clone();
+        Ok(MemoryUnitOfWork {
+            shared: self.state.clone(),
+            versions: snapshot.versions.clone(),
+            pending: Mutex::new((snapshot, Vec::new())),
+        })
+    }
+}
+
+/// An in-memory transaction. It works on a snapshot of the state taken when it began, and its changes are applied to
+/// the shared state together when it commits. Like a MongoDB transaction, it fails to commit with
+/// [`Error::WriteConflict`] if anything else wrote one of the documents it writes after it began.
+#[derive(Debug)]
+pub struct MemoryUnitOfWork {
+    shared: Arc<Mutex<State>>,
+    /// Document versions when this transaction began.
+    versions: HashMap<DocumentKey, u64>,
+    /// The snapshot with this transaction's changes applied, and the changes themselves.
+    pending: Mutex<(State, Vec<Change>)>,
+}
+
+impl MemoryUnitOfWork {
+    /// Runs `op` on the snapshot, recording `change` if it succeeds.
+    fn write<T>(&self, change: Change, op: impl FnOnce(&mut State) -> Result<T, Error>) -> Result<T, Error> {
+        let mut pending = lock(&self.pending);
+        let result = op(&mut pending.0)?;
+        pending.1.push(change);
+        Ok(result)
+    }
+
+    /// Runs a removal on the snapshot, recording `change` only if it removed something. Removing nothing isn't a
+    /// change, so it can't conflict.
+    fn remove<T>(&self, change: Change, op: impl FnOnce(&mut State) -> Option<T>) -> Option<T> {
+        let mut pending = lock(&self.pending);
+        let removed = op(&mut pending.0);
+        if removed.is_some() {
+            pending.1.push(change);
+        }
+        removed
+    }
+
+    fn snapshot(&self) -> MutexGuard<'_, (State, Vec<Change>)> {
+        lock(&self.pending)
+    }
+}
+
+#[async_trait]
+impl UnitOfWork for MemoryUnitOfWork {
+    async fn commit(self) -> Result<(), Error> {
+        let MemoryUnitOfWork {
+            shared,
+            versions,
+            pending,
+        } = self;
+        let (_, changes) = pending.into_inner().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut shared = lock(&shared);
+        let conflicted = changes.iter().any(|change| {
+            let key = change.key();
+            shared.version(&key) != versions.get(&key).copied().unwrap_or(0)
+        });
+        if conflicted {
+            return Err(Error::WriteConflict);
+        }
+        // Apply to a copy first so a failed change leaves the shared state untouched
+        let mut committed = shared.clone();
+        for change in &changes {
+            committed.apply(change)?;
+        }
+        *shared = committed;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PlayerRepository for MemoryUnitOfWork {
+    async fn get_player(&self, id: &str) -> Result<Option<Player>, Error> {
+        Ok(self.snapshot().0.players.get(id).cloned())
+    }
+
+    async fn insert_player(&self, player: &Player) -> Result<(), Error> {
+        self.write(Change::InsertPlayer(player.clone()), |state| state.insert_player(player))
+    }
+
+    async fn update_player(&self, player: &Player) -> Result<(), Error> {
+        self.write(Change::UpdatePlayer(player.clone()), |state| {
+            state.update_player(player);
+            Ok(())
+        })
+    }
+
+    async fn claim_experience_cooldown(&self, id: &str, now: DateTime<Utc>, cooldown: Duration) -> Result<bool, Error> {
+        let claimed = self.snapshot().0.experience_claimed(id, now, cooldown);
+        match claimed {
+            Some(player) => {
+                self.update_player(&player).await?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+#[async_trait]
+impl PokemonRepository for MemoryUnitOfWork {
+    async fn get_pokemon(&self, id: ObjectId) -> Result<Option<OwnedPokemon>, Error> {
+        Ok(self.snapshot().0.pokemon.iter().find(|p| p.id == id).cloned())
+    }
+
+    async fn pokemon_owned_by(&self, owner: &str) -> Result<Vec<OwnedPokemon>, Error> {
+        Ok(self.snapshot().0.pokemon_owned_by(owner))
+    }
+
+    async fn insert_pokemon(&self, pokemon: &OwnedPokemon) -> Result<(), Error> {
+        self.write(Change::InsertPokemon(pokemon.clone()), |state| state.insert_pokemon(pokemon))
+    }
+
+    async fn update_pokemon(&self, pokemon: &OwnedPokemon) -> Result<(), Error> {
+        self.write(Change::UpdatePokemon(pokemon.clone()), |state| {
+            state.update_pokemon(pokemon);
+            Ok(())
+        })
+    }
+
+    async fn delete_pokemon(&self, id: ObjectId) -> Result<bool, Error> {
+        let removed = self.remove(Change::DeletePokemon(id), |state| {
+            if state.delete_pokemon(id) {
+                Some(())
+            } else {
+                None
+            }
+        });
+        Ok(removed.is_some())
+    }
+}
+
+#[async_trait]
+impl InventoryRepository for MemoryUnitOfWork {
+    async fn get_inventory(&self, owner: &str) -> Result<Inventory, Error> {
+        Ok(self.snapshot().0.get_inventory(owner))
+    }
+
+    async fn save_inventory(&self, inventory: &Inventory) -> Result<(), Error> {
+        self.write(Change::SaveInventory(inventory.clone()), |state| {
+            state.save_inventory(inventory);
+            Ok(())
+        })
+    }
+}
+
+#[async_trait]
+impl SpawnRepository for MemoryUnitOfWork {
+    async fn active_spawn(&self, channel_id: &str) -> Result<Option<Spawn>, Error> {
+        Ok(self.snapshot().0.spawns.get(channel_id).cloned())
+    }
+
+    async fn put_spawn(&self, spawn: &Spawn) -> Result<(), Error> {
+        self.write(Change::PutSpawn(spawn.clone()), |state| {
+            state.put_spawn(spawn);
+            Ok(())
+        })
+    }
+
+    async fn take_spawn(&self, channel_id: &str) -> Result<Option<Spawn>, Error> {
+        Ok(self.remove(Change::TakeSpawn(channel_id.to_string()), |state| state.take_spawn(channel_id)))
+    }
+}
+
+#[async_trait]
+impl GuildRepository for MemoryUnitOfWork {
+    async fn get_guild_settings(&self, guild_id: &str) -> Result<GuildSettings, Error> {
+        Ok(self.snapshot().0.get_guild_settings(guild_id))
+    }
+
+    async fn save_guild_settings(&self, settings: &GuildSettings) -> Result<(), Error> {
+        self.write(Change::SaveGuildSettings(settings.clone()), |state| {
+            state.save_guild_settings(settings);
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::SubsecRound;
+
+    use super::*;
+    use crate::database::test_data::repo_with_currency;
+
+    /// Runs the steps of a purchase: read the balance, then write it back less `cost`.
+    async fn spend(work: &MemoryUnitOfWork, cost: i64) {
+        let mut inventory = work.get_inventory("ash").await.unwrap();
+        inventory.currency -= cost;
+        work.save_inventory(&inventory).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn interleaved_writes_to_the_same_document_conflict() {
+        let repo = repo_with_currency(1000).await;
+
+        let first = repo.begin().await.unwrap();
+        let second = repo.begin().await.unwrap();
+        spend(&first, 300).await;
+        spend(&second, 500).await;
+        first.commit().await.unwrap();
+        assert!(matches!(second.commit().await, Err(Error::WriteConflict)));
+
+        // Only the first purchase was paid for, rather than the second overwriting it
+        assert_eq!(repo.get_inventory("ash").await.unwrap().currency, 700);
+    }
+
+    #[tokio::test]
+    async fn sequential_units_do_not_conflict() {
+        let repo = repo_with_currency(1000).await;
+
+        let first = repo.begin().await.unwrap();
+        spend(&first, 300).await;
+        first.commit().await.unwrap();
+        let second = repo.begin().await.unwrap();
+        spend(&second, 500).await;
+        second.commit().await.unwrap();
+
+        assert_eq!(repo.get_inventory("ash").await.unwrap().currency, 200);
+    }
+
+    #[tokio::test]
+    async fn writes_outside_units_conflict_with_them() {
+        let repo = repo_with_currency(1000).await;
+
+        let work = repo.begin().await.unwrap();
+        spend(&work, 300).await;
+        let mut inventory = repo.get_inventory("ash").await.unwrap();
+        inventory.currency += 50;
+        repo.save_inventory(&inventory).await.unwrap();
+
+        assert!(matches!(work.commit().await, Err(Error::WriteConflict)));
+        assert_eq!(repo.get_inventory("ash").await.unwrap().currency, 1050);
+    }
+
+    #[tokio::test]
+    async fn writes_to_different_documents_do_not_conflict() {
+        let repo = repo_with_currency(1000).await;
+
+        let first = repo.begin().await.unwrap();
+        let second = repo.begin().await.unwrap();
+        spend(&first, 300).await;
+        second.insert_player(&Player::new("misty")).await.unwrap();
+        first.commit().await.unwrap();
+        second.commit().await.unwrap();
+
+        assert_eq!(repo.get_inventory("ash").await.unwrap().currency, 700);
+        assert!(repo.get_player("misty").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn experience_cooldown_is_claimed_once_per_cooldown() {
+        let repo = MemoryRepository::new();
+        repo.insert_player(&Player::new("ash")).await.unwrap();
+        // Stored times only keep milliseconds
+        let start = Utc::now().trunc_subsecs(3);
+        let cooldown = Duration::seconds(30);
+
+        assert!(repo.claim_experience_cooldown("ash", start, cooldown).await.unwrap());
+        let soon = start + Duration::seconds(10);
+        assert!(!repo.claim_experience_cooldown("ash", soon, cooldown).await.unwrap());
+        let later = start + cooldown;
+        assert!(repo.claim_experience_cooldown("ash", later, cooldown).await.unwrap());
+        assert!(!repo.claim_experience_cooldown("misty", start, cooldown).await.unwrap());
+
+        let player = repo.get_player("ash").await.unwrap().unwrap();
+        assert_eq!(player.last_experience_at.map(|at| at.to_chrono()), Some(later));
+    }
+}
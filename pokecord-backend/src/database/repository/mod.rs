@@ -0,0 +1,116 @@
+//! Storage operations game logic is written against, so it can run on [`MemoryRepository`] in tests and
+//! [`MongoRepository`] in production.
+//!
+//! Operations that change several documents must go through a [`UnitOfWork`] from [`Transactional::begin`], so that
+//! a crash or error partway through can't leave e.g. a traded pokemon with both players or neither.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use mongodb::bson::oid::ObjectId;
+
+use super::{
+    query::{Page, PokemonQuery},
+    records::{GuildSettings, Inventory, OwnedPokemon, Player, Spawn},
+    Error,
+};
+
+mod memory;
+mod mongo;
+
+pub use memory::{MemoryRepository, MemoryUnitOfWork};
+pub use mongo::{MongoRepository, MongoUnitOfWork};
+
+#[async_trait]
+pub trait PlayerRepository: Send + Sync {
+    async fn get_player(&self, id: &str) -> Result<Option<Player>, Error>;
+
+    /// Adds a new player, failing with [`Error::AlreadyExists`] if they are already registered.
+    async fn insert_player(&self, player: &Player) -> Result<(), Error>;
+
+    async fn update_player(&self, player: &Player) -> Result<(), Error>;
+
+    /// Records that the player earned experience from chatting at `now`, unless they already did less than
+    /// `cooldown` before. Returns whether it was recorded, which is never the case for unregistered players.
+    ///
+    /// The check and the write happen atomically, so only one of several messages sent at once earns experience.
+    async fn claim_experience_cooldown(&self, id: &str, now: DateTime<Utc>, cooldown: Duration) -> Result<bool, Error>;
+}
+
+#[async_trait]
+pub trait PokemonRepository: Send + Sync {
+    async fn get_pokemon(&self, id: ObjectId) -> Result<Option<OwnedPokemon>, Error>;
+
+    /// All pokemon owned by a player, in the order they were caught.
+    async fn pokemon_owned_by(&self, owner: &str) -> Result<Vec<OwnedPokemon>, Error>;
+
+    async fn insert_pokemon(&self, pokemon: &OwnedPokemon) -> Result<(), Error>;
+
+    async fn update_pokemon(&self, pokemon: &OwnedPokemon) -> Result<(), Error>;
+
+    /// Removes a pokemon, returning whether it existed.
+    async fn delete_pokemon(&self, id: ObjectId) -> Result<bool, Error>;
+}
+
+/// Paged browsing of a player's pokemon. This isn't part of [`Repository`], since listings never need to be
+/// transactional.
+#[async_trait]
+pub trait PokemonQueryRepository: Send + Sync {
+    async fn query_pokemon(&self, owner: &str, query: &PokemonQuery) -> Result<Page<OwnedPokemon>, Error>;
+}
+
+#[async_trait]
+pub trait InventoryRepository: Send + Sync {
+    /// A player's inventory, which is empty if they have never had anything.
+    async fn get_inventory(&self, owner: &str) -> Result<Inventory, Error>;
+
+    async fn save_inventory(&self, inventory: &Inventory) -> Result<(), Error>;
+}
+
+#[async_trait]
+pub trait SpawnRepository: Send + Sync {
+    /// The pokemon currently waiting in a channel, if any.
+    async fn active_spawn(&self, channel_id: &str) -> Result<Option<Spawn>, Error>;
+
+    /// Makes `spawn` the active spawn of its channel, replacing any earlier one.
+    async fn put_spawn(&self, spawn: &Spawn) -> Result<(), Error>;
+
+    /// Removes and returns a channel's active spawn. Only one caller can take each spawn, so two players can't catch
+    /// the same pokemon.
+    async fn take_spawn(&self, channel_id: &str) -> Result<Option<Spawn>, Error>;
+}
+
+#[async_trait]
+pub trait GuildRepository: Send + Sync {
+    /// A guild's settings, which are the defaults if it has never changed them.
+    async fn get_guild_settings(&self, guild_id: &str) -> Result<GuildSettings, Error>;
+
+    async fn save_guild_settings(&self, settings: &GuildSettings) -> Result<(), Error>;
+}
+
+/// Every repository, for handlers that need several.
+pub trait Repository:
+    PlayerRepository + PokemonRepository + InventoryRepository + SpawnRepository + GuildRepository
+{
+}
+
+impl<T> Repository for T where
+    T: PlayerRepository + PokemonRepository + InventoryRepository + SpawnRepository + GuildRepository
+{
+}
+
+/// A set of changes that are applied together or not at all. Reads see the unit's own changes. Dropping it without
+/// committing discards every change.
+#[async_trait]
+pub trait UnitOfWork: Repository + Sized {
+    /// Applies every change made through this unit. Fails with [`Error::WriteConflict`], or a MongoDB transient
+    /// transaction error, if a concurrent unit changed the same documents first; the whole operation can be retried.
+    async fn commit(self) -> Result<(), Error>;
+}
+
+/// A repository that can group changes into a [`UnitOfWork`].
+#[async_trait]
+pub trait Transactional: Repository {
+    type UnitOfWork: UnitOfWork;
+
+    async fn begin(&self) -> Result<Self::UnitOfWork, Error>;
+}
@@ -0,0 +1,429 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use futures::stream::TryStreamExt;
+use mongodb::{
+    bson::{self, doc, oid::ObjectId, Bson, Document},
+    options::FindOptions,
+    Client, ClientSession,
+};
+use tokio::sync::Mutex;
+
+use super::{
+    GuildRepository, InventoryRepository, PlayerRepository, PokemonQueryRepository, PokemonRepository, SpawnRepository,
+    Transactional, UnitOfWork,
+};
+use crate::database::{
+    query::{Cursor, Page, PokemonFilter, PokemonQuery},
+    error::{ServerErrorExt, DUPLICATE_KEY},
+    records::{GuildSettings, Inventory, OwnedPokemon, Player, Spawn},
+    Database, Error, TypedCollection,
+};
+
+/// Repository backed by the MongoDB [`Database`].
+#[derive(Debug, Clone)]
+pub struct MongoRepository {
+    client: Client,
+    players: TypedCollection<Player>,
+    pokemon: TypedCollection<OwnedPokemon>,
+    inventories: TypedCollection<Inventory>,
+    spawns: TypedCollection<Spawn>,
+    guilds: TypedCollection<GuildSettings>,
+}
+
+impl MongoRepository {
+    pub fn new(db: &Database) -> MongoRepository {
+        MongoRepository {
+            client: db.client().clone(),
+            players: db.collection(),
+            pokemon: db.collection(),
+            inventories: db.collection(),
+            spawns: db.collection(),
+            guilds: db.collection(),
+        }
+    }
+}
+
+#[async_trait]
+impl PlayerRepository for MongoRepository {
+    async fn get_player(&self, id: &str) -> Result<Option<Player>, Error> {
+        self.players.find_one(doc! { "_id": id }).await
+    }
+
+    async fn insert_player(&self, player: &Player) -> Result<(), Error> {
+        match self.players.insert_one(player).await {
+            Err(Error::Mongo(err)) if err.is_server_error(DUPLICATE_KEY) => Err(Error::AlreadyExists(player.id.clone())),
+            result => result,
+        }
+    }
+
+    async fn update_player(&self, player: &Player) -> Result<(), Error> {
+        self.players.upsert(doc! { "_id": &player.id }, player).await
+    }
+
+    async fn claim_experience_cooldown(&self, id: &str, now: DateTime<Utc>, cooldown: Duration) -> Result<bool, Error> {
+        let (filter, update) = experience_cooldown_claim(id, now, cooldown);
+        Ok(self.players.update_one(filter, update).await?.matched_count > 0)
+    }
+}
+
+/// Filter and update that set `last_experience_at` to `now` if it's unset or at least `cooldown` earlier.
+fn experience_cooldown_claim(id: &str, now: DateTime<Utc>, cooldown: Duration) -> (Document, Document) {
+    let filter = doc! {
+        "_id": id,
+        "$or": [
+            { "last_experience_at": null },
+            { "last_experience_at": { "$lte": now - cooldown } },
+        ],
+    };
+    (filter, doc! { "$set": { "last_experience_at": now } })
+}
+
+#[async_trait]
+impl PokemonRepository for MongoRepository {
+    async fn get_pokemon(&self, id: ObjectId) -> Result<Option<OwnedPokemon>, Error> {
+        self.pokemon.find_one(doc! { "_id": id }).await
+    }
+
+    async fn pokemon_owned_by(&self, owner: &str) -> Result<Vec<OwnedPokemon>, Error> {
+        let options = FindOptions::builder().sort(doc! { "caught_at": 1 }).build();
+        self.pokemon.find(doc! { "owner": owner }, options).await
+    }
+
+    async fn insert_pokemon(&self, pokemon: &OwnedPokemon) -> Result<(), Error> {
+        self.pokemon.insert_one(pokemon).await
+    }
+
+    async fn update_pokemon(&self, pokemon: &OwnedPokemon) -> Result<(), Error> {
+        self.pokemon.upsert(doc! { "_id": pokemon.id }, pokemon).await
+    }
+
+    async fn delete_pokemon(&self, id: ObjectId) -> Result<bool, Error> {
+        self.pokemon.delete_one(doc! { "_id": id }).await
+    }
+}
+
+/// Escapes `text` so that it matches literally in a regex.
+fn escape_regex(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\^$.|?*+()[]{}".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Conditions on stored fields for everything in `filter` except IVs, which are matched after computing their total.
+fn filter_document(owner: &str, filter: &PokemonFilter) -> Document {
+    let mut conditions = doc! { "owner": owner };
+    if let Some(species) = &filter.species {
+        conditions.insert("species_name", species);
+    }
+    if let Some(type_name) = &filter.type_name {
+        conditions.insert("types", type_name);
+    }
+    if let Some(shiny) = filter.shiny {
+        conditions.insert("shiny", shiny);
+    }
+    if let Some(legendary) = filter.legendary {
+        conditions.insert("legendary", legendary);
+    }
+    let mut level = Document::new();
+    if let Some(min) = filter.min_level {
+        level.insert("$gte", min as i64);
+    }
+    if let Some(max) = filter.max_level {
+        level.insert("$lte", max as i64);
+    }
+    if !level.is_empty() {
+        conditions.insert("level", level);
+    }
+    if let Some(nickname) = &filter.nickname {
+        conditions.insert("nickname", doc! { "$regex": escape_regex(nickname), "$options": "i" });
+    }
+    conditions
+}
+
+#[async_trait]
+impl PokemonQueryRepository for MongoRepository {
+    async fn query_pokemon(&self, owner: &str, query: &PokemonQuery) -> Result<Page<OwnedPokemon>, Error> {
+        let direction = if query.descending { -1 } else { 1 };
+        let mut iv_total = Document::new();
+        if let Some(min) = query.filter.min_iv_total() {
+            iv_total.insert("$gte", min as i64);
+        }
+        if let Some(max) = query.filter.max_iv_total() {
+            iv_total.insert("$lte", max as i64);
+        }
+
+        let mut pipeline = vec![
+            doc! { "$match": filter_document(owner, &query.filter) },
+            doc! {
+                "$addFields": {
+                    "iv_total": {
+                        "$add": [
+                            "$ivs.hp", "$ivs.attack", "$ivs.defense",
+                            "$ivs.special_attack", "$ivs.special_defense", "$ivs.speed",
+                        ]
+                    }
+                }
+            },
+            // Every sort key becomes a number, matching the cursor's value; dates become milliseconds
+            doc! { "$addFields": { "sort_value": { "$toLong": format!("${}", query.sort.field()) } } },
+        ];
+        if !iv_total.is_empty() {
+            pipeline.push(doc! { "$match": { "iv_total": iv_total } });
+        }
+        if let Some(Cursor { value, id, .. }) = query.after {
+            let after = if query.descending { "$lt" } else { "$gt" };
+            pipeline.push(doc! {
+                "$match": {
+                    "$or": [
+                        { "sort_value": { after: Bson::Int64(value) } },
+                        { "sort_value": Bson::Int64(value), "_id": { after: id } },
+                    ]
+                }
+            });
+        }
+        pipeline.push(doc! { "$sort": { "sort_value": direction, "_id": direction } });
+        // One extra to tell whether there is another page
+        pipeline.push(doc! { "$limit": query.limit as i64 + 1 });
+
+        let mut cursor = self.pokemon.inner().aggregate(pipeline, None).await?;
+        let mut items = Vec::new();
+        while let Some(document) = cursor.try_next().await? {
+            items.push(bson::from_document::<OwnedPokemon>(document)?);
+        }
+        let has_more = items.len() > query.limit;
+        items.truncate(query.limit);
+        Ok(Page {
+            next: items.last().filter(|_| has_more).map(|p| query.cursor_at(p)),
+            items,
+        })
+    }
+}
+
+#[async_trait]
+impl InventoryRepository for MongoRepository {
+    async fn get_inventory(&self, owner: &str) -> Result<Inventory, Error> {
+        Ok(self
+            .inventories
+            .find_one(doc! { "_id": owner })
+            .await?
+            .unwrap_or_else(|| Inventory::new(owner)))
+    }
+
+    async fn save_inventory(&self, inventory: &Inventory) -> Result<(), Error> {
+        self.inventories.upsert(doc! { "_id": &inventory.owner }, inventory).await
+    }
+}
+
+#[async_trait]
+impl SpawnRepository for MongoRepository {
+    async fn active_spawn(&self, channel_id: &str) -> Result<Option<Spawn>, Error> {
+        self.spawns.find_one(doc! { "_id": channel_id }).await
+    }
+
+    async fn put_spawn(&self, spawn: &Spawn) -> Result<(), Error> {
+        self.spawns.upsert(doc! { "_id": &spawn.channel_id }, spawn).await
+    }
+
+    async fn take_spawn(&self, channel_id: &str) -> Result<Option<Spawn>, Error> {
+        self.spawns.find_one_and_delete(doc! { "_id": channel_id }).await
+    }
+}
+
+#[async_trait]
+impl GuildRepository for MongoRepository {
+    async fn get_guild_settings(&self, guild_id: &str) -> Result<GuildSettings, Error> {
+        Ok(self
+            .guilds
+            .find_one(doc! { "_id": guild_id })
+            .await?
+            .unwrap_or_else(|| GuildSettings::new(guild_id)))
+    }
+
+    async fn save_guild_settings(&self, settings: &GuildSettings) -> Result<(), Error> {
+        self.guilds.upsert(doc! { "_id": &settings.guild_id }, settings).await
+    }
+}
+
+#[async_trait]
+impl Transactional for MongoRepository {
+    type UnitOfWork = MongoUnitOfWork;
+
+    async fn begin(&self) -> Result<MongoUnitOfWork, Error> {
+        let mut session = self.client.start_session(None).await?;
+        session.start_transaction(None).await?;
+        Ok(MongoUnitOfWork {
+            session: Mutex::new(session),
+            repo: self.clone(),
+        })
+    }
+}
+
+/// A MongoDB transaction. Transactions need the server to be a replica set.
+pub struct MongoUnitOfWork {
+    /// Held for the duration of each operation, since a session can only run one at a time.
+    session: Mutex<ClientSession>,
+    repo: MongoRepository,
+}
+
+#[async_trait]
+impl UnitOfWork for MongoUnitOfWork {
+    async fn commit(self) -> Result<(), Error> {
+        // Conflicts with concurrent transactions show up here or on the conflicting write, and convert to
+        // `Error::WriteConflict` either way
+        self.session.into_inner().commit_transaction().await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PlayerRepository for MongoUnitOfWork {
+    async fn get_player(&self, id: &str) -> Result<Option<Player>, Error> {
+        let mut session = self.session.lock().await;
+        self.repo
+            .players
+            .find_one_with_session(doc! { "_id": id }, &mut session)
+            .await
+    }
+
+    async fn insert_player(&self, player: &Player) -> Result<(), Error> {
+        let mut session = self.session.lock().await;
+        match self.repo.players.insert_one_with_session(player, &mut session).await {
+            Err(Error::Mongo(err)) if err.is_server_error(DUPLICATE_KEY) => Err(Error::AlreadyExists(player.id.clone())),
+            result => result,
+        }
+    }
+
+    async fn update_player(&self, player: &Player) -> Result<(), Error> {
+        let mut session = self.session.lock().await;
+        self.repo
+            .players
+            .upsert_with_session(doc! { "_id": &player.id }, player, &mut session)
+            .await
+    }
+
+    async fn claim_experience_cooldown(&self, id: &str, now: DateTime<Utc>, cooldown: Duration) -> Result<bool, Error> {
+        let mut session = self.session.lock().await;
+        let (filter, update) = experience_cooldown_claim(id, now, cooldown);
+        let result = self
+            .repo
+            .players
+            .update_one_with_session(filter, update, &mut session)
+            .await?;
+        Ok(result.matched_count > 0)
+    }
+}
+
+#[async_trait]
+impl PokemonRepository for MongoUnitOfWork {
+    async fn get_pokemon(&self, id: ObjectId) -> Result<Option<OwnedPokemon>, Error> {
+        let mut session = self.session.lock().await;
+        self.repo
+            .pokemon
+            .find_one_with_session(doc! { "_id": id }, &mut session)
+            .await
+    }
+
+    async fn pokemon_owned_by(&self, owner: &str) -> Result<Vec<OwnedPokemon>, Error> {
+        let mut session = self.session.lock().await;
+        let options = FindOptions::builder().sort(doc! { "caught_at": 1 }).build();
+        self.repo
+            .pokemon
+            .find_with_session(doc! { "owner": owner }, options, &mut session)
+            .await
+    }
+
+    async fn insert_pokemon(&self, pokemon: &OwnedPokemon) -> Result<(), Error> {
+        let mut session = self.session.lock().await;
+        self.repo.pokemon.insert_one_with_session(pokemon, &mut session).await
+    }
+
+    async fn update_pokemon(&self, pokemon: &OwnedPokemon) -> Result<(), Error> {
+        let mut session = self.session.lock().await;
+        self.repo
+            .pokemon
+            .upsert_with_session(doc! { "_id": pokemon.id }, pokemon, &mut session)
+            .await
+    }
+
+    async fn delete_pokemon(&self, id: ObjectId) -> Result<bool, Error> {
+        let mut session = self.session.lock().await;
+        self.repo
+            .pokemon
+            .delete_one_with_session(doc! { "_id": id }, &mut session)
+            .await
+    }
+}
+
+#[async_trait]
+impl InventoryRepository for MongoUnitOfWork {
+    async fn get_inventory(&self, owner: &str) -> Result<Inventory, Error> {
+        let mut session = self.session.lock().await;
+        Ok(self
+            .repo
+            .inventories
+            .find_one_with_session(doc! { "_id": owner }, &mut session)
+            .await?
+            .unwrap_or_else(|| Inventory::new(owner)))
+    }
+
+    async fn save_inventory(&self, inventory: &Inventory) -> Result<(), Error> {
+        let mut session = self.session.lock().await;
+        self.repo
+            .inventories
+            .upsert_with_session(doc! { "_id": &inventory.owner }, inventory, &mut session)
+            .await
+    }
+}
+
+#[async_trait]
+impl SpawnRepository for MongoUnitOfWork {
+    async fn active_spawn(&self, channel_id: &str) -> Result<Option<Spawn>, Error> {
+        let mut session = self.session.lock().await;
+        self.repo
+            .spawns
+            .find_one_with_session(doc! { "_id": channel_id }, &mut session)
+            .await
+    }
+
+    async fn put_spawn(&self, spawn: &Spawn) -> Result<(), Error> {
+        let mut session = self.session.lock().await;
+        self.repo
+            .spawns
+            .upsert_with_session(doc! { "_id": &spawn.channel_id }, spawn, &mut session)
+            .await
+    }
+
+    async fn take_spawn(&self, channel_id: &str) -> Result<Option<Spawn>, Error> {
+        let mut session = self.session.lock().await;
+        self.repo
+            .spawns
+            .find_one_and_delete_with_session(doc! { "_id": channel_id }, &mut session)
+            .await
+    }
+}
+
+#[async_trait]
+impl GuildRepository for MongoUnitOfWork {
+    async fn get_guild_settings(&self, guild_id: &str) -> Result<GuildSettings, Error> {
+        let mut session = self.session.lock().await;
+        Ok(self
+            .repo
+            .guilds
+            .find_one_with_session(doc! { "_id": guild_id }, &mut session)
+            .await?
+            .unwrap_or_else(|| GuildSettings::new(guild_id)))
+    }
+
+    async fn save_guild_settings(&self, settings: &GuildSettings) -> Result<(), Error> {
+        let mut session = self.session.lock().await;
+        self.repo
+            .guilds
+            .upsert_with_session(doc! { "_id": &settings.guild_id }, settings, &mut session)
+            .await
+    }
+}
@@ -0,0 +1,17 @@
+//! Repositories set up for tests of game logic.
+
+use super::{
+    records::Inventory,
+    repository::{InventoryRepository, MemoryRepository},
+};
+
+/// A repository where `ash` has `currency` and no items.
+pub async fn repo_with_currency(currency: i64) -> MemoryRepository {
+    let repo = MemoryRepository::new();
+    let inventory = Inventory {
+        currency,
+        ..Inventory::new("ash")
+    };
+    repo.save_inventory(&inventory).await.unwrap();
+    repo
+}
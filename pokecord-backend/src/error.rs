@@ -0,0 +1,24 @@
+use pyo3::prelude::*;
+
+use crate::{battle, database, pokedex};
+
+/// Error from game logic that uses both Pokemon data and the database.
+#[derive(thiserror::Error, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Pokedex(#[from] pokedex::Error),
+    #[error(transparent)]
+    Database(#[from] database::Error),
+    #[error(transparent)]
+    Battle(#[from] battle::Error),
+}
+
+impl From<Error> for PyErr {
+    fn from(err: Error) -> PyErr {
+        match err {
+            Error::Pokedex(err) => err.into(),
+            Error::Database(err) => err.into(),
+            Error::Battle(err) => err.into(),
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use crate::database::{
+    operations::retry_on_conflict,
+    records::{GuildSettings, ProgressScope},
+    repository::{GuildRepository, Transactional, UnitOfWork},
+    Error,
+};
+
+/// Longest allowed command prefix.
+const MAX_PREFIX_LEN: usize = 5;
+
+/// Changes to a guild's settings. Fields left as `None` keep their current value.
+#[derive(Debug, Clone, Default)]
+pub struct SettingsUpdate {
+    /// New spawn channel. `Some(None)` clears it.
+    pub spawn_channel: Option<Option<String>>,
+    pub spawn_rate: Option<u32>,
+    pub language: Option<String>,
+    pub prefix: Option<String>,
+    pub progress_scope: Option<ProgressScope>,
+}
+
+impl SettingsUpdate {
+    /// Fails with [`Error::InvalidSetting`] if any new value isn't allowed. `languages` are the PokeAPI language names
+    /// the guild may choose from.
+    fn validate(&self, languages: &[String]) -> Result<(), Error> {
+        if self.spawn_rate == Some(0) {
+            return Err(Error::InvalidSetting("spawn rate must be at least 1".to_string()));
+        }
+        if let Some(language) = &self.language {
+            if !languages.contains(language) {
+                return Err(Error::InvalidSetting(format!("unknown language {}", language)));
+            }
+        }
+        if let Some(prefix) = &self.prefix {
+            if prefix.is_empty() || prefix.chars().count() > MAX_PREFIX_LEN || prefix.chars().any(char::is_whitespace) {
+                return Err(Error::InvalidSetting(format!(
+                    "prefix must be 1 to {} characters without spaces",
+                    MAX_PREFIX_LEN
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets every field of `settings` this update changes.
+    fn apply(&self, settings: &mut GuildSettings) {
+        if let Some(spawn_channel) = &self.spawn_channel {
+            settings.spawn_channel = spawn_channel.clone();
+        }
+        if let Some(spawn_rate) = self.spawn_rate {
+            settings.spawn_rate = spawn_rate;
+        }
+        if let Some(language) = &self.language {
+            settings.language = language.clone();
+        }
+        if let Some(prefix) = &self.prefix {
+            settings.prefix = prefix.clone();
+        }
+        if let Some(progress_scope) = self.progress_scope {
+            settings.progress_scope = progress_scope;
+        }
+    }
+}
+
+/// Validates and applies `update`. `languages` are the PokeAPI language names the guild may choose from. Settings
+/// the update leaves out keep whatever value they have when it's applied, even if another update changed them since.
+pub async fn update_settings<R: Transactional>(
+    repo: &R,
+    guild_id: &str,
+    update: SettingsUpdate,
+    languages: &[String],
+) -> Result<GuildSettings, Error> {
+    update.validate(languages)?;
+    retry_on_conflict(|| try_update_settings(repo, guild_id, &update)).await
+}
+
+async fn try_update_settings<R: Transactional>(
+    repo: &R,
+    guild_id: &str,
+    update: &SettingsUpdate,
+) -> Result<GuildSettings, Error> {
+    let work = repo.begin().await?;
+    let mut settings = work.get_guild_settings(guild_id).await?;
+    update.apply(&mut settings);
+    work.save_guild_settings(&settings).await?;
+    work.commit().await?;
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::repository::MemoryRepository;
+
+    fn languages() -> Vec<String> {
+        vec!["en".to_string(), "fr".to_string()]
+    }
+
+    async fn update(repo: &MemoryRepository, update: SettingsUpdate) -> Result<GuildSettings, Error> {
+        update_settings(repo, "kanto", update, &languages()).await
+    }
+
+    /// Asserts that `change` is rejected and leaves the stored settings alone.
+    async fn assert_invalid(change: SettingsUpdate) {
+        let repo = MemoryRepository::new();
+        let result = update(&repo, change).await;
+        assert!(matches!(result, Err(Error::InvalidSetting(_))), "{:?}", result);
+        assert_eq!(
+            repo.get_guild_settings("kanto").await.unwrap(),
+            GuildSettings::new("kanto")
+        );
+    }
+
+    #[tokio::test]
+    async fn updates_only_change_the_given_settings() {
+        let repo = MemoryRepository::new();
+        let channel = SettingsUpdate {
+            spawn_channel: Some(Some("route-1".to_string())),
+            ..SettingsUpdate::default()
+        };
+        update(&repo, channel).await.unwrap();
+        let rest = SettingsUpdate {
+            spawn_rate: Some(5),
+            language: Some("fr".to_string()),
+            prefix: Some("p!".to_string()),
+            progress_scope: Some(ProgressScope::Guild),
+            ..SettingsUpdate::default()
+        };
+        let settings = update(&repo, rest).await.unwrap();
+
+        let expected = GuildSettings {
+            spawn_channel: Some("route-1".to_string()),
+            spawn_rate: 5,
+            language: "fr".to_string(),
+            prefix: "p!".to_string(),
+            progress_scope: ProgressScope::Guild,
+            ..GuildSettings::new("kanto")
+        };
+        assert_eq!(settings, expected);
+        assert_eq!(repo.get_guild_settings("kanto").await.unwrap(), expected);
+    }
+
+    #[tokio::test]
+    async fn spawn_channels_can_be_cleared() {
+        let repo = MemoryRepository::new();
+        let set = SettingsUpdate {
+            spawn_channel: Some(Some("route-1".to_string())),
+            ..SettingsUpdate::default()
+        };
+        update(&repo, set).await.unwrap();
+        let clear = SettingsUpdate {
+            spawn_channel: Some(None),
+            ..SettingsUpdate::default()
+        };
+        assert_eq!(update(&repo, clear).await.unwrap().spawn_channel, None);
+    }
+
+    #[tokio::test]
+    async fn spawn_rate_must_be_positive() {
+        assert_invalid(SettingsUpdate {
+            spawn_rate: Some(0),
+            ..SettingsUpdate::default()
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn prefixes_must_be_short_and_without_spaces() {
+        for prefix in &["", "pokemon!", "p !", "\t"] {
+            assert_invalid(SettingsUpdate {
+                prefix: Some(prefix.to_string()),
+                ..SettingsUpdate::default()
+            })
+            .await;
+        }
+        // Length is counted in characters, not bytes
+        let repo = MemoryRepository::new();
+        let prefix = SettingsUpdate {
+            prefix: Some("ポケモン!".to_string()),
+            ..SettingsUpdate::default()
+        };
+        assert_eq!(update(&repo, prefix).await.unwrap().prefix, "ポケモン!");
+    }
+
+    #[tokio::test]
+    async fn languages_must_be_known() {
+        assert_invalid(SettingsUpdate {
+            language: Some("tlh".to_string()),
+            ..SettingsUpdate::default()
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn invalid_updates_change_nothing() {
+        // The spawn rate is valid, but the whole update is rejected with the prefix
+        assert_invalid(SettingsUpdate {
+            spawn_rate: Some(5),
+            prefix: Some("way too long".to_string()),
+            ..SettingsUpdate::default()
+        })
+        .await;
+    }
+}
@@ -0,0 +1,122 @@
+//! The `guilds` module contains per-server configuration: where and how often
+//! pokemon spawn, which language they are shown in, the command prefix, and
+//! whether players' progress is shared with other servers.
+
+use crate::database::{self, records::ProgressScope, repository::GuildRepository, MongoRepository};
+use crate::models::GuildSettings;
+use crate::pokedex::{Language, Pokedex};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3_asyncio::tokio as pytokio;
+
+mod handlers;
+
+// Adds all required functions into the module.
+pub fn init_submodule(module: &PyModule) -> PyResult<()> {
+    module.add_function(pyo3::wrap_pyfunction!(get_guild_settings, module)?)?;
+    module.add_function(pyo3::wrap_pyfunction!(update_guild_settings, module)?)?;
+    Ok(())
+}
+
+/// The key a player's records are stored under when they play in `guild_id`, which depends on the guild's progress
+/// scope. Outside any guild, e.g. in DMs, this is always their global key.
+pub(crate) async fn player_key<R: GuildRepository>(
+    repo: &R,
+    guild_id: Option<&str>,
+    player_id: &str,
+) -> Result<String, database::Error> {
+    match guild_id {
+        Some(guild_id) => Ok(repo.get_guild_settings(guild_id).await?.player_key(player_id)),
+        None => Ok(player_id.to_string()),
+    }
+}
+
+/// The languages to show text in: `languages` if the caller chose some, otherwise the guild's language setting.
+pub(crate) async fn languages<R: GuildRepository>(
+    repo: &R,
+    guild_id: Option<&str>,
+    languages: Option<Vec<String>>,
+) -> Result<Vec<String>, database::Error> {
+    match (languages, guild_id) {
+        (Some(languages), _) => Ok(languages),
+        (None, Some(guild_id)) => Ok(vec![repo.get_guild_settings(guild_id).await?.language]),
+        (None, None) => Ok(Vec::new()),
+    }
+}
+
+/// Fetches a guild's settings.
+///
+/// # Returns
+///
+/// The guild's `GuildSettings`, which are the defaults if it never changed
+/// any.
+#[pyfunction]
+#[text_signature = "(guild_id, /)"]
+fn get_guild_settings(py: Python, guild_id: String) -> PyResult<PyObject> {
+    pytokio::into_coroutine(py, async move {
+        let repo = MongoRepository::new(database::get()?);
+        let settings = GuildSettings::from(repo.get_guild_settings(&guild_id).await?);
+        Ok(Python::with_gil(|py| settings.into_py(py)))
+    })
+}
+
+/// Changes some of a guild's settings. Settings that aren't given keep their
+/// current value.
+///
+/// # Arguments
+///
+/// * `spawn_channel` - ID of the channel pokemon spawn in. An empty string
+///   lets them spawn in any channel again.
+/// * `spawn_rate` - Average number of messages between spawns.
+/// * `language` - PokeAPI language name, e.g. `fr`.
+/// * `prefix` - Command prefix, up to 5 characters.
+/// * `progress_scope` - `"global"` to share players' progress with other
+///   servers, or `"guild"` to keep it separate.
+///
+/// # Returns
+///
+/// The updated `GuildSettings`. Raises `ValueError` for invalid settings.
+#[pyfunction]
+#[text_signature = "(guild_id, spawn_channel=None, spawn_rate=None, language=None, prefix=None, progress_scope=None, /)"]
+fn update_guild_settings(
+    py: Python,
+    guild_id: String,
+    spawn_channel: Option<String>,
+    spawn_rate: Option<u32>,
+    language: Option<String>,
+    prefix: Option<String>,
+    progress_scope: Option<String>,
+) -> PyResult<PyObject> {
+    let progress_scope = match progress_scope.as_deref() {
+        None => None,
+        Some("global") => Some(ProgressScope::Global),
+        Some("guild") => Some(ProgressScope::Guild),
+        Some(other) => {
+            return Err(PyValueError::new_err(format!(
+                "progress_scope must be \"global\" or \"guild\", not {:?}",
+                other
+            )))
+        }
+    };
+    let update = handlers::SettingsUpdate {
+        spawn_channel: spawn_channel.map(|channel| Some(channel).filter(|c| !c.is_empty())),
+        spawn_rate,
+        language,
+        prefix,
+        progress_scope,
+    };
+    pytokio::into_coroutine(py, async move {
+        let languages = match update.language {
+            Some(_) => Pokedex::new()
+                .list::<Language>()
+                .await?
+                .into_iter()
+                .map(|l| l.name)
+                .collect(),
+            None => Vec::new(),
+        };
+        let repo = MongoRepository::new(database::get()?);
+        let settings = handlers::update_settings(&repo, &guild_id, update, &languages).await?;
+        Ok(Python::with_gil(|py| GuildSettings::from(settings).into_py(py)))
+    })
+}
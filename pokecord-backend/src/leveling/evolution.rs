@@ -0,0 +1,348 @@
+//! Checking which species an owned pokemon can evolve into along its PokeAPI evolution chain, and evolving it.
+
+use std::ops::Range;
+
+use chrono::{DateTime, Timelike, Utc};
+
+use crate::database::records::{self, Inventory, OwnedPokemon};
+use crate::pokedex::{self, ChainLink, EvolutionDetail, NamedResource, PokedexSource, Pokemon, PokemonSpecies};
+
+/// Held item that stops a pokemon from evolving automatically.
+pub const EVERSTONE: &str = "everstone";
+
+/// PokeAPI names of the evolution triggers pokecord supports. Others, like trading, can't happen in pokecord.
+const LEVEL_UP: &str = "level-up";
+const USE_ITEM: &str = "use-item";
+
+/// Hours of the day, in UTC, that count as day for time-based evolutions. The rest of the day is night.
+const DAY_HOURS: Range<u32> = 6..18;
+
+/// What is making a pokemon evolve.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Trigger<'a> {
+    LevelUp,
+    /// Using the item with this PokeAPI name from the player's inventory.
+    UseItem(&'a str),
+}
+
+/// A species a pokemon can evolve into, and the conditions it met.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate {
+    pub species: NamedResource<PokemonSpecies>,
+    pub detail: EvolutionDetail,
+}
+
+/// Every species `pokemon` can evolve into through `trigger` at `now`, in evolution chain order.
+pub async fn available_evolutions<P: PokedexSource>(
+    pokedex: &mut P,
+    pokemon: &OwnedPokemon,
+    inventory: &Inventory,
+    trigger: Trigger<'_>,
+    now: DateTime<Utc>,
+) -> Result<Vec<Candidate>, pokedex::Error> {
+    let species: PokemonSpecies = pokedex.get_by_id(pokemon.species_id as usize).await?;
+    let chain = species.evolution_chain.resolve(pokedex).await?;
+    let link = match find_link(&chain.chain, species.id) {
+        Some(link) => link,
+        None => return Ok(Vec::new()),
+    };
+    Ok(link
+        .evolves_to
+        .iter()
+        .filter_map(|next| {
+            let detail = next
+                .evolution_details
+                .iter()
+                .find(|detail| conditions_met(detail, pokemon, inventory, trigger, now))?;
+            Some(Candidate {
+                species: next.species.clone(),
+                detail: detail.clone(),
+            })
+        })
+        .collect())
+}
+
+/// Whether `pokemon` still meets the conditions it met to evolve into `candidate`, e.g. after being read again.
+pub fn still_available(
+    candidate: &Candidate,
+    pokemon: &OwnedPokemon,
+    inventory: &Inventory,
+    trigger: Trigger,
+    now: DateTime<Utc>,
+) -> bool {
+    conditions_met(&candidate.detail, pokemon, inventory, trigger, now)
+}
+
+/// Whether `pokemon` shouldn't evolve without the player asking it to.
+pub fn is_blocked(pokemon: &OwnedPokemon) -> bool {
+    pokemon.evolution_blocked || pokemon.held_item.as_deref() == Some(EVERSTONE)
+}
+
+/// Turns `pokemon` into `variety` of `species`. Its IVs, nature, nickname, shininess, level and experience stay the
+/// same. A held item the evolution needed is used up. The evolution is added to the pokemon's history and returned.
+pub fn evolve(
+    pokemon: &mut OwnedPokemon,
+    candidate: &Candidate,
+    species: &PokemonSpecies,
+    variety: &Pokemon,
+    now: DateTime<Utc>,
+) -> records::Evolution {
+    let evolution = records::Evolution {
+        from_species_id: pokemon.species_id,
+        from_species_name: pokemon.species_name.clone(),
+        to_species_id: species.id as u32,
+        to_species_name: species.name.clone(),
+        level: pokemon.level,
+        evolved_at: now.into(),
+    };
+    if candidate.detail.held_item.is_some() {
+        pokemon.held_item = None;
+    }
+    pokemon.species_id = species.id as u32;
+    pokemon.species_name = species.name.clone();
+    pokemon.variety_id = variety.id as u32;
+    pokemon.types = variety.types.iter().map(|t| t.typ.name.clone()).collect();
+    pokemon.legendary = species.is_legendary || species.is_mythical;
+    pokemon.evolutions.push(evolution.clone());
+    evolution
+}
+
+/// The link for species `species_id` in the chain starting at `link`.
+fn find_link(link: &ChainLink, species_id: usize) -> Option<&ChainLink> {
+    if link.species.id() == Some(species_id) {
+        return Some(link);
+    }
+    link.evolves_to.iter().find_map(|next| find_link(next, species_id))
+}
+
+/// Whether every condition of `detail` holds. Conditions pokecord has no equivalent of, such as locations or known
+/// moves, never hold.
+fn conditions_met(
+    detail: &EvolutionDetail,
+    pokemon: &OwnedPokemon,
+    inventory: &Inventory,
+    trigger: Trigger,
+    now: DateTime<Utc>,
+) -> bool {
+    let trigger_met = match (detail.trigger.name.as_str(), trigger) {
+        (LEVEL_UP, Trigger::LevelUp) => detail.item.is_none(),
+        (USE_ITEM, Trigger::UseItem(item)) => {
+            detail.item.as_ref().map(|i| i.name.as_str()) == Some(item)
+                && inventory.items.get(item).copied().unwrap_or(0) > 0
+        }
+        _ => false,
+    };
+    let unsupported = detail.gender.is_some()
+        || detail.known_move.is_some()
+        || detail.known_move_type.is_some()
+        || detail.location.is_some()
+        || detail.min_beauty.is_some()
+        || detail.needs_overworld_rain
+        || detail.party_species.is_some()
+        || detail.party_type.is_some()
+        || detail.relative_physical_stats.is_some()
+        || detail.trade_species.is_some()
+        || detail.turn_upside_down;
+    let friendship = detail.min_happiness.into_iter().chain(detail.min_affection).max();
+    let time_met = match detail.time_of_day.as_str() {
+        "" => true,
+        "day" => DAY_HOURS.contains(&now.hour()),
+        "night" => !DAY_HOURS.contains(&now.hour()),
+        _ => false,
+    };
+
+    trigger_met
+        && !unsupported
+        && time_met
+        && detail.min_level.into_iter().all(|level| pokemon.level >= level)
+        && friendship.into_iter().all(|min| pokemon.friendship >= min)
+        && detail
+            .held_item
+            .iter()
+            .all(|item| pokemon.held_item.as_deref() == Some(item.name.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::pokedex::test_data::{self, evolution_detail, item};
+
+    const NOON: u32 = 12;
+    const MIDNIGHT: u32 = 0;
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2021, 6, 1, hour, 0, 0).unwrap()
+    }
+
+    fn charmander(level: u32) -> OwnedPokemon {
+        OwnedPokemon::generate("ash", 4, "charmander", 4, level, &mut rand::thread_rng())
+    }
+
+    fn inventory(items: &[&str]) -> Inventory {
+        Inventory {
+            items: items.iter().map(|item| (item.to_string(), 1)).collect(),
+            ..Inventory::new("ash")
+        }
+    }
+
+    /// Whether `pokemon` meets `detail` by leveling up at noon with nothing in its trainer's inventory.
+    fn levels_up(detail: &EvolutionDetail, pokemon: &OwnedPokemon) -> bool {
+        conditions_met(detail, pokemon, &inventory(&[]), Trigger::LevelUp, at(NOON))
+    }
+
+    #[test]
+    fn minimum_level() {
+        let detail = EvolutionDetail {
+            min_level: Some(16),
+            ..evolution_detail(LEVEL_UP)
+        };
+        assert!(!levels_up(&detail, &charmander(15)));
+        assert!(levels_up(&detail, &charmander(16)));
+        assert!(levels_up(&detail, &charmander(40)));
+    }
+
+    #[test]
+    fn minimum_friendship() {
+        let detail = EvolutionDetail {
+            min_happiness: Some(220),
+            ..evolution_detail(LEVEL_UP)
+        };
+        let mut pokemon = charmander(20);
+        pokemon.friendship = 219;
+        assert!(!levels_up(&detail, &pokemon));
+        pokemon.friendship = 220;
+        assert!(levels_up(&detail, &pokemon));
+    }
+
+    #[test]
+    fn held_item() {
+        let detail = EvolutionDetail {
+            held_item: Some(item("metal-coat")),
+            ..evolution_detail(LEVEL_UP)
+        };
+        let mut pokemon = charmander(20);
+        assert!(!levels_up(&detail, &pokemon));
+        pokemon.held_item = Some("everstone".to_string());
+        assert!(!levels_up(&detail, &pokemon));
+        pokemon.held_item = Some("metal-coat".to_string());
+        assert!(levels_up(&detail, &pokemon));
+    }
+
+    #[test]
+    fn item_use() {
+        let detail = EvolutionDetail {
+            item: Some(item("fire-stone")),
+            ..evolution_detail(USE_ITEM)
+        };
+        let pokemon = charmander(20);
+        let use_item = |items: &[&str], item| {
+            conditions_met(&detail, &pokemon, &inventory(items), Trigger::UseItem(item), at(NOON))
+        };
+        assert!(use_item(&["fire-stone"], "fire-stone"));
+        assert!(!use_item(&[], "fire-stone"));
+        assert!(!use_item(&["fire-stone", "water-stone"], "water-stone"));
+        assert!(!conditions_met(
+            &detail,
+            &pokemon,
+            &inventory(&["fire-stone"]),
+            Trigger::LevelUp,
+            at(NOON)
+        ));
+    }
+
+    #[test]
+    fn leveling_up_never_meets_item_evolutions() {
+        let detail = EvolutionDetail {
+            item: Some(item("fire-stone")),
+            ..evolution_detail(LEVEL_UP)
+        };
+        assert!(!levels_up(&detail, &charmander(20)));
+    }
+
+    #[test]
+    fn time_of_day() {
+        let pokemon = charmander(20);
+        let level_up_at = |time_of_day: &str, hour| {
+            let detail = EvolutionDetail {
+                time_of_day: time_of_day.to_string(),
+                ..evolution_detail(LEVEL_UP)
+            };
+            conditions_met(&detail, &pokemon, &inventory(&[]), Trigger::LevelUp, at(hour))
+        };
+        assert!(level_up_at("day", NOON));
+        assert!(!level_up_at("day", MIDNIGHT));
+        assert!(!level_up_at("night", NOON));
+        assert!(level_up_at("night", MIDNIGHT));
+        assert!(level_up_at("", MIDNIGHT));
+        assert!(!level_up_at("dusk", NOON));
+    }
+
+    #[test]
+    fn unsupported_conditions_are_never_met() {
+        let detail = EvolutionDetail {
+            needs_overworld_rain: true,
+            ..evolution_detail(LEVEL_UP)
+        };
+        assert!(!levels_up(&detail, &charmander(20)));
+    }
+
+    #[test]
+    fn everstones_and_blocking_stop_automatic_evolution() {
+        let mut pokemon = charmander(20);
+        assert!(!is_blocked(&pokemon));
+        pokemon.held_item = Some(EVERSTONE.to_string());
+        assert!(is_blocked(&pokemon));
+        pokemon.held_item = None;
+        pokemon.evolution_blocked = true;
+        assert!(is_blocked(&pokemon));
+    }
+
+    #[test]
+    fn evolving_keeps_what_makes_the_pokemon_unique() {
+        let charmeleon = test_data::species(5, "charmeleon", "Charmeleon", &[(5, "charmeleon", true)]);
+        let variety = test_data::pokemon(5, "charmeleon", 5, &["fire"]);
+        let candidate = Candidate {
+            species: test_data::species_ref(5, "charmeleon"),
+            detail: EvolutionDetail {
+                held_item: Some(item("charcoal")),
+                ..evolution_detail(LEVEL_UP)
+            },
+        };
+        let mut pokemon = OwnedPokemon {
+            nickname: Some("Blaze".to_string()),
+            held_item: Some("charcoal".to_string()),
+            ..charmander(16)
+        };
+        let original = pokemon.clone();
+
+        let evolution = evolve(&mut pokemon, &candidate, &charmeleon, &variety, at(NOON));
+        assert_eq!(
+            (pokemon.species_id, pokemon.species_name.as_str(), pokemon.variety_id),
+            (5, "charmeleon", 5)
+        );
+        assert_eq!(pokemon.types, vec!["fire".to_string()]);
+        assert_eq!(pokemon.held_item, None);
+        assert_eq!(
+            (pokemon.ivs, &pokemon.nature, &pokemon.nickname, pokemon.shiny),
+            (original.ivs, &original.nature, &original.nickname, original.shiny)
+        );
+        assert_eq!(
+            (pokemon.level, pokemon.experience),
+            (original.level, original.experience)
+        );
+
+        assert_eq!(pokemon.evolutions, vec![evolution.clone()]);
+        assert_eq!(
+            (evolution.from_species_id, evolution.from_species_name.as_str()),
+            (4, "charmander")
+        );
+        assert_eq!(
+            (evolution.to_species_id, evolution.to_species_name.as_str()),
+            (5, "charmeleon")
+        );
+        assert_eq!(evolution.level, 16);
+        assert_eq!(evolution.evolved_at.to_chrono(), at(NOON));
+    }
+}
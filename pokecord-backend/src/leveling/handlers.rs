@@ -0,0 +1,635 @@
+use chrono::{DateTime, Duration, Utc};
+use mongodb::bson::oid::ObjectId;
+
+use super::evolution::{self, Candidate, Trigger};
+use crate::database::{
+    self,
+    operations::{owned_pokemon, retry_on_conflict},
+    records::{Evolution, Inventory, OwnedPokemon, Player, MAX_FRIENDSHIP, MAX_LEVEL},
+    repository::{InventoryRepository, PlayerRepository, PokemonRepository, Transactional, UnitOfWork},
+};
+use crate::error::Error;
+use crate::models;
+use crate::pokedex::{self, GrowthRate, PokedexSource, Pokemon, PokemonSpecies};
+
+/// Least time between two messages that earn a player experience, so spamming doesn't level up faster.
+const EXPERIENCE_COOLDOWN_SECS: i64 = 30;
+
+/// Pokemon earn experience per message as if they had defeated a wild pokemon of their own species and level, which
+/// is worth this fraction of its base experience per level.
+const EXPERIENCE_LEVEL_DIVISOR: u32 = 7;
+
+/// Friendship gained per level up while friendship is below each threshold. As in the games, friendship grows
+/// slower the higher it already is.
+const FRIENDSHIP_PER_LEVEL: &[(u8, u8)] = &[(100, 5), (200, 3), (MAX_FRIENDSHIP, 2)];
+
+/// Gives experience to the selected pokemon of the player stored under `key` for sending a chat message at `now`,
+/// falling back to their party's lead if they have no pokemon selected. Leveling up can make the pokemon evolve,
+/// unless the player blocked its evolution.
+///
+/// Returns `None` if nothing was gained, because the player messaged too recently, has no pokemon to train, or their
+/// pokemon is already at the highest level. Messages that race another change to the pokemon earn nothing either, as
+/// if they were rate limited.
+pub async fn award_chat_experience<R: Transactional, P: PokedexSource>(
+    repo: &R,
+    pokedex: &mut P,
+    key: &str,
+    now: DateTime<Utc>,
+) -> Result<Option<models::ExperienceGain>, Error> {
+    // Claiming the cooldown before anything else means a burst of messages does the work below once, not once each
+    let cooldown = Duration::seconds(EXPERIENCE_COOLDOWN_SECS);
+    if !repo.claim_experience_cooldown(key, now, cooldown).await? {
+        return Ok(None);
+    }
+    let player = match repo.get_player(key).await? {
+        Some(player) => player,
+        None => return Ok(None),
+    };
+    let original = match player.selected.or_else(|| player.party.first().copied()) {
+        Some(id) => match repo.get_pokemon(id).await? {
+            Some(pokemon) if pokemon.owner == key && pokemon.level < MAX_LEVEL => pokemon,
+            _ => return Ok(None),
+        },
+        None => return Ok(None),
+    };
+
+    // Everything is looked up before the unit of work begins, so that it isn't held open during PokeAPI requests
+    let mut pokemon = original.clone();
+    let variety: Pokemon = pokedex.get_by_id(pokemon.variety_id as usize).await?;
+    let species: PokemonSpecies = pokedex.get_by_id(pokemon.species_id as usize).await?;
+    let growth_rate = pokedex.get_by_ref(&species.growth_rate).await?;
+    let experience = (variety.base_experience.max(1) as u32 * pokemon.level / EXPERIENCE_LEVEL_DIVISOR).max(1);
+    let previous_level = pokemon.level;
+    let levels_gained = add_experience(&mut pokemon, &growth_rate, experience);
+
+    let mut evolution = None;
+    let mut ready_evolutions = Vec::new();
+    if levels_gained > 0 {
+        let inventory = repo.get_inventory(key).await?;
+        let mut candidates =
+            evolution::available_evolutions(pokedex, &pokemon, &inventory, Trigger::LevelUp, now).await?;
+        // Branching evolutions are left for the player to choose
+        if candidates.len() == 1 && !evolution::is_blocked(&pokemon) {
+            let target = Target::resolve(pokedex, &pokemon, candidates.remove(0)).await?;
+            evolution = Some(target.evolve(&mut pokemon, now));
+        } else {
+            ready_evolutions = candidates.into_iter().map(|c| c.species.name).collect();
+        }
+    }
+
+    match save_experience(repo, key, &original, &pokemon, evolution.as_ref()).await {
+        Ok(true) => {}
+        Ok(false) | Err(database::Error::WriteConflict) => return Ok(None),
+        Err(err) => return Err(err.into()),
+    }
+    Ok(Some(models::ExperienceGain {
+        experience,
+        previous_level,
+        evolution: evolution.map(models::Evolution::from),
+        ready_evolutions,
+        pokemon: pokemon.into(),
+    }))
+}
+
+/// Replaces `original` with `trained`, adding the species it evolved into to the player's Pokedex. Returns `false`
+/// without saving anything if the pokemon changed since `original` was read, e.g. because it was traded.
+async fn save_experience<R: Transactional>(
+    repo: &R,
+    key: &str,
+    original: &OwnedPokemon,
+    trained: &OwnedPokemon,
+    evolution: Option<&Evolution>,
+) -> Result<bool, database::Error> {
+    let work = repo.begin().await?;
+    if work.get_pokemon(original.id).await?.as_ref() != Some(original) {
+        return Ok(false);
+    }
+    if let Some(evolution) = evolution {
+        let mut player = work
+            .get_player(key)
+            .await?
+            .ok_or_else(|| database::Error::NotRegistered(key.to_string()))?;
+        add_to_pokedex(&mut player, evolution);
+        work.update_player(&player).await?;
+    }
+    work.update_pokemon(trained).await?;
+    work.commit().await?;
+    Ok(true)
+}
+
+/// Evolves one of the player's pokemon, using `item` from their inventory if given or else leveling up, into the
+/// species named `into`. `into` may be left out if the pokemon can only evolve into one species. Pokemon whose
+/// evolution is blocked can still be evolved this way.
+pub async fn evolve_pokemon<R: Transactional, P: PokedexSource>(
+    repo: &R,
+    pokedex: &mut P,
+    key: &str,
+    id: ObjectId,
+    item: Option<&str>,
+    into: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<OwnedPokemon, Error> {
+    // Everything is looked up before the unit of work begins, so that it isn't held open during PokeAPI requests.
+    // The unit checks the conditions again, in case the pokemon or inventory changed in the meantime.
+    let pokemon = owned_pokemon(repo, key, id).await?;
+    let inventory = repo.get_inventory(key).await?;
+    let trigger = evolution_trigger(&inventory, item)?;
+    let mut candidates = evolution::available_evolutions(pokedex, &pokemon, &inventory, trigger, now).await?;
+    if let Some(into) = into {
+        candidates.retain(|candidate| candidate.species.name == into);
+    }
+    let candidate = match candidates.len() {
+        0 => {
+            let reason = format!("{} doesn't meet the conditions to evolve", pokemon.species_name);
+            return Err(database::Error::CannotEvolve(reason).into());
+        }
+        1 => candidates.remove(0),
+        _ => {
+            let names: Vec<_> = candidates.into_iter().map(|c| c.species.name).collect();
+            let reason = format!("choose which species to evolve into: {}", names.join(", "));
+            return Err(database::Error::CannotEvolve(reason).into());
+        }
+    };
+
+    let target = Target::resolve(pokedex, &pokemon, candidate).await?;
+    Ok(retry_on_conflict(|| try_evolve_pokemon(repo, key, id, item, &target, now)).await?)
+}
+
+async fn try_evolve_pokemon<R: Transactional>(
+    repo: &R,
+    key: &str,
+    id: ObjectId,
+    item: Option<&str>,
+    target: &Target,
+    now: DateTime<Utc>,
+) -> Result<OwnedPokemon, database::Error> {
+    let work = repo.begin().await?;
+    let mut player = work
+        .get_player(key)
+        .await?
+        .ok_or_else(|| database::Error::NotRegistered(key.to_string()))?;
+    let mut pokemon = owned_pokemon(&work, key, id).await?;
+    let mut inventory = work.get_inventory(key).await?;
+    let trigger = evolution_trigger(&inventory, item)?;
+    if !target.available_to(&pokemon, &inventory, trigger, now) {
+        let reason = format!("{} no longer meets the conditions to evolve", pokemon.species_name);
+        return Err(database::Error::CannotEvolve(reason));
+    }
+
+    let evolution = target.evolve(&mut pokemon, now);
+    add_to_pokedex(&mut player, &evolution);
+    if let Some(item) = item {
+        let count = inventory.items.entry(item.to_string()).or_insert(0);
+        *count -= 1;
+        if *count == 0 {
+            inventory.items.remove(item);
+        }
+        work.save_inventory(&inventory).await?;
+    }
+    work.update_player(&player).await?;
+    work.update_pokemon(&pokemon).await?;
+    work.commit().await?;
+    Ok(pokemon)
+}
+
+/// The trigger for evolving a pokemon with `item` from `inventory`, or by leveling up if there's no item.
+fn evolution_trigger<'a>(inventory: &Inventory, item: Option<&'a str>) -> Result<Trigger<'a>, database::Error> {
+    match item {
+        Some(item) if inventory.items.get(item).copied().unwrap_or(0) == 0 => {
+            Err(database::Error::MissingItem(item.to_string()))
+        }
+        Some(item) => Ok(Trigger::UseItem(item)),
+        None => Ok(Trigger::LevelUp),
+    }
+}
+
+/// Blocks or allows one of the player's pokemon evolving automatically as it levels up.
+pub async fn set_evolution_blocked<R: Transactional>(
+    repo: &R,
+    key: &str,
+    id: ObjectId,
+    blocked: bool,
+) -> Result<OwnedPokemon, database::Error> {
+    let work = repo.begin().await?;
+    let mut pokemon = owned_pokemon(&work, key, id).await?;
+    pokemon.evolution_blocked = blocked;
+    work.update_pokemon(&pokemon).await?;
+    work.commit().await?;
+    Ok(pokemon)
+}
+
+/// Gives one of the player's pokemon an item from their inventory to hold, or takes its item away if `item` is
+/// `None`. Any item it was holding goes back into the inventory.
+pub async fn hold_item<R: Transactional>(
+    repo: &R,
+    key: &str,
+    id: ObjectId,
+    item: Option<&str>,
+) -> Result<OwnedPokemon, database::Error> {
+    let work = repo.begin().await?;
+    let mut pokemon = owned_pokemon(&work, key, id).await?;
+    let mut inventory = work.get_inventory(key).await?;
+    if let Some(previous) = pokemon.held_item.take() {
+        *inventory.items.entry(previous).or_insert(0) += 1;
+    }
+    if let Some(item) = item {
+        match inventory.items.get_mut(item) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ => return Err(database::Error::MissingItem(item.to_string())),
+        }
+        if inventory.items[item] == 0 {
+            inventory.items.remove(item);
+        }
+        pokemon.held_item = Some(item.to_string());
+    }
+    work.save_inventory(&inventory).await?;
+    work.update_pokemon(&pokemon).await?;
+    work.commit().await?;
+    Ok(pokemon)
+}
+
+/// A species a pokemon can evolve into, with everything needed from PokeAPI to evolve it.
+struct Target {
+    /// Species of the pokemon the evolution was found for.
+    from_species_id: u32,
+    candidate: Candidate,
+    species: PokemonSpecies,
+    /// The species' default variety, which the pokemon becomes.
+    variety: Pokemon,
+}
+
+impl Target {
+    /// Looks up the species and default variety of `candidate`, which `pokemon` can evolve into.
+    async fn resolve<P: PokedexSource>(
+        pokedex: &mut P,
+        pokemon: &OwnedPokemon,
+        candidate: Candidate,
+    ) -> Result<Target, Error> {
+        let species = pokedex.get_by_ref(&candidate.species).await?;
+        let variety_ref = &species
+            .varieties
+            .iter()
+            .find(|v| v.is_default)
+            .or_else(|| species.varieties.first())
+            .ok_or_else(|| pokedex::Error::Incomplete(format!("{} has no varieties", species.name)))?
+            .pokemon;
+        let variety = pokedex.get_by_ref(variety_ref).await?;
+        Ok(Target {
+            from_species_id: pokemon.species_id,
+            candidate,
+            species,
+            variety,
+        })
+    }
+
+    /// Whether `pokemon` can still evolve into this species, e.g. after being read again.
+    fn available_to(
+        &self,
+        pokemon: &OwnedPokemon,
+        inventory: &Inventory,
+        trigger: Trigger,
+        now: DateTime<Utc>,
+    ) -> bool {
+        pokemon.species_id == self.from_species_id
+            && evolution::still_available(&self.candidate, pokemon, inventory, trigger, now)
+    }
+
+    fn evolve(&self, pokemon: &mut OwnedPokemon, now: DateTime<Utc>) -> Evolution {
+        evolution::evolve(pokemon, &self.candidate, &self.species, &self.variety, now)
+    }
+}
+
+/// Adds the species a pokemon evolved into to the player's Pokedex.
+fn add_to_pokedex(player: &mut Player, evolution: &Evolution) {
+    player.seen_species.insert(evolution.to_species_id);
+    player.caught_species.insert(evolution.to_species_id);
+}
+
+/// Adds `amount` experience to `pokemon` using its species' growth rate, returning the number of levels gained.
+/// Friendship grows with each level.
+fn add_experience(pokemon: &mut OwnedPokemon, growth_rate: &GrowthRate, amount: u32) -> u32 {
+    let threshold = |level: u32| {
+        growth_rate
+            .levels
+            .iter()
+            .find(|l| l.level == level)
+            .map(|l| l.experience)
+    };
+    let total = threshold(pokemon.level)
+        .unwrap_or(0)
+        .saturating_add(pokemon.experience)
+        .saturating_add(amount);
+
+    let mut gained = 0;
+    while pokemon.level < MAX_LEVEL {
+        match threshold(pokemon.level + 1) {
+            Some(next) if total >= next => {
+                pokemon.level += 1;
+                gained += 1;
+                let &(_, increase) = FRIENDSHIP_PER_LEVEL
+                    .iter()
+                    .find(|&&(below, _)| pokemon.friendship < below)
+                    .unwrap_or(&(MAX_FRIENDSHIP, 0));
+                pokemon.friendship = pokemon.friendship.saturating_add(increase);
+            }
+            _ => break,
+        }
+    }
+    pokemon.experience = match pokemon.level {
+        MAX_LEVEL => 0,
+        level => total - threshold(level).unwrap_or(0),
+    };
+    gained
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::SubsecRound;
+
+    use super::*;
+    use crate::database::repository::MemoryRepository;
+    use crate::pokedex::{test_data, EvolutionDetail, MemoryPokedex};
+
+    /// A Pokedex with charmander, which evolves into charmeleon at level 16, and eevee, which evolves into flareon
+    /// with a fire stone.
+    fn pokedex() -> MemoryPokedex {
+        let mut pokedex = MemoryPokedex::new();
+        for &(id, name) in &[(4, "charmander"), (5, "charmeleon"), (133, "eevee"), (136, "flareon")] {
+            pokedex
+                .insert(&test_data::species(id, name, name, &[(id, name, true)]))
+                .unwrap();
+            pokedex.insert(&test_data::pokemon(id, name, id, &["normal"])).unwrap();
+        }
+        let level_16 = EvolutionDetail {
+            min_level: Some(16),
+            ..test_data::evolution_detail("level-up")
+        };
+        let fire_stone = EvolutionDetail {
+            item: Some(test_data::item("fire-stone")),
+            ..test_data::evolution_detail("use-item")
+        };
+        pokedex
+            .insert(&test_data::evolution_chain(
+                (4, "charmander"),
+                &[(5, "charmeleon", level_16)],
+            ))
+            .unwrap();
+        pokedex
+            .insert(&test_data::evolution_chain(
+                (133, "eevee"),
+                &[(136, "flareon", fire_stone)],
+            ))
+            .unwrap();
+        pokedex.insert(&cubic()).unwrap();
+        pokedex
+    }
+
+    /// A repository where `ash` has `pokemon` selected and the given items.
+    async fn trainer_with(pokemon: &OwnedPokemon, items: &[&str]) -> MemoryRepository {
+        let repo = MemoryRepository::new();
+        let player = Player {
+            selected: Some(pokemon.id),
+            ..Player::new("ash")
+        };
+        let inventory = Inventory {
+            items: items.iter().map(|item| (item.to_string(), 1)).collect(),
+            ..Inventory::new("ash")
+        };
+        repo.insert_player(&player).await.unwrap();
+        repo.insert_pokemon(pokemon).await.unwrap();
+        repo.save_inventory(&inventory).await.unwrap();
+        repo
+    }
+
+    /// A level 15 charmander that levels up with its next message.
+    fn charmander() -> OwnedPokemon {
+        OwnedPokemon {
+            experience: 4096 - 3375 - 50,
+            ..OwnedPokemon::generate("ash", 4, "charmander", 4, 15, &mut rand::thread_rng())
+        }
+    }
+
+    fn eevee() -> OwnedPokemon {
+        OwnedPokemon::generate("ash", 133, "eevee", 133, 20, &mut rand::thread_rng())
+    }
+
+    /// A repository where `ash` has a Pikachu selected.
+    async fn trainer_with_pikachu() -> (MemoryRepository, OwnedPokemon) {
+        let repo = MemoryRepository::new();
+        let pikachu = OwnedPokemon::generate("ash", 25, "pikachu", 25, 5, &mut rand::thread_rng());
+        let player = Player {
+            selected: Some(pikachu.id),
+            ..Player::new("ash")
+        };
+        repo.insert_player(&player).await.unwrap();
+        repo.insert_pokemon(&pikachu).await.unwrap();
+        (repo, pikachu)
+    }
+
+    /// Reaching each level takes its cube in total experience, like the medium-fast growth rate.
+    fn cubic() -> GrowthRate {
+        test_data::growth_rate(|level| level.pow(3))
+    }
+
+    /// A level `level` pokemon with `experience` towards its next level and `friendship`.
+    fn trained(level: u32, experience: u32, friendship: u8) -> OwnedPokemon {
+        OwnedPokemon {
+            experience,
+            friendship,
+            ..OwnedPokemon::generate("ash", 25, "pikachu", 25, level, &mut rand::thread_rng())
+        }
+    }
+
+    #[test]
+    fn experience_below_the_next_level_is_kept() {
+        let mut pokemon = trained(5, 10, 70);
+        assert_eq!(add_experience(&mut pokemon, &cubic(), 80), 0);
+        assert_eq!((pokemon.level, pokemon.experience, pokemon.friendship), (5, 90, 70));
+    }
+
+    #[test]
+    fn reaching_a_threshold_levels_up() {
+        // Level 5 starts at 125 experience and level 6 at 216
+        let mut pokemon = trained(5, 10, 70);
+        assert_eq!(add_experience(&mut pokemon, &cubic(), 81), 1);
+        assert_eq!((pokemon.level, pokemon.experience), (6, 0));
+    }
+
+    #[test]
+    fn several_levels_can_be_gained_at_once() {
+        let mut pokemon = trained(5, 0, 70);
+        assert_eq!(add_experience(&mut pokemon, &cubic(), 512 + 20 - 125), 3);
+        assert_eq!((pokemon.level, pokemon.experience), (8, 20));
+    }
+
+    #[test]
+    fn friendship_grows_slower_as_it_rises() {
+        let mut pokemon = trained(5, 0, 98);
+        add_experience(&mut pokemon, &cubic(), 343 - 125);
+        assert_eq!(pokemon.friendship, 98 + 5 + 3);
+
+        let mut pokemon = trained(5, 0, 199);
+        add_experience(&mut pokemon, &cubic(), 343 - 125);
+        assert_eq!(pokemon.friendship, 199 + 3 + 2);
+
+        let mut pokemon = trained(5, 0, MAX_FRIENDSHIP - 1);
+        add_experience(&mut pokemon, &cubic(), 343 - 125);
+        assert_eq!(pokemon.friendship, MAX_FRIENDSHIP);
+    }
+
+    #[test]
+    fn experience_stops_at_the_highest_level() {
+        let mut pokemon = trained(MAX_LEVEL - 1, 0, 70);
+        assert_eq!(add_experience(&mut pokemon, &cubic(), u32::MAX), 1);
+        assert_eq!((pokemon.level, pokemon.experience), (MAX_LEVEL, 0));
+    }
+
+    #[tokio::test]
+    async fn messages_within_the_cooldown_earn_nothing() {
+        let (repo, pikachu) = trainer_with_pikachu().await;
+        // Stored times only keep milliseconds
+        let earlier = Utc::now().trunc_subsecs(3);
+        let cooldown = Duration::seconds(EXPERIENCE_COOLDOWN_SECS);
+        assert!(repo.claim_experience_cooldown("ash", earlier, cooldown).await.unwrap());
+
+        // The Pokedex is empty, so this would fail if it got as far as looking anything up
+        let now = earlier + Duration::seconds(1);
+        let gain = award_chat_experience(&repo, &mut MemoryPokedex::new(), "ash", now)
+            .await
+            .unwrap();
+        assert!(gain.is_none());
+        assert_eq!(repo.get_pokemon(pikachu.id).await.unwrap(), Some(pikachu));
+        let player = repo.get_player("ash").await.unwrap().unwrap();
+        assert_eq!(player.last_experience_at.map(|at| at.to_chrono()), Some(earlier));
+    }
+
+    #[tokio::test]
+    async fn unregistered_players_earn_nothing() {
+        let repo = MemoryRepository::new();
+        let gain = award_chat_experience(&repo, &mut MemoryPokedex::new(), "ash", Utc::now())
+            .await
+            .unwrap();
+        assert!(gain.is_none());
+    }
+
+    #[tokio::test]
+    async fn blocking_evolution() {
+        let (repo, pikachu) = trainer_with_pikachu().await;
+        let blocked = set_evolution_blocked(&repo, "ash", pikachu.id, true).await.unwrap();
+        assert!(blocked.evolution_blocked);
+        let stored = repo.get_pokemon(pikachu.id).await.unwrap().unwrap();
+        assert!(stored.evolution_blocked);
+
+        let result = set_evolution_blocked(&repo, "misty", pikachu.id, false).await;
+        assert!(matches!(result, Err(database::Error::NotOwned { .. })));
+    }
+
+    #[tokio::test]
+    async fn leveling_up_can_evolve() {
+        let charmander = charmander();
+        let repo = trainer_with(&charmander, &[]).await;
+        let gain = award_chat_experience(&repo, &mut pokedex(), "ash", Utc::now())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(gain.previous_level, 15);
+        assert!(gain.evolution.is_some());
+
+        let stored = repo.get_pokemon(charmander.id).await.unwrap().unwrap();
+        assert_eq!((stored.level, stored.species_name.as_str()), (16, "charmeleon"));
+        assert_eq!(stored.evolutions.len(), 1);
+        let player = repo.get_player("ash").await.unwrap().unwrap();
+        assert!(player.caught_species.contains(&5));
+    }
+
+    #[tokio::test]
+    async fn everstones_stop_evolution_while_leveling() {
+        let charmander = OwnedPokemon {
+            held_item: Some(evolution::EVERSTONE.to_string()),
+            ..charmander()
+        };
+        let repo = trainer_with(&charmander, &[]).await;
+        let gain = award_chat_experience(&repo, &mut pokedex(), "ash", Utc::now())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(gain.evolution.is_none());
+        assert_eq!(gain.ready_evolutions, vec!["charmeleon".to_string()]);
+
+        let stored = repo.get_pokemon(charmander.id).await.unwrap().unwrap();
+        assert_eq!((stored.level, stored.species_name.as_str()), (16, "charmander"));
+    }
+
+    #[tokio::test]
+    async fn evolving_with_an_item_uses_it_up() {
+        let eevee = eevee();
+        let repo = trainer_with(&eevee, &["fire-stone"]).await;
+        let evolved = evolve_pokemon(
+            &repo,
+            &mut pokedex(),
+            "ash",
+            eevee.id,
+            Some("fire-stone"),
+            None,
+            Utc::now(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(evolved.species_name, "flareon");
+        assert_eq!(repo.get_pokemon(eevee.id).await.unwrap(), Some(evolved));
+        assert!(repo.get_inventory("ash").await.unwrap().items.is_empty());
+        let player = repo.get_player("ash").await.unwrap().unwrap();
+        assert!(player.caught_species.contains(&136));
+    }
+
+    #[tokio::test]
+    async fn evolving_needs_the_item_in_the_inventory() {
+        let eevee = eevee();
+        let repo = trainer_with(&eevee, &[]).await;
+        let result = evolve_pokemon(
+            &repo,
+            &mut pokedex(),
+            "ash",
+            eevee.id,
+            Some("fire-stone"),
+            None,
+            Utc::now(),
+        )
+        .await;
+        assert!(matches!(result, Err(Error::Database(database::Error::MissingItem(_)))));
+        assert_eq!(repo.get_pokemon(eevee.id).await.unwrap(), Some(eevee));
+    }
+
+    #[tokio::test]
+    async fn evolving_needs_the_conditions_met() {
+        let charmander = charmander();
+        let repo = trainer_with(&charmander, &[]).await;
+        let result = evolve_pokemon(&repo, &mut pokedex(), "ash", charmander.id, None, None, Utc::now()).await;
+        assert!(matches!(result, Err(Error::Database(database::Error::CannotEvolve(_)))));
+
+        let result = evolve_pokemon(&repo, &mut pokedex(), "misty", charmander.id, None, None, Utc::now()).await;
+        assert!(matches!(result, Err(Error::Database(database::Error::NotOwned { .. }))));
+    }
+
+    #[tokio::test]
+    async fn blocked_pokemon_can_still_be_evolved_on_request() {
+        let charmander = OwnedPokemon {
+            level: 16,
+            evolution_blocked: true,
+            ..charmander()
+        };
+        let repo = trainer_with(&charmander, &[]).await;
+        let evolved = evolve_pokemon(
+            &repo,
+            &mut pokedex(),
+            "ash",
+            charmander.id,
+            None,
+            Some("charmeleon"),
+            Utc::now(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(evolved.species_name, "charmeleon");
+    }
+}
@@ -0,0 +1,136 @@
+//! The `leveling` module contains code for pokemon gaining experience as
+//! their trainer chats, and for evolving them.
+
+use crate::database::{self, MongoRepository};
+use crate::guilds;
+use crate::models::OwnedPokemon;
+use crate::pokedex::Pokedex;
+use pyo3::prelude::*;
+use pyo3_asyncio::tokio as pytokio;
+
+mod evolution;
+mod handlers;
+
+// Adds all required functions into the module.
+pub fn init_submodule(module: &PyModule) -> PyResult<()> {
+    module.add_function(pyo3::wrap_pyfunction!(award_chat_experience, module)?)?;
+    module.add_function(pyo3::wrap_pyfunction!(evolve_pokemon, module)?)?;
+    module.add_function(pyo3::wrap_pyfunction!(set_evolution_blocked, module)?)?;
+    module.add_function(pyo3::wrap_pyfunction!(hold_item, module)?)?;
+    Ok(())
+}
+
+/// Gives experience to a player's selected pokemon for a chat message. Call
+/// this for every message; messages sent less than 30 seconds after the last
+/// one that earned experience are ignored.
+///
+/// Leveling up makes the pokemon evolve automatically if it can, unless its
+/// evolution is blocked or it is holding an everstone.
+///
+/// # Returns
+///
+/// An `ExperienceGain` with the level ups and evolutions to announce, or
+/// `None` if nothing was gained.
+#[pyfunction]
+#[text_signature = "(player_id, guild_id=None, /)"]
+fn award_chat_experience(py: Python, player_id: String, guild_id: Option<String>) -> PyResult<PyObject> {
+    pytokio::into_coroutine(py, async move {
+        let repo = MongoRepository::new(database::get()?);
+        let key = guilds::player_key(&repo, guild_id.as_deref(), &player_id).await?;
+        let gain = handlers::award_chat_experience(&repo, &mut Pokedex::new(), &key, chrono::Utc::now()).await?;
+        Ok(Python::with_gil(|py| gain.into_py(py)))
+    })
+}
+
+/// Evolves one of a player's pokemon. Its IVs, nature, nickname and
+/// shininess are kept.
+///
+/// # Arguments
+///
+/// * `item` - Optional PokeAPI name of an item from the player's inventory to
+///   use on the pokemon, e.g. `fire-stone`. Without one, the pokemon evolves
+///   as if it leveled up.
+/// * `species` - PokeAPI name of the species to evolve into. Only needed if
+///   the pokemon could evolve into several.
+///
+/// # Returns
+///
+/// The evolved `OwnedPokemon`. Raises `ValueError` if the pokemon doesn't
+/// meet the conditions to evolve or the player doesn't have the item.
+#[pyfunction]
+#[text_signature = "(player_id, pokemon_id, item=None, species=None, guild_id=None, /)"]
+fn evolve_pokemon(
+    py: Python,
+    player_id: String,
+    pokemon_id: String,
+    item: Option<String>,
+    species: Option<String>,
+    guild_id: Option<String>,
+) -> PyResult<PyObject> {
+    let pokemon_id = database::parse_id(&pokemon_id)?;
+    pytokio::into_coroutine(py, async move {
+        let repo = MongoRepository::new(database::get()?);
+        let key = guilds::player_key(&repo, guild_id.as_deref(), &player_id).await?;
+        let pokemon = handlers::evolve_pokemon(
+            &repo,
+            &mut Pokedex::new(),
+            &key,
+            pokemon_id,
+            item.as_deref(),
+            species.as_deref(),
+            chrono::Utc::now(),
+        )
+        .await?;
+        Ok(Python::with_gil(|py| OwnedPokemon::from(pokemon).into_py(py)))
+    })
+}
+
+/// Stops one of a player's pokemon from evolving automatically as it levels
+/// up, or allows it again. It can still be evolved with `evolve_pokemon`.
+///
+/// # Returns
+///
+/// The updated `OwnedPokemon`.
+#[pyfunction]
+#[text_signature = "(player_id, pokemon_id, blocked, guild_id=None, /)"]
+fn set_evolution_blocked(
+    py: Python,
+    player_id: String,
+    pokemon_id: String,
+    blocked: bool,
+    guild_id: Option<String>,
+) -> PyResult<PyObject> {
+    let pokemon_id = database::parse_id(&pokemon_id)?;
+    pytokio::into_coroutine(py, async move {
+        let repo = MongoRepository::new(database::get()?);
+        let key = guilds::player_key(&repo, guild_id.as_deref(), &player_id).await?;
+        let pokemon = handlers::set_evolution_blocked(&repo, &key, pokemon_id, blocked).await?;
+        Ok(Python::with_gil(|py| OwnedPokemon::from(pokemon).into_py(py)))
+    })
+}
+
+/// Gives one of a player's pokemon an item from their inventory to hold, or
+/// takes its item back if `item` is `None`. Any item it held before goes back
+/// into the inventory.
+///
+/// # Returns
+///
+/// The updated `OwnedPokemon`. Raises `ValueError` if the player doesn't
+/// have the item.
+#[pyfunction]
+#[text_signature = "(player_id, pokemon_id, item=None, guild_id=None, /)"]
+fn hold_item(
+    py: Python,
+    player_id: String,
+    pokemon_id: String,
+    item: Option<String>,
+    guild_id: Option<String>,
+) -> PyResult<PyObject> {
+    let pokemon_id = database::parse_id(&pokemon_id)?;
+    pytokio::into_coroutine(py, async move {
+        let repo = MongoRepository::new(database::get()?);
+        let key = guilds::player_key(&repo, guild_id.as_deref(), &player_id).await?;
+        let pokemon = handlers::hold_item(&repo, &key, pokemon_id, item.as_deref()).await?;
+        Ok(Python::with_gil(|py| OwnedPokemon::from(pokemon).into_py(py)))
+    })
+}
@@ -2,24 +2,40 @@
 //! the pokecord discord frontend exists here.
 
 use pyo3::prelude::*;
+use pyo3::types::PyDict;
 use pyo3::{create_exception, wrap_pyfunction};
 use pyo3_asyncio::tokio as pytokio;
-use pyo3_log;
+use std::sync::Arc;
+use tokio::sync::OnceCell;
 
-use crate::pokedex::{Pokedex, Pokemon, PokemonSpecies};
+use crate::pokedex::cache::{self, MongoStore};
+use crate::pokedex::{warmup, NameIndex, Pokedex, Pokemon};
 
-mod database;
+pub mod battle;
+pub mod database;
+mod error;
+mod guilds;
+mod leveling;
 mod models;
-mod pokedex;
+mod party;
+mod players;
+pub mod pokedex;
 mod registration;
+mod spawning;
 
 create_exception!(
     pokecord_backend,
     PokedexError,
     pyo3::exceptions::PyException
 );
+create_exception!(
+    pokecord_backend,
+    DatabaseError,
+    pyo3::exceptions::PyException
+);
 
-
+/// Index of all species names, built on first use by `search_species`.
+static NAME_INDEX: OnceCell<NameIndex> = OnceCell::const_new();
 
 /// Test function that tests logging at different levels to confirm config.
 #[pyfunction]
@@ -40,7 +56,150 @@ fn list_pokemon(py: Python) -> PyResult<PyObject> {
         Ok(Python::with_gil(|py| names.into_py(py)))
     })
 }
-    
+
+/// Searches all species names, in every language, for names close to `query`.
+/// The first search builds the name index, which is slow on a cold cache.
+///
+/// # Returns
+///
+/// A `list` of up to `limit` `SpeciesMatch`, best match first.
+#[pyfunction(limit = "5")]
+#[text_signature = "(query, limit=5, /)"]
+fn search_species(py: Python, query: String, limit: usize) -> PyResult<PyObject> {
+    pytokio::into_coroutine(py, async move {
+        let index = NAME_INDEX
+            .get_or_try_init(|| async { NameIndex::build(&mut Pokedex::new()).await })
+            .await?;
+        let matches: Vec<_> = index
+            .search(&query, limit)
+            .into_iter()
+            .map(|m| models::SpeciesMatch {
+                name: m.name,
+                species_name: m.species,
+                distance: m.distance,
+            })
+            .collect();
+        Ok(Python::with_gil(|py| matches.into_py(py)))
+    })
+}
+
+/// Prefetches PokeAPI resources into the cache so gameplay doesn't wait on
+/// cold requests. Progress is logged, and an interrupted warm-up resumes where
+/// it left off.
+///
+/// # Arguments
+///
+/// * `resources` - Optional `list` of resource types to warm, e.g.
+///   `["pokemon-species", "type"]`. Defaults to everything gameplay needs.
+/// * `shard` - Optional name of the shard warming the cache. Shards sharing a
+///   cache each keep their own progress.
+#[pyfunction]
+#[text_signature = "(resources=None, shard=None, /)"]
+fn warm_cache(py: Python, resources: Option<Vec<String>>, shard: Option<String>) -> PyResult<PyObject> {
+    let targets = match resources {
+        Some(names) => names
+            .iter()
+            .map(|name| name.parse())
+            .collect::<Result<Vec<warmup::Target>, _>>()
+            .map_err(pyo3::exceptions::PyValueError::new_err)?,
+        None => warmup::DEFAULT_TARGETS.to_vec(),
+    };
+    pytokio::into_coroutine(py, async move {
+        let mut pokedex = Pokedex::new();
+        warmup::warm_up(&mut pokedex, &targets, shard.as_deref(), |progress| {
+            if progress.done % 100 == 0 || progress.done == progress.total {
+                log::info!("Warmed {}/{} {}", progress.done, progress.total, progress.target);
+            }
+        })
+        .await?;
+        Ok(Python::with_gil(|py| py.None()))
+    })
+}
+
+/// Reports how effective the PokeAPI cache has been since startup.
+///
+/// # Returns
+///
+/// A `dict` with counts of `fresh_hits`, `revalidated` (304 responses),
+/// `modified`, `misses`, `uncacheable` responses and `write_failures`, along
+/// with the number of cache `entries` and their total `disk_bytes`.
+#[pyfunction]
+#[text_signature = "(/)"]
+fn cache_stats(py: Python) -> PyResult<PyObject> {
+    pytokio::into_coroutine(py, async {
+        let stats = Pokedex::new().stats().await?;
+        Python::with_gil(|py| {
+            let dict = PyDict::new(py);
+            dict.set_item("fresh_hits", stats.fresh_hits)?;
+            dict.set_item("revalidated", stats.revalidated)?;
+            dict.set_item("modified", stats.modified)?;
+            dict.set_item("misses", stats.misses)?;
+            dict.set_item("uncacheable", stats.uncacheable)?;
+            dict.set_item("write_failures", stats.write_failures)?;
+            dict.set_item("entries", stats.entries)?;
+            dict.set_item("disk_bytes", stats.disk_bytes)?;
+            Ok(dict.to_object(py))
+        })
+    })
+}
+
+/// Connects to the database configured in the environment, and brings its
+/// schema up to date. Await this once at startup, before any command that
+/// uses the database; those raise `DatabaseError` until it has finished.
+/// Connecting again once connected does nothing.
+#[pyfunction]
+#[text_signature = "(/)"]
+fn connect(py: Python) -> PyResult<PyObject> {
+    pytokio::into_coroutine(py, async {
+        let config = database::Config::from_env()?;
+        database::init(&config).await?;
+        Ok(Python::with_gil(|py| py.None()))
+    })
+}
+
+/// Pings the database.
+///
+/// # Returns
+///
+/// The round trip time in milliseconds, as a `float`. Raises `DatabaseError`
+/// if the database is unreachable.
+#[pyfunction]
+#[text_signature = "(/)"]
+fn database_health(py: Python) -> PyResult<PyObject> {
+    pytokio::into_coroutine(py, async {
+        let health = database::get()?.health_check().await?;
+        let millis = health.latency.as_secs_f64() * 1000.0;
+        Ok(Python::with_gil(|py| millis.into_py(py)))
+    })
+}
+
+/// Makes all PokeAPI requests in this process share a cache stored in the
+/// `pokecache` collection of MongoDB, instead of the local `.pokecache`
+/// directory. This lets several bot shards share one warm cache. Call this
+/// once at startup, before any other backend calls.
+///
+/// # Arguments
+///
+/// * `database_name` - Optional name of the database to keep the cache in, on
+///   the same server as the game database. Defaults to the game database.
+#[pyfunction]
+#[text_signature = "(database_name=None, /)"]
+fn use_shared_cache(py: Python, database_name: Option<String>) -> PyResult<PyObject> {
+    pytokio::into_coroutine(py, async move {
+        // Reuse the game database's connection pool rather than opening another client
+        let db = database::get()?;
+        let collection = match &database_name {
+            Some(name) => db.client().database(name).collection("pokecache"),
+            None => db.inner().collection("pokecache"),
+        };
+        let store = MongoStore::new(collection);
+        if !cache::set_default_store(Arc::new(store)) {
+            log::warn!("Shared cache was already configured, ignoring");
+        }
+        Ok(Python::with_gil(|py| py.None()))
+    })
+}
+
 /// Main entry point for all python code. This function represents the
 /// root Python module. All submodules should be added here.
 /// TODO: Another macro maybe?
@@ -49,9 +208,16 @@ fn pokecord_backend(py: Python, m: &PyModule) -> PyResult<()> {
     pyo3_log::init();
     pytokio::init_multi_thread();
     pyo3_asyncio::try_init(py)?;
+    m.add("PokedexError", py.get_type::<PokedexError>())?;
+    m.add("DatabaseError", py.get_type::<DatabaseError>())?;
+    m.add_function(wrap_pyfunction!(connect, m)?)?;
     m.add_function(wrap_pyfunction!(test_logging, m)?)?;
     m.add_function(wrap_pyfunction!(list_pokemon, m)?)?;
-    m.add("PokedexError", py.get_type::<PokedexError>())?;
+    m.add_function(wrap_pyfunction!(search_species, m)?)?;
+    m.add_function(wrap_pyfunction!(warm_cache, m)?)?;
+    m.add_function(wrap_pyfunction!(cache_stats, m)?)?;
+    m.add_function(wrap_pyfunction!(use_shared_cache, m)?)?;
+    m.add_function(wrap_pyfunction!(database_health, m)?)?;
     let submod = PyModule::new(py, "registration")?;
     registration::init_submodule(submod)?;
     m.add_submodule(submod)?;
@@ -59,5 +225,29 @@ fn pokecord_backend(py: Python, m: &PyModule) -> PyResult<()> {
     let submod = PyModule::new(py, "models")?;
     models::init_submodule(submod)?;
     m.add_submodule(submod)?;
+
+    let submod = PyModule::new(py, "spawning")?;
+    spawning::init_submodule(submod)?;
+    m.add_submodule(submod)?;
+
+    let submod = PyModule::new(py, "guilds")?;
+    guilds::init_submodule(submod)?;
+    m.add_submodule(submod)?;
+
+    let submod = PyModule::new(py, "players")?;
+    players::init_submodule(submod)?;
+    m.add_submodule(submod)?;
+
+    let submod = PyModule::new(py, "party")?;
+    party::init_submodule(submod)?;
+    m.add_submodule(submod)?;
+
+    let submod = PyModule::new(py, "leveling")?;
+    leveling::init_submodule(submod)?;
+    m.add_submodule(submod)?;
+
+    let submod = PyModule::new(py, "battle")?;
+    battle::init_submodule(submod)?;
+    m.add_submodule(submod)?;
     Ok(())
 }
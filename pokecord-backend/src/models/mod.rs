@@ -3,12 +3,375 @@
 //! implementations and are intended to represent data only.
 use pyo3::prelude::*;
 
+use crate::database::records::{self, ProgressScope};
+
 /// Inits the model's module.
 pub fn init_submodule(module: &PyModule) -> PyResult<()> {
     module.add_class::<Pokemon>()?;
+    module.add_class::<SpeciesMatch>()?;
+    module.add_class::<GuildSettings>()?;
+    module.add_class::<OwnedPokemon>()?;
+    module.add_class::<Player>()?;
+    module.add_class::<DexCompletion>()?;
+    module.add_class::<PokemonPage>()?;
+    module.add_class::<Evolution>()?;
+    module.add_class::<ExperienceGain>()?;
+    module.add_class::<BattleEvent>()?;
+    module.add_class::<BattleResult>()?;
     Ok(())
 }
 
 /// Class representing a single Pokemon, including relevant data fields.
 #[pyclass(module = "pokecord_backend.models")]
-pub struct Pokemon {}
+#[derive(Debug, Clone, Default)]
+pub struct Pokemon {
+    /// National dex number of this pokemon's species.
+    #[pyo3(get)]
+    pub species_id: usize,
+    /// Name of this pokemon's species, e.g. `vulpix`.
+    #[pyo3(get)]
+    pub species_name: String,
+    /// PokeAPI ID of the variety this pokemon is. This is the same as
+    /// `species_id` for the default variety of a species.
+    #[pyo3(get)]
+    pub variety_id: usize,
+    /// Name of the variety this pokemon is, e.g. `vulpix-alola`.
+    #[pyo3(get)]
+    pub variety_name: String,
+    /// Whether this pokemon is its species' default variety, as opposed to a
+    /// regional variant, mega evolution or other alternate form.
+    #[pyo3(get)]
+    pub is_default_variety: bool,
+    /// Localized name to show for this pokemon, including its form if it is
+    /// not the default variety, e.g. `Alolan Vulpix`.
+    #[pyo3(get)]
+    pub display_name: String,
+    /// Localized Pokedex flavor text for this pokemon's species, if any.
+    #[pyo3(get)]
+    pub flavor_text: Option<String>,
+    /// URL of the best available image of this pokemon.
+    #[pyo3(get)]
+    pub image_url: String,
+    /// API names of this pokemon's types, e.g. `["fire"]`.
+    #[pyo3(get)]
+    pub types: Vec<String>,
+    /// Whether this pokemon's species is legendary or mythical.
+    #[pyo3(get)]
+    pub is_legendary: bool,
+}
+
+/// Class representing a species suggested by a name search, for "did you
+/// mean" replies.
+#[pyclass(module = "pokecord_backend.models")]
+#[derive(Debug, Clone)]
+pub struct SpeciesMatch {
+    /// The name that matched the search, which may be in any language.
+    #[pyo3(get)]
+    pub name: String,
+    /// API name of the matched species, e.g. `mr-mime`.
+    #[pyo3(get)]
+    pub species_name: String,
+    /// How many edits away from the search the name is. 0 is an exact match,
+    /// ignoring case and accents.
+    #[pyo3(get)]
+    pub distance: usize,
+}
+
+/// Class representing a guild's configuration.
+#[pyclass(module = "pokecord_backend.models")]
+#[derive(Debug, Clone)]
+pub struct GuildSettings {
+    #[pyo3(get)]
+    pub guild_id: String,
+    /// ID of the channel pokemon spawn in, or `None` if they can spawn in any
+    /// channel.
+    #[pyo3(get)]
+    pub spawn_channel: Option<String>,
+    /// Average number of messages between spawns.
+    #[pyo3(get)]
+    pub spawn_rate: u32,
+    /// PokeAPI name of the language pokemon are shown in, e.g. `en`.
+    #[pyo3(get)]
+    pub language: String,
+    /// Command prefix, e.g. `p!`.
+    #[pyo3(get)]
+    pub prefix: String,
+    /// `"global"` if players' progress is shared with other servers, or
+    /// `"guild"` if it is only used in this one.
+    #[pyo3(get)]
+    pub progress_scope: String,
+}
+
+impl From<records::GuildSettings> for GuildSettings {
+    fn from(settings: records::GuildSettings) -> GuildSettings {
+        GuildSettings {
+            guild_id: settings.guild_id,
+            spawn_channel: settings.spawn_channel,
+            spawn_rate: settings.spawn_rate,
+            language: settings.language,
+            prefix: settings.prefix,
+            progress_scope: match settings.progress_scope {
+                ProgressScope::Global => "global",
+                ProgressScope::Guild => "guild",
+            }
+            .to_string(),
+        }
+    }
+}
+
+/// Seconds since the Unix epoch, which Python's `datetime.fromtimestamp`
+/// accepts.
+fn timestamp(date: &mongodb::bson::DateTime) -> f64 {
+    date.timestamp_millis() as f64 / 1000.0
+}
+
+/// Class representing a pokemon that belongs to a player.
+#[pyclass(module = "pokecord_backend.models")]
+#[derive(Debug, Clone)]
+pub struct OwnedPokemon {
+    /// Unique ID of this pokemon, for commands that refer to it.
+    #[pyo3(get)]
+    pub id: String,
+    #[pyo3(get)]
+    pub species_id: u32,
+    #[pyo3(get)]
+    pub species_name: String,
+    #[pyo3(get)]
+    pub variety_id: u32,
+    #[pyo3(get)]
+    pub nickname: Option<String>,
+    #[pyo3(get)]
+    pub level: u32,
+    /// Experience points gained since reaching the current level.
+    #[pyo3(get)]
+    pub experience: u32,
+    /// How close this pokemon's IVs are to perfect, from 0 to 100.
+    #[pyo3(get)]
+    pub iv_percentage: f64,
+    #[pyo3(get)]
+    pub nature: String,
+    #[pyo3(get)]
+    pub shiny: bool,
+    /// When this pokemon was caught, in seconds since the Unix epoch.
+    #[pyo3(get)]
+    pub caught_at: f64,
+    /// From 0 to 255. Some species only evolve once friendly enough.
+    #[pyo3(get)]
+    pub friendship: u8,
+    /// PokeAPI name of the item the pokemon is holding.
+    #[pyo3(get)]
+    pub held_item: Option<String>,
+    /// Whether the player stopped this pokemon from evolving automatically.
+    #[pyo3(get)]
+    pub evolution_blocked: bool,
+    /// Every evolution this pokemon went through, oldest first.
+    #[pyo3(get)]
+    pub evolutions: Vec<Evolution>,
+}
+
+impl From<records::OwnedPokemon> for OwnedPokemon {
+    fn from(pokemon: records::OwnedPokemon) -> OwnedPokemon {
+        OwnedPokemon {
+            id: pokemon.id.to_hex(),
+            species_id: pokemon.species_id,
+            species_name: pokemon.species_name,
+            variety_id: pokemon.variety_id,
+            nickname: pokemon.nickname,
+            level: pokemon.level,
+            experience: pokemon.experience,
+            iv_percentage: pokemon.ivs.iv_percentage(),
+            nature: pokemon.nature,
+            shiny: pokemon.shiny,
+            caught_at: timestamp(&pokemon.caught_at),
+            friendship: pokemon.friendship,
+            held_item: pokemon.held_item,
+            evolution_blocked: pokemon.evolution_blocked,
+            evolutions: pokemon.evolutions.into_iter().map(Evolution::from).collect(),
+        }
+    }
+}
+
+/// Class representing an evolution a pokemon went through.
+#[pyclass(module = "pokecord_backend.models")]
+#[derive(Debug, Clone)]
+pub struct Evolution {
+    #[pyo3(get)]
+    pub from_species_id: u32,
+    #[pyo3(get)]
+    pub from_species_name: String,
+    #[pyo3(get)]
+    pub to_species_id: u32,
+    #[pyo3(get)]
+    pub to_species_name: String,
+    /// Level the pokemon evolved at.
+    #[pyo3(get)]
+    pub level: u32,
+    /// When the pokemon evolved, in seconds since the Unix epoch.
+    #[pyo3(get)]
+    pub evolved_at: f64,
+}
+
+impl From<records::Evolution> for Evolution {
+    fn from(evolution: records::Evolution) -> Evolution {
+        Evolution {
+            from_species_id: evolution.from_species_id,
+            from_species_name: evolution.from_species_name,
+            to_species_id: evolution.to_species_id,
+            to_species_name: evolution.to_species_name,
+            level: evolution.level,
+            evolved_at: timestamp(&evolution.evolved_at),
+        }
+    }
+}
+
+/// Class representing a player's trainer profile.
+#[pyclass(module = "pokecord_backend.models")]
+#[derive(Debug, Clone)]
+pub struct Player {
+    /// Discord user ID.
+    #[pyo3(get)]
+    pub player_id: String,
+    /// When the player registered, in seconds since the Unix epoch.
+    #[pyo3(get)]
+    pub registered_at: f64,
+    /// The starter the player chose, if they chose one and still own it.
+    #[pyo3(get)]
+    pub starter: Option<OwnedPokemon>,
+    /// Number of wild pokemon caught, including any since traded or
+    /// released.
+    #[pyo3(get)]
+    pub total_caught: u32,
+    /// Number of pokemon the player owns now.
+    #[pyo3(get)]
+    pub total_owned: usize,
+    /// Number of different species the player has encountered.
+    #[pyo3(get)]
+    pub species_seen: usize,
+    /// Number of different species the player has owned.
+    #[pyo3(get)]
+    pub species_caught: usize,
+    #[pyo3(get)]
+    pub balance: i64,
+    /// The pokemon that gains experience as the player chats, if any.
+    #[pyo3(get)]
+    pub selected: Option<OwnedPokemon>,
+    #[pyo3(get)]
+    pub favorites: Vec<OwnedPokemon>,
+    /// The player's party, lead first.
+    #[pyo3(get)]
+    pub party: Vec<OwnedPokemon>,
+    /// Names of earned badges.
+    #[pyo3(get)]
+    pub badges: Vec<String>,
+}
+
+/// Class representing how much of one generation's Pokedex a player has
+/// completed.
+#[pyclass(module = "pokecord_backend.models")]
+#[derive(Debug, Clone)]
+pub struct DexCompletion {
+    /// Localized generation name, e.g. `Generation I`.
+    #[pyo3(get)]
+    pub generation: String,
+    /// Localized name of the generation's main region, e.g. `Kanto`.
+    #[pyo3(get)]
+    pub region: String,
+    /// Number of species introduced in the generation.
+    #[pyo3(get)]
+    pub total: usize,
+    #[pyo3(get)]
+    pub seen: usize,
+    #[pyo3(get)]
+    pub caught: usize,
+    /// Percentage of the generation's species seen, from 0 to 100.
+    #[pyo3(get)]
+    pub seen_percentage: f64,
+    /// Percentage of the generation's species caught, from 0 to 100.
+    #[pyo3(get)]
+    pub caught_percentage: f64,
+    /// Localized names of the species not caught yet, in national dex order.
+    #[pyo3(get)]
+    pub missing_species: Vec<String>,
+}
+
+/// Class representing one page of a player's pokemon.
+#[pyclass(module = "pokecord_backend.models")]
+#[derive(Debug, Clone)]
+pub struct PokemonPage {
+    #[pyo3(get)]
+    pub pokemon: Vec<OwnedPokemon>,
+    /// Cursor to pass back for the next page, or `None` on the last page.
+    #[pyo3(get)]
+    pub next_cursor: Option<String>,
+}
+
+/// Class representing the experience a pokemon gained from a chat message,
+/// and what happened as a result.
+#[pyclass(module = "pokecord_backend.models")]
+#[derive(Debug, Clone)]
+pub struct ExperienceGain {
+    /// The pokemon that gained experience, after leveling up and evolving.
+    #[pyo3(get)]
+    pub pokemon: OwnedPokemon,
+    #[pyo3(get)]
+    pub experience: u32,
+    /// The pokemon's level before gaining experience. It leveled up if this
+    /// is lower than its current level.
+    #[pyo3(get)]
+    pub previous_level: u32,
+    /// The evolution the pokemon went through automatically, if any.
+    #[pyo3(get)]
+    pub evolution: Option<Evolution>,
+    /// Species the pokemon can now evolve into with `evolve_pokemon`, because
+    /// its evolution is blocked or there is more than one to choose from.
+    #[pyo3(get)]
+    pub ready_evolutions: Vec<String>,
+}
+
+/// Class representing something that happened during a battle.
+#[pyclass(module = "pokecord_backend.models")]
+#[derive(Debug, Clone, Default)]
+pub struct BattleEvent {
+    /// Turn the event happened in, starting at 1.
+    #[pyo3(get)]
+    pub turn: u32,
+    /// One of `move-used`, `missed`, `no-effect`, `damaged`, `recoil`,
+    /// `fainted`, `switched-in`, `won` or `draw`.
+    #[pyo3(get)]
+    pub kind: String,
+    /// Side the event happened to: 0 for the player and 1 for the opponent.
+    /// For `damaged` events this is the side that took the damage.
+    #[pyo3(get)]
+    pub side: Option<usize>,
+    #[pyo3(get)]
+    pub pokemon: Option<String>,
+    #[pyo3(get)]
+    pub move_name: Option<String>,
+    #[pyo3(get)]
+    pub damage: Option<u32>,
+    #[pyo3(get)]
+    pub critical: bool,
+    /// Damage multiplier from type effectiveness, e.g. 2.0 for super
+    /// effective moves.
+    #[pyo3(get)]
+    pub effectiveness: Option<f64>,
+    /// HP left after taking damage.
+    #[pyo3(get)]
+    pub hp: Option<u32>,
+}
+
+/// Class representing how a battle played out.
+#[pyclass(module = "pokecord_backend.models")]
+#[derive(Debug, Clone)]
+pub struct BattleResult {
+    /// Seed that replays this battle.
+    #[pyo3(get)]
+    pub seed: u64,
+    #[pyo3(get)]
+    pub turns: u32,
+    /// ID of the player who won, or `None` after a draw.
+    #[pyo3(get)]
+    pub winner: Option<String>,
+    #[pyo3(get)]
+    pub events: Vec<BattleEvent>,
+}
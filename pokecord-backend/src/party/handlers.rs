@@ -0,0 +1,257 @@
+use mongodb::bson::oid::ObjectId;
+
+use crate::database::{
+    operations::{owned_pokemon, party_pokemon, retry_on_conflict},
+    records::{OwnedPokemon, MAX_PARTY_SIZE},
+    repository::{PlayerRepository, Repository, Transactional, UnitOfWork},
+    Error,
+};
+
+/// A change to a player's party. Slots are numbered from 0, which is the lead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PartyChange {
+    /// Adds a pokemon to the end of the party.
+    Add(ObjectId),
+    Remove(ObjectId),
+    /// Exchanges the pokemon in two slots.
+    Swap(usize, usize),
+    /// Moves the pokemon in one slot to another, shifting those in between.
+    Move { from: usize, to: usize },
+    /// Moves a pokemon to the front of the party, adding it if it isn't in the party yet.
+    SetLead(ObjectId),
+}
+
+/// The party of the player stored under `key`, lead first. Pokemon the player no longer owns are left out.
+pub async fn get_party<R: Repository>(repo: &R, key: &str) -> Result<Vec<OwnedPokemon>, Error> {
+    let player = repo
+        .get_player(key)
+        .await?
+        .ok_or_else(|| Error::NotRegistered(key.to_string()))?;
+    party_pokemon(repo, key, &player.party).await
+}
+
+/// Applies `change` to the party of the player stored under `key` and returns the new party. Fails with
+/// [`Error::InvalidParty`] if the party would hold a pokemon twice or more than [`MAX_PARTY_SIZE`] pokemon, or a slot
+/// is empty, and with [`Error::NotOwned`] when adding a pokemon the player doesn't own.
+pub async fn change_party<R: Transactional>(
+    repo: &R,
+    key: &str,
+    change: PartyChange,
+) -> Result<Vec<OwnedPokemon>, Error> {
+    retry_on_conflict(|| try_change_party(repo, key, change)).await
+}
+
+async fn try_change_party<R: Transactional>(
+    repo: &R,
+    key: &str,
+    change: PartyChange,
+) -> Result<Vec<OwnedPokemon>, Error> {
+    let work = repo.begin().await?;
+    let mut player = work
+        .get_player(key)
+        .await?
+        .ok_or_else(|| Error::NotRegistered(key.to_string()))?;
+    // Released or traded pokemon drop out of the party, so they don't block changes
+    let mut party: Vec<ObjectId> = party_pokemon(&work, key, &player.party)
+        .await?
+        .iter()
+        .map(|pokemon| pokemon.id)
+        .collect();
+
+    match change {
+        PartyChange::Add(id) => {
+            owned_pokemon(&work, key, id).await?;
+            if party.contains(&id) {
+                return Err(Error::InvalidParty(format!("{} is already in the party", id)));
+            }
+            party.push(id);
+        }
+        PartyChange::Remove(id) => {
+            let slot = slot_of(&party, id)?;
+            party.remove(slot);
+        }
+        PartyChange::Swap(first, second) => {
+            check_slot(&party, first)?;
+            check_slot(&party, second)?;
+            party.swap(first, second);
+        }
+        PartyChange::Move { from, to } => {
+            check_slot(&party, from)?;
+            check_slot(&party, to)?;
+            let id = party.remove(from);
+            party.insert(to, id);
+        }
+        PartyChange::SetLead(id) => {
+            owned_pokemon(&work, key, id).await?;
+            party.retain(|member| *member != id);
+            party.insert(0, id);
+        }
+    }
+    if party.len() > MAX_PARTY_SIZE {
+        return Err(Error::InvalidParty(format!(
+            "a party holds at most {} pokemon",
+            MAX_PARTY_SIZE
+        )));
+    }
+
+    player.party = party;
+    work.update_player(&player).await?;
+    let pokemon = party_pokemon(&work, key, &player.party).await?;
+    work.commit().await?;
+    Ok(pokemon)
+}
+
+fn slot_of(party: &[ObjectId], id: ObjectId) -> Result<usize, Error> {
+    party
+        .iter()
+        .position(|member| *member == id)
+        .ok_or_else(|| Error::InvalidParty(format!("{} is not in the party", id)))
+}
+
+fn check_slot(party: &[ObjectId], slot: usize) -> Result<(), Error> {
+    if slot < party.len() {
+        Ok(())
+    } else {
+        Err(Error::InvalidParty(format!("slot {} is empty", slot)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{
+        records::Player,
+        repository::{MemoryRepository, PokemonRepository},
+    };
+
+    /// A repository where `ash` owns `count` pokemon and `misty` owns one, with empty parties.
+    async fn trainers(count: usize) -> (MemoryRepository, Vec<ObjectId>, ObjectId) {
+        let repo = MemoryRepository::new();
+        let mut rng = rand::thread_rng();
+        repo.insert_player(&Player::new("ash")).await.unwrap();
+        repo.insert_player(&Player::new("misty")).await.unwrap();
+        let mut owned = Vec::new();
+        for _ in 0..count {
+            let pokemon = OwnedPokemon::generate("ash", 25, "pikachu", 25, 5, &mut rng);
+            repo.insert_pokemon(&pokemon).await.unwrap();
+            owned.push(pokemon.id);
+        }
+        let staryu = OwnedPokemon::generate("misty", 120, "staryu", 120, 5, &mut rng);
+        repo.insert_pokemon(&staryu).await.unwrap();
+        (repo, owned, staryu.id)
+    }
+
+    async fn party_ids(repo: &MemoryRepository) -> Vec<ObjectId> {
+        repo.get_player("ash").await.unwrap().unwrap().party
+    }
+
+    /// Fills the party of `ash` with `ids`, in order.
+    async fn add_all(repo: &MemoryRepository, ids: &[ObjectId]) {
+        for &id in ids {
+            change_party(repo, "ash", PartyChange::Add(id)).await.unwrap();
+        }
+    }
+
+    fn assert_invalid(result: Result<Vec<OwnedPokemon>, Error>) {
+        assert!(
+            matches!(result, Err(Error::InvalidParty(_))),
+            "{:?} was allowed",
+            result
+        );
+    }
+
+    #[tokio::test]
+    async fn adding_and_removing_members() {
+        let (repo, owned, _) = trainers(3).await;
+        add_all(&repo, &owned).await;
+        assert_eq!(party_ids(&repo).await, owned);
+
+        let party = change_party(&repo, "ash", PartyChange::Remove(owned[1])).await.unwrap();
+        let ids: Vec<_> = party.iter().map(|pokemon| pokemon.id).collect();
+        assert_eq!(ids, vec![owned[0], owned[2]]);
+        assert_invalid(change_party(&repo, "ash", PartyChange::Remove(owned[1])).await);
+    }
+
+    #[tokio::test]
+    async fn adding_a_member_twice_fails() {
+        let (repo, owned, _) = trainers(1).await;
+        add_all(&repo, &owned).await;
+        assert_invalid(change_party(&repo, "ash", PartyChange::Add(owned[0])).await);
+        assert_eq!(party_ids(&repo).await, owned);
+    }
+
+    #[tokio::test]
+    async fn parties_hold_at_most_six() {
+        let (repo, owned, _) = trainers(MAX_PARTY_SIZE + 1).await;
+        add_all(&repo, &owned[..MAX_PARTY_SIZE]).await;
+        assert_invalid(change_party(&repo, "ash", PartyChange::Add(owned[MAX_PARTY_SIZE])).await);
+        assert_eq!(party_ids(&repo).await, &owned[..MAX_PARTY_SIZE]);
+    }
+
+    #[tokio::test]
+    async fn adding_someone_elses_pokemon_fails() {
+        let (repo, _, staryu) = trainers(0).await;
+        let result = change_party(&repo, "ash", PartyChange::Add(staryu)).await;
+        assert!(matches!(result, Err(Error::NotOwned { .. })));
+        assert!(party_ids(&repo).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn swapping_and_moving_slots() {
+        let (repo, owned, _) = trainers(3).await;
+        add_all(&repo, &owned).await;
+
+        change_party(&repo, "ash", PartyChange::Swap(0, 2)).await.unwrap();
+        assert_eq!(party_ids(&repo).await, vec![owned[2], owned[1], owned[0]]);
+        change_party(&repo, "ash", PartyChange::Move { from: 0, to: 2 })
+            .await
+            .unwrap();
+        assert_eq!(party_ids(&repo).await, vec![owned[1], owned[0], owned[2]]);
+    }
+
+    #[tokio::test]
+    async fn empty_slots_cannot_be_swapped_or_moved() {
+        let (repo, owned, _) = trainers(2).await;
+        add_all(&repo, &owned).await;
+
+        assert_invalid(change_party(&repo, "ash", PartyChange::Swap(0, 2)).await);
+        assert_invalid(change_party(&repo, "ash", PartyChange::Swap(5, 1)).await);
+        assert_invalid(change_party(&repo, "ash", PartyChange::Move { from: 2, to: 0 }).await);
+        assert_invalid(change_party(&repo, "ash", PartyChange::Move { from: 0, to: 2 }).await);
+        assert_eq!(party_ids(&repo).await, owned);
+    }
+
+    #[tokio::test]
+    async fn setting_the_lead_of_a_full_party() {
+        let (repo, owned, _) = trainers(MAX_PARTY_SIZE + 1).await;
+        add_all(&repo, &owned[..MAX_PARTY_SIZE]).await;
+
+        // A member can become the lead, but a newcomer would make seven
+        change_party(&repo, "ash", PartyChange::SetLead(owned[3]))
+            .await
+            .unwrap();
+        let mut expected = vec![owned[3], owned[0], owned[1], owned[2], owned[4], owned[5]];
+        assert_eq!(party_ids(&repo).await, expected);
+        assert_invalid(change_party(&repo, "ash", PartyChange::SetLead(owned[MAX_PARTY_SIZE])).await);
+        assert_eq!(party_ids(&repo).await, expected);
+
+        change_party(&repo, "ash", PartyChange::Remove(owned[5])).await.unwrap();
+        change_party(&repo, "ash", PartyChange::SetLead(owned[MAX_PARTY_SIZE]))
+            .await
+            .unwrap();
+        expected.pop();
+        expected.insert(0, owned[MAX_PARTY_SIZE]);
+        assert_eq!(party_ids(&repo).await, expected);
+    }
+
+    #[tokio::test]
+    async fn pokemon_that_left_are_dropped_from_the_party() {
+        let (repo, owned, _) = trainers(2).await;
+        add_all(&repo, &owned).await;
+        repo.delete_pokemon(owned[0]).await.unwrap();
+
+        let party = get_party(&repo, "ash").await.unwrap();
+        assert_eq!(party.len(), 1);
+        assert_eq!(party[0].id, owned[1]);
+    }
+}
@@ -0,0 +1,136 @@
+//! The `party` module contains code for managing the up to six pokemon a
+//! player battles with and trains.
+
+use crate::database::{self, records, MongoRepository};
+use crate::guilds;
+use crate::models::OwnedPokemon;
+use handlers::PartyChange;
+use pyo3::prelude::*;
+use pyo3_asyncio::tokio as pytokio;
+
+mod handlers;
+
+// Adds all required functions into the module.
+pub fn init_submodule(module: &PyModule) -> PyResult<()> {
+    module.add_function(pyo3::wrap_pyfunction!(get_party, module)?)?;
+    module.add_function(pyo3::wrap_pyfunction!(add_to_party, module)?)?;
+    module.add_function(pyo3::wrap_pyfunction!(remove_from_party, module)?)?;
+    module.add_function(pyo3::wrap_pyfunction!(swap_party_slots, module)?)?;
+    module.add_function(pyo3::wrap_pyfunction!(move_party_slot, module)?)?;
+    module.add_function(pyo3::wrap_pyfunction!(set_party_lead, module)?)?;
+    Ok(())
+}
+
+/// Fetches a player's party.
+///
+/// # Returns
+///
+/// A `list` of the `OwnedPokemon` in the party, lead first. Raises `KeyError`
+/// if the player isn't registered.
+#[pyfunction]
+#[text_signature = "(player_id, guild_id=None, /)"]
+fn get_party(py: Python, player_id: String, guild_id: Option<String>) -> PyResult<PyObject> {
+    pytokio::into_coroutine(py, async move {
+        let repo = MongoRepository::new(database::get()?);
+        let key = guilds::player_key(&repo, guild_id.as_deref(), &player_id).await?;
+        let party = handlers::get_party(&repo, &key).await?;
+        Ok(Python::with_gil(|py| to_py(py, party)))
+    })
+}
+
+/// Adds one of the player's pokemon to the end of their party.
+///
+/// # Returns
+///
+/// The new party. Raises `ValueError` if the party is full, already holds the
+/// pokemon or the player doesn't own it.
+#[pyfunction]
+#[text_signature = "(player_id, pokemon_id, guild_id=None, /)"]
+fn add_to_party(py: Python, player_id: String, pokemon_id: String, guild_id: Option<String>) -> PyResult<PyObject> {
+    let change = PartyChange::Add(database::parse_id(&pokemon_id)?);
+    change_party(py, player_id, guild_id, change)
+}
+
+/// Removes a pokemon from the player's party.
+///
+/// # Returns
+///
+/// The new party. Raises `ValueError` if the pokemon isn't in the party.
+#[pyfunction]
+#[text_signature = "(player_id, pokemon_id, guild_id=None, /)"]
+fn remove_from_party(
+    py: Python,
+    player_id: String,
+    pokemon_id: String,
+    guild_id: Option<String>,
+) -> PyResult<PyObject> {
+    let change = PartyChange::Remove(database::parse_id(&pokemon_id)?);
+    change_party(py, player_id, guild_id, change)
+}
+
+/// Exchanges the pokemon in two party slots. Slots are numbered from 0, which
+/// is the lead.
+///
+/// # Returns
+///
+/// The new party. Raises `ValueError` if either slot is empty.
+#[pyfunction]
+#[text_signature = "(player_id, first_slot, second_slot, guild_id=None, /)"]
+fn swap_party_slots(
+    py: Python,
+    player_id: String,
+    first_slot: usize,
+    second_slot: usize,
+    guild_id: Option<String>,
+) -> PyResult<PyObject> {
+    change_party(py, player_id, guild_id, PartyChange::Swap(first_slot, second_slot))
+}
+
+/// Moves the pokemon in one party slot to another, shifting the pokemon in
+/// between. Slots are numbered from 0, which is the lead.
+///
+/// # Returns
+///
+/// The new party. Raises `ValueError` if either slot is empty.
+#[pyfunction]
+#[text_signature = "(player_id, from_slot, to_slot, guild_id=None, /)"]
+fn move_party_slot(
+    py: Python,
+    player_id: String,
+    from_slot: usize,
+    to_slot: usize,
+    guild_id: Option<String>,
+) -> PyResult<PyObject> {
+    let change = PartyChange::Move {
+        from: from_slot,
+        to: to_slot,
+    };
+    change_party(py, player_id, guild_id, change)
+}
+
+/// Makes one of the player's pokemon their party's lead, adding it to the
+/// party if it isn't in it yet.
+///
+/// # Returns
+///
+/// The new party. Raises `ValueError` if the party is full or the player
+/// doesn't own the pokemon.
+#[pyfunction]
+#[text_signature = "(player_id, pokemon_id, guild_id=None, /)"]
+fn set_party_lead(py: Python, player_id: String, pokemon_id: String, guild_id: Option<String>) -> PyResult<PyObject> {
+    let change = PartyChange::SetLead(database::parse_id(&pokemon_id)?);
+    change_party(py, player_id, guild_id, change)
+}
+
+fn change_party(py: Python, player_id: String, guild_id: Option<String>, change: PartyChange) -> PyResult<PyObject> {
+    pytokio::into_coroutine(py, async move {
+        let repo = MongoRepository::new(database::get()?);
+        let key = guilds::player_key(&repo, guild_id.as_deref(), &player_id).await?;
+        let party = handlers::change_party(&repo, &key, change).await?;
+        Ok(Python::with_gil(|py| to_py(py, party)))
+    })
+}
+
+fn to_py(py: Python, party: Vec<records::OwnedPokemon>) -> PyObject {
+    party.into_iter().map(OwnedPokemon::from).collect::<Vec<_>>().into_py(py)
+}
@@ -0,0 +1,236 @@
+use std::collections::{BTreeSet, HashMap};
+
+use mongodb::bson::oid::ObjectId;
+
+use crate::database::{
+    operations::owned_pokemon,
+    query::{PokemonQuery, MAX_PAGE_SIZE},
+    records::{self, OwnedPokemon},
+    repository::{PlayerRepository, PokemonQueryRepository, Repository, Transactional, UnitOfWork},
+    Error,
+};
+use crate::error;
+use crate::models;
+use crate::pokedex::{Generation, Named, PokedexSource};
+
+/// Builds the profile of the player stored under `key`, or `None` if they aren't registered. `player_id` is their
+/// Discord user ID.
+pub async fn get_profile<R: Repository>(repo: &R, key: &str, player_id: &str) -> Result<Option<models::Player>, Error> {
+    let player = match repo.get_player(key).await? {
+        Some(player) => player,
+        None => return Ok(None),
+    };
+    let inventory = repo.get_inventory(key).await?;
+    let owned = repo.pokemon_owned_by(key).await?;
+
+    let find = |id: &ObjectId| owned.iter().find(|p| p.id == *id).cloned().map(models::OwnedPokemon::from);
+    let caught = caught_species(&player, &owned);
+    let seen = player.seen_species.union(&caught).count();
+
+    Ok(Some(models::Player {
+        player_id: player_id.to_string(),
+        registered_at: player.registered_at.timestamp_millis() as f64 / 1000.0,
+        starter: player.starter.as_ref().and_then(find),
+        total_caught: player.total_caught,
+        total_owned: owned.len(),
+        species_seen: seen,
+        species_caught: caught.len(),
+        balance: inventory.currency,
+        selected: player.selected.as_ref().and_then(find),
+        favorites: player.favorites.iter().filter_map(find).collect(),
+        party: player.party.iter().filter_map(find).collect(),
+        badges: player.badges,
+    }))
+}
+
+/// Every species a player has caught. Owned pokemon count too, in case they were added without going through a catch.
+fn caught_species(player: &records::Player, owned: &[OwnedPokemon]) -> BTreeSet<u32> {
+    player
+        .caught_species
+        .iter()
+        .copied()
+        .chain(owned.iter().map(|p| p.species_id))
+        .collect()
+}
+
+/// How much of each generation's Pokedex the player stored under `key` has completed, oldest generation first.
+/// Generations are read from PokeAPI, so new ones are picked up without code changes.
+pub async fn pokedex_completion<R: Repository, P: PokedexSource>(
+    repo: &R,
+    pokedex: &mut P,
+    key: &str,
+    languages: &[String],
+) -> Result<Vec<models::DexCompletion>, error::Error> {
+    let player = registered_player(repo, key).await?;
+    let caught = caught_species(&player, &repo.pokemon_owned_by(key).await?);
+    let seen: BTreeSet<u32> = player.seen_species.union(&caught).copied().collect();
+
+    let generation_refs = pokedex.list::<Generation>().await?;
+    let mut generations = pokedex.get_all_by_ref(&generation_refs).await?;
+    generations.sort_by_key(|g| g.id);
+    let region_refs: Vec<_> = generations.iter().map(|g| g.main_region.clone()).collect();
+    let regions = pokedex.get_all_by_ref(&region_refs).await?;
+
+    let species_by_generation: Vec<Vec<_>> = generations
+        .iter()
+        .map(|generation| {
+            let mut species: Vec<_> = generation
+                .pokemon_species
+                .iter()
+                .filter_map(|reference| Some((reference.id()? as u32, reference)))
+                .collect();
+            species.sort_by_key(|(id, _)| *id);
+            species
+        })
+        .collect();
+    // Look up every missing species together, so each is only fetched once
+    let missing_refs: Vec<_> = species_by_generation
+        .iter()
+        .flatten()
+        .filter(|(id, _)| !caught.contains(id))
+        .map(|(_, reference)| (*reference).clone())
+        .collect();
+    let missing_names: HashMap<usize, String> = pokedex
+        .get_all_by_ref(&missing_refs)
+        .await?
+        .into_iter()
+        .map(|missing| {
+            let name = missing.localized_name(languages).unwrap_or(missing.name.as_str()).to_string();
+            (missing.id, name)
+        })
+        .collect();
+
+    let mut completion = Vec::with_capacity(generations.len());
+    for ((generation, region), species) in generations.iter().zip(&regions).zip(&species_by_generation) {
+        let seen_count = species.iter().filter(|(id, _)| seen.contains(id)).count();
+        let caught_count = species.iter().filter(|(id, _)| caught.contains(id)).count();
+        let missing_species = species
+            .iter()
+            .filter(|(id, _)| !caught.contains(id))
+            .filter_map(|(id, _)| missing_names.get(&(*id as usize)).cloned())
+            .collect();
+
+        let percentage = |count: usize| match species.len() {
+            0 => 0.0,
+            total => count as f64 * 100.0 / total as f64,
+        };
+        completion.push(models::DexCompletion {
+            generation: generation
+                .localized_name(languages)
+                .unwrap_or(generation.name.as_str())
+                .to_string(),
+            region: region.localized_name(languages).unwrap_or(region.name.as_str()).to_string(),
+            total: species.len(),
+            seen: seen_count,
+            caught: caught_count,
+            seen_percentage: percentage(seen_count),
+            caught_percentage: percentage(caught_count),
+            missing_species,
+        });
+    }
+    Ok(completion)
+}
+
+/// The player stored under `key`, failing with [`Error::NotRegistered`] if there is none.
+pub async fn registered_player<R: Repository>(repo: &R, key: &str) -> Result<records::Player, Error> {
+    repo.get_player(key)
+        .await?
+        .ok_or_else(|| Error::NotRegistered(key.to_string()))
+}
+
+/// Makes a pokemon the player's selected pokemon.
+pub async fn select_pokemon<R: Transactional>(repo: &R, key: &str, id: ObjectId) -> Result<OwnedPokemon, Error> {
+    // The player is written back whole, so concurrent changes to it (e.g. a catch) must conflict rather than be lost
+    let work = repo.begin().await?;
+    let mut player = registered_player(&work, key).await?;
+    let pokemon = owned_pokemon(&work, key, id).await?;
+    player.selected = Some(pokemon.id);
+    work.update_player(&player).await?;
+    work.commit().await?;
+    Ok(pokemon)
+}
+
+/// One page of the pokemon owned by the player stored under `key`.
+pub async fn list_pokemon<R: PokemonQueryRepository>(
+    repo: &R,
+    key: &str,
+    mut query: PokemonQuery,
+) -> Result<models::PokemonPage, Error> {
+    query.validate()?;
+    query.limit = query.limit.clamp(1, MAX_PAGE_SIZE);
+    let page = repo.query_pokemon(key, &query).await?;
+    Ok(models::PokemonPage {
+        pokemon: page.items.into_iter().map(models::OwnedPokemon::from).collect(),
+        next_cursor: page.next.map(|cursor| cursor.to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::repository::{MemoryRepository, PlayerRepository, PokemonRepository};
+    use crate::pokedex::test_data::{generation, region, species};
+    use crate::pokedex::MemoryPokedex;
+
+    /// A repository where `ash` and `misty` each own one pokemon.
+    async fn two_trainers() -> (MemoryRepository, OwnedPokemon, OwnedPokemon) {
+        let repo = MemoryRepository::new();
+        let mut rng = rand::thread_rng();
+        let pikachu = OwnedPokemon::generate("ash", 25, "pikachu", 25, 5, &mut rng);
+        let staryu = OwnedPokemon::generate("misty", 120, "staryu", 120, 5, &mut rng);
+        for (key, pokemon) in &[("ash", &pikachu), ("misty", &staryu)] {
+            repo.insert_player(&records::Player::new(key)).await.unwrap();
+            repo.insert_pokemon(pokemon).await.unwrap();
+        }
+        (repo, pikachu, staryu)
+    }
+
+    #[tokio::test]
+    async fn selecting_an_owned_pokemon() {
+        let (repo, pikachu, _) = two_trainers().await;
+        let selected = select_pokemon(&repo, "ash", pikachu.id).await.unwrap();
+        assert_eq!(selected.id, pikachu.id);
+        assert_eq!(repo.get_player("ash").await.unwrap().unwrap().selected, Some(pikachu.id));
+    }
+
+    #[tokio::test]
+    async fn selecting_someone_elses_pokemon_fails() {
+        let (repo, _, staryu) = two_trainers().await;
+        let result = select_pokemon(&repo, "ash", staryu.id).await;
+        assert!(matches!(result, Err(Error::NotOwned { .. })));
+        assert_eq!(repo.get_player("ash").await.unwrap().unwrap().selected, None);
+    }
+
+    #[tokio::test]
+    async fn selecting_for_an_unregistered_player_fails() {
+        let (repo, pikachu, _) = two_trainers().await;
+        let result = select_pokemon(&repo, "brock", pikachu.id).await;
+        assert!(matches!(result, Err(Error::NotRegistered(_))));
+    }
+
+    #[tokio::test]
+    async fn pokedex_completion_counts_seen_and_caught_species() {
+        let starters = [(1, "bulbasaur", "Bulbasaur"), (4, "charmander", "Charmander"), (25, "pikachu", "Pikachu")];
+        let mut pokedex = MemoryPokedex::new();
+        pokedex.insert(&region(1, "kanto", "Kanto")).unwrap();
+        let generation_species: Vec<_> = starters.iter().map(|&(id, name, _)| (id, name)).collect();
+        pokedex.insert(&generation(1, "generation-i", 1, &generation_species)).unwrap();
+        for &(id, name, display_name) in &starters {
+            pokedex.insert(&species(id, name, display_name, &[(id, name, true)])).unwrap();
+        }
+
+        // Ash owns a Pikachu, and has also seen a Charmander
+        let (repo, _, _) = two_trainers().await;
+        let mut ash = repo.get_player("ash").await.unwrap().unwrap();
+        ash.seen_species.insert(4);
+        repo.update_player(&ash).await.unwrap();
+
+        let completion = pokedex_completion(&repo, &mut pokedex, "ash", &["en".to_string()]).await.unwrap();
+        assert_eq!(completion.len(), 1);
+        let kanto = &completion[0];
+        assert_eq!((kanto.generation.as_str(), kanto.region.as_str()), ("generation-i", "Kanto"));
+        assert_eq!((kanto.total, kanto.seen, kanto.caught), (3, 2, 1));
+        assert!((kanto.caught_percentage - 100.0 / 3.0).abs() < 1e-9);
+        assert_eq!(kanto.missing_species, ["Bulbasaur", "Charmander"]);
+    }
+}
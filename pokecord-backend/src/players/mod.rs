@@ -0,0 +1,165 @@
+//! The `players` module contains code for reading and changing a registered
+//! player's trainer profile.
+
+use crate::database::{
+    self,
+    query::{PokemonFilter, PokemonQuery},
+    MongoRepository,
+};
+use crate::guilds;
+use crate::models::OwnedPokemon;
+use crate::pokedex::Pokedex;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use pyo3_asyncio::tokio as pytokio;
+
+mod handlers;
+
+// Adds all required functions into the module.
+pub fn init_submodule(module: &PyModule) -> PyResult<()> {
+    module.add_function(pyo3::wrap_pyfunction!(get_profile, module)?)?;
+    module.add_function(pyo3::wrap_pyfunction!(select_pokemon, module)?)?;
+    module.add_function(pyo3::wrap_pyfunction!(get_pokedex_completion, module)?)?;
+    module.add_function(pyo3::wrap_pyfunction!(list_owned_pokemon, module)?)?;
+    Ok(())
+}
+
+/// Fetches a player's trainer profile, in `guild_id` if given.
+///
+/// # Returns
+///
+/// The player's `Player` profile, or `None` if they aren't registered.
+#[pyfunction]
+#[text_signature = "(player_id, guild_id=None, /)"]
+fn get_profile(py: Python, player_id: String, guild_id: Option<String>) -> PyResult<PyObject> {
+    pytokio::into_coroutine(py, async move {
+        let repo = MongoRepository::new(database::get()?);
+        let key = guilds::player_key(&repo, guild_id.as_deref(), &player_id).await?;
+        let profile = handlers::get_profile(&repo, &key, &player_id).await?;
+        Ok(Python::with_gil(|py| profile.into_py(py)))
+    })
+}
+
+/// Selects which of a player's pokemon gains experience as they chat.
+///
+/// # Arguments
+///
+/// * `pokemon_id` - ID of an `OwnedPokemon` belonging to the player.
+///
+/// # Returns
+///
+/// The selected `OwnedPokemon`. Raises `KeyError` if the player isn't
+/// registered and `ValueError` if they don't own the pokemon.
+#[pyfunction]
+#[text_signature = "(player_id, pokemon_id, guild_id=None, /)"]
+fn select_pokemon(py: Python, player_id: String, pokemon_id: String, guild_id: Option<String>) -> PyResult<PyObject> {
+    let pokemon_id = database::parse_id(&pokemon_id)?;
+    pytokio::into_coroutine(py, async move {
+        let repo = MongoRepository::new(database::get()?);
+        let key = guilds::player_key(&repo, guild_id.as_deref(), &player_id).await?;
+        let pokemon = OwnedPokemon::from(handlers::select_pokemon(&repo, &key, pokemon_id).await?);
+        Ok(Python::with_gil(|py| pokemon.into_py(py)))
+    })
+}
+
+/// Reports how much of each generation's Pokedex a player has seen and
+/// caught. This fetches every species the player is missing, so it is slow on
+/// a cold cache.
+///
+/// # Arguments
+///
+/// * `languages` - Optional `list` of PokeAPI language names to show
+///   generation, region and species names in. Defaults to the guild's
+///   language.
+///
+/// # Returns
+///
+/// A `list` of `DexCompletion`, oldest generation first. Raises `KeyError`
+/// if the player isn't registered.
+#[pyfunction]
+#[text_signature = "(player_id, guild_id=None, languages=None, /)"]
+fn get_pokedex_completion(
+    py: Python,
+    player_id: String,
+    guild_id: Option<String>,
+    languages: Option<Vec<String>>,
+) -> PyResult<PyObject> {
+    pytokio::into_coroutine(py, async move {
+        let repo = MongoRepository::new(database::get()?);
+        let key = guilds::player_key(&repo, guild_id.as_deref(), &player_id).await?;
+        let languages = guilds::languages(&repo, guild_id.as_deref(), languages).await?;
+        let completion = handlers::pokedex_completion(&repo, &mut Pokedex::new(), &key, &languages).await?;
+        Ok(Python::with_gil(|py| completion.into_py(py)))
+    })
+}
+
+/// Lists a page of a player's pokemon. Pass the returned `next_cursor` back
+/// to get the following page.
+///
+/// # Arguments
+///
+/// * `cursor` - `next_cursor` of the previous page, or `None` for the first
+///   page.
+/// * `limit` - Page size, at most 100.
+/// * `sort` - One of `level`, `iv`, `dex-number` or `catch-date`, prefixed
+///   with `-` to sort in descending order. Defaults to `catch-date`.
+/// * `filters` - Optional `dict` of conditions that must all match. Keys are
+///   `species` and `type` (API names), `shiny` and `legendary` (`bool`),
+///   `min_level` and `max_level`, `nickname` (text to search for) and
+///   `min_iv` and `max_iv` (IV percentages).
+///
+/// # Returns
+///
+/// A `PokemonPage`. Raises `ValueError` for an invalid sort, filter or
+/// cursor, or a cursor from a listing with a different sort.
+#[pyfunction(limit = "20")]
+#[text_signature = "(player_id, guild_id=None, cursor=None, limit=20, sort=None, filters=None, /)"]
+fn list_owned_pokemon(
+    py: Python,
+    player_id: String,
+    guild_id: Option<String>,
+    cursor: Option<String>,
+    limit: usize,
+    sort: Option<String>,
+    filters: Option<&PyDict>,
+) -> PyResult<PyObject> {
+    let mut query = PokemonQuery {
+        filter: match filters {
+            Some(filters) => parse_filter(filters)?,
+            None => PokemonFilter::default(),
+        },
+        after: cursor.map(|c| c.parse()).transpose()?,
+        limit,
+        ..PokemonQuery::default()
+    };
+    if let Some(sort) = sort {
+        query.descending = sort.starts_with('-');
+        query.sort = sort.trim_start_matches('-').parse()?;
+    }
+    pytokio::into_coroutine(py, async move {
+        let repo = MongoRepository::new(database::get()?);
+        let key = guilds::player_key(&repo, guild_id.as_deref(), &player_id).await?;
+        let page = handlers::list_pokemon(&repo, &key, query).await?;
+        Ok(Python::with_gil(|py| page.into_py(py)))
+    })
+}
+
+fn parse_filter(filters: &PyDict) -> PyResult<PokemonFilter> {
+    let mut filter = PokemonFilter::default();
+    for (key, value) in filters {
+        match key.extract::<&str>()? {
+            "species" => filter.species = value.extract()?,
+            "type" => filter.type_name = value.extract()?,
+            "shiny" => filter.shiny = value.extract()?,
+            "legendary" => filter.legendary = value.extract()?,
+            "min_level" => filter.min_level = value.extract()?,
+            "max_level" => filter.max_level = value.extract()?,
+            "nickname" => filter.nickname = value.extract()?,
+            "min_iv" => filter.min_iv = value.extract()?,
+            "max_iv" => filter.max_iv = value.extract()?,
+            other => return Err(PyValueError::new_err(format!("unknown filter `{}`", other))),
+        }
+    }
+    Ok(filter)
+}
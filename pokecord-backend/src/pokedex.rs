@@ -1,20 +1,38 @@
-use std::time::SystemTime;
+use std::{path::PathBuf, time::SystemTime};
 
 use http_cache_semantics::{AfterResponse, BeforeRequest, CacheOptions, CachePolicy};
-use reqwest::Client;
+use reqwest::{Client, Request, Response};
 use serde::{de::DeserializeOwned, Serialize};
 use url::Url;
 
 mod api_models;
-mod cache;
+pub mod cache;
+mod dump;
 mod error;
+pub mod fixtures;
+mod localization;
+mod memory;
+mod search;
+mod source;
+mod sprites;
+mod stats;
+#[cfg(test)]
+pub mod test_data;
+pub mod warmup;
 
 pub use api_models::*;
 pub use error::Error;
-
-use crate::pokedex::cache::Entry;
-
-use self::cache::Cache;
+pub use localization::{localize, normalize_flavor_text, Localized, Named, FALLBACK_LANGUAGE};
+pub use memory::MemoryPokedex;
+pub use search::{normalize as normalize_name, NameIndex, SearchMatch};
+pub use source::PokedexSource;
+pub use sprites::SpriteVariant;
+pub use stats::CacheStats;
+
+use self::cache::{Cache, CacheKey, Entry};
+use self::dump::Dump;
+use self::fixtures::Recorder;
+use self::stats::{record, COUNTERS};
 
 /// Base URL for all PokeAPI endpoints.
 const API_BASE: &str = "https://pokeapi.co/api/v2/";
@@ -23,6 +41,13 @@ const API_BASE: &str = "https://pokeapi.co/api/v2/";
 pub struct Pokedex {
     client: Client,
     cache: Cache,
+    /// Local copy of the API to serve all requests from instead of the network, if any.
+    dump: Option<Dump>,
+    /// Server to send API requests to instead of PokeAPI, e.g. a fixture [`ReplayServer`](fixtures::ReplayServer).
+    /// Cache keys and resource URLs are unaffected.
+    api_base: Option<Url>,
+    /// Where to save every response as a fixture, if anywhere.
+    recorder: Option<Recorder>,
 }
 
 /// Cursor for paginating through an API list.
@@ -41,9 +66,56 @@ impl Pokedex {
         Pokedex {
             client: Client::new(),
             cache: Cache::new(),
+            dump: None,
+            api_base: None,
+            recorder: None,
+        }
+    }
+
+    /// Send requests to the API at `api_base` instead of PokeAPI, e.g. a fixture
+    /// [`ReplayServer`](fixtures::ReplayServer).
+    pub fn with_api_base(self, api_base: Url) -> Pokedex {
+        Pokedex {
+            api_base: Some(api_base),
+            ..self
+        }
+    }
+
+    /// Save every response received as a fixture.
+    pub fn recording(self, recorder: Recorder) -> Pokedex {
+        Pokedex {
+            recorder: Some(recorder),
+            ..self
+        }
+    }
+
+    /// Create a PokeAPI client that caches responses in `cache`.
+    pub fn with_cache(cache: Cache) -> Pokedex {
+        Pokedex {
+            cache,
+            ..Pokedex::new()
         }
     }
 
+    /// Create a PokeAPI client that never uses the network, and instead serves every resource from a checkout of
+    /// [PokeAPI's static data](https://github.com/PokeAPI/api-data) at `root`.
+    pub fn offline<P: Into<PathBuf>>(root: P) -> Result<Pokedex, Error> {
+        Ok(Pokedex {
+            dump: Some(Dump::open(root)?),
+            ..Pokedex::new()
+        })
+    }
+
+    /// Cache counters since the process started, along with the current size of this client's cache.
+    pub async fn stats(&self) -> Result<CacheStats, Error> {
+        let (entries, disk_bytes) = self.cache.disk_usage().await?;
+        Ok(CacheStats {
+            entries,
+            disk_bytes,
+            ..COUNTERS.snapshot()
+        })
+    }
+
     /// Get an API resource by name.
     pub async fn get_by_name<T: ApiResource>(
         &mut self,
@@ -84,7 +156,7 @@ impl Pokedex {
 
         while let Some(c) = cursor {
             let (results, next_cursor) = self.page_list(c).await?;
-            acc.extend(results.into_iter());
+            acc.extend(results);
             cursor = next_cursor;
         }
 
@@ -118,101 +190,154 @@ impl Pokedex {
     }
 
     /// Make a cache-aware HTTP GET request for `url`.
+    ///
+    /// Responses are cached as `T` re-serialized, rather than the raw body, so only the fields `T` keeps are stored.
+    /// The cache policy still holds the response's validators, so revalidation works as normal.
     async fn get<T: Serialize + DeserializeOwned>(&mut self, url: Url) -> Result<T, Error> {
         log::debug!("Fetching {}", url);
 
+        if let Some(dump) = &self.dump {
+            return dump.get(&url).await;
+        }
+
         let cache_key = self.cache.cache_key(&url);
-        let mut req = self.client.get(url.clone()).build()?;
-
-        match self.cache.get(&cache_key).await {
-            Ok(Some(entry)) => {
-                let now = SystemTime::now();
-                log::debug!("Found cache entry for {} with age = {:?}, ttl = {:?}", url, entry.cache_policy.age(now), entry.cache_policy.time_to_live(now));
-                match entry.cache_policy.before_request(&req, SystemTime::now()) {
-                    // Cache was up to date, so use the value stored there
-                    BeforeRequest::Fresh(_) => {
-                        log::debug!("Can use fresh cache entry for {}", url);
-                        Ok(serde_json::from_slice::<T>(&entry.body)?)
-                    }
-                    BeforeRequest::Stale { request, .. } => {
-                        log::debug!("Cache entry for {} is stale, will revalidate", url);
-                        // Cache is stale, send a revalidation request - we only need to copy the revalidation headers
-                        *req.headers_mut() = request.headers;
-
-                        // Send the revalidation request - this is different from sending an uncached request because
-                        // the server may respond with a 304 not modified, in which case we still use the cached body.
-                        // .try_clone().unwrap() is safe because there's no request body
-                        let res = self
-                            .client
-                            .execute(req.try_clone().unwrap())
-                            .await?
-                            .error_for_status()?;
-                        let (policy, bytes) =
-                            match entry
-                                .cache_policy
-                                .after_response(&req, &res, SystemTime::now())
-                            {
-                                AfterResponse::NotModified(policy, _) => {
-                                    log::debug!("Server says cache entry for {} is up to date", url);
-                                    (policy, entry.body)
-                                }
-                                AfterResponse::Modified(policy, _) => {
-                                    log::debug!("Server returned modified data for {}", url);
-                                    (policy, res.bytes().await?)
-                                }
-                            };
-
-                        let value = serde_json::from_slice(bytes.as_ref())?;
-
-                        // Update the cache, but don't let this fail the whole request
-                        if policy.is_storable() {
-                            if let Err(err) =
-                                self.cache.put(&cache_key, &Entry::new(bytes, policy)).await
-                            {
-                                log::warn!("Cache update for {} failed: {}", url, err);
-                            }
-                        } else {
-                            log::debug!("Request for {} is not cacheable", url);
-                        }
+        let mut req = self.client.get(self.request_url(&url)).build()?;
 
-                        Ok(value)
-                    }
-                }
-            }
+        let entry = match self.cache.get(&cache_key).await {
+            Ok(Some(entry)) => entry,
+            // There's nothing in cache (or accessing it failed), we have to make a new request
             Ok(None) | Err(_) => {
-                // There's nothing in cache (or accessing it failed), we have to make a new request
                 log::debug!("No cache entry for {}", url);
+                return self.fetch(req, &url, &cache_key).await;
+            }
+        };
 
-                // .try_clone().unwrap() is safe because there's no request body
-                let res = self
-                    .client
-                    .execute(req.try_clone().unwrap())
-                    .await?
-                    .error_for_status()?;
-
-                // We _mostly_ want the defaults, but this is a private cache, not a shared one (i.e. a proxy), so we
-                // can cache more things.
-                let opts = CacheOptions {
-                    shared: false,
-                    ..Default::default()
-                };
-                let policy = CachePolicy::new_options(&req, &res, SystemTime::now(), opts);
-
-                let bytes = res.bytes().await?;
-                let value = serde_json::from_slice(bytes.as_ref())?;
-
-                if policy.is_storable() {
-                    if let Err(err) = self.cache.put(&cache_key, &Entry::new(bytes, policy)).await {
-                        log::warn!("Cache update for {} failed: {}", url, err);
-                    }
-                } else {
-                    log::debug!("Request for {} is not cacheable", url);
+        let now = SystemTime::now();
+        log::debug!("Found cache entry for {} with age = {:?}, ttl = {:?}", url, entry.cache_policy.age(now), entry.cache_policy.time_to_live(now));
+        match entry.cache_policy.before_request(&canonical_request(&req, &url), SystemTime::now()) {
+            // Cache was up to date, so use the value stored there
+            BeforeRequest::Fresh(_) => match serde_json::from_slice::<T>(&entry.body) {
+                Ok(value) => {
+                    log::debug!("Can use fresh cache entry for {}", url);
+                    record(&COUNTERS.fresh_hits);
+                    Ok(value)
                 }
+                Err(err) => {
+                    // Stored entries only hold the fields `T` had when they were written, which may not be enough
+                    log::debug!("Cache entry for {} doesn't match the current model ({}), refetching", url, err);
+                    self.fetch(req, &url, &cache_key).await
+                }
+            },
+            BeforeRequest::Stale { request, .. } => {
+                log::debug!("Cache entry for {} is stale, will revalidate", url);
+                // Cache is stale, send a revalidation request - we only need to copy the revalidation headers
+                *req.headers_mut() = request.headers;
+
+                // Send the revalidation request - this is different from sending an uncached request because
+                // the server may respond with a 304 not modified, in which case we still use the cached body.
+                // .try_clone().unwrap() is safe because there's no request body
+                let res = self.execute(req.try_clone().unwrap()).await?;
+                let (policy, value) =
+                    match entry
+                        .cache_policy
+                        .after_response(&canonical_request(&req, &url), &res, SystemTime::now())
+                    {
+                        AfterResponse::NotModified(policy, _) => match serde_json::from_slice(&entry.body) {
+                            Ok(value) => {
+                                log::debug!("Server says cache entry for {} is up to date", url);
+                                record(&COUNTERS.revalidated);
+                                (policy, value)
+                            }
+                            Err(err) => {
+                                log::debug!("Cache entry for {} doesn't match the current model ({}), refetching", url, err);
+                                // `req` carries the revalidation headers, which would just get another 304
+                                let req = self.client.get(self.request_url(&url)).build()?;
+                                return self.fetch(req, &url, &cache_key).await;
+                            }
+                        },
+                        AfterResponse::Modified(policy, _) => {
+                            log::debug!("Server returned modified data for {}", url);
+                            record(&COUNTERS.modified);
+                            (policy, serde_json::from_slice(res.bytes().await?.as_ref())?)
+                        }
+                    };
 
+                self.store(&cache_key, &url, &value, policy).await;
                 Ok(value)
             }
         }
     }
+
+    /// Make an HTTP GET request without revalidating, and cache the response.
+    async fn fetch<T: Serialize + DeserializeOwned>(
+        &mut self,
+        req: Request,
+        url: &Url,
+        cache_key: &CacheKey,
+    ) -> Result<T, Error> {
+        record(&COUNTERS.misses);
+
+        // .try_clone().unwrap() is safe because there's no request body
+        let res = self.execute(req.try_clone().unwrap()).await?;
+
+        // We _mostly_ want the defaults, but this is a private cache, not a shared one (i.e. a proxy), so we
+        // can cache more things.
+        let opts = CacheOptions {
+            shared: false,
+            ..Default::default()
+        };
+        let policy = CachePolicy::new_options(&canonical_request(&req, url), &res, SystemTime::now(), opts);
+
+        let value = serde_json::from_slice(res.bytes().await?.as_ref())?;
+        self.store(cache_key, url, &value, policy).await;
+        Ok(value)
+    }
+
+    /// Send `req`, recording the response if there's a recorder.
+    async fn execute(&self, req: Request) -> Result<Response, Error> {
+        let res = self.client.execute(req).await?;
+        let res = match &self.recorder {
+            Some(recorder) => recorder.record(res).await?,
+            None => res,
+        };
+        Ok(res.error_for_status()?)
+    }
+
+    /// The URL to actually request for the API resource at `url`, taking `api_base` into account.
+    fn request_url(&self, url: &Url) -> Url {
+        let api_base = match &self.api_base {
+            Some(api_base) => api_base,
+            None => return url.clone(),
+        };
+        match url.as_str().strip_prefix(API_BASE) {
+            Some(endpoint) => api_base.join(endpoint).expect("Invalid API URL"),
+            None => url.clone(),
+        }
+    }
+
+    /// Store `value` in the cache, but don't let this fail the whole request.
+    async fn store<T: Serialize>(&mut self, cache_key: &CacheKey, url: &Url, value: &T, policy: CachePolicy) {
+        if !policy.is_storable() {
+            log::debug!("Request for {} is not cacheable", url);
+            record(&COUNTERS.uncacheable);
+            return;
+        }
+
+        let result = match serde_json::to_vec(value) {
+            Ok(body) => self.cache.put(cache_key, &Entry::new(body.into(), policy)).await,
+            Err(err) => Err(err.into()),
+        };
+        if let Err(err) = result {
+            log::warn!("Cache update for {} failed: {}", url, err);
+            record(&COUNTERS.write_failures);
+        }
+    }
+}
+
+impl Default for Pokedex {
+    fn default() -> Pokedex {
+        Pokedex::new()
+    }
 }
 
 /// Returns the image URL for a Pokemon ID
@@ -221,6 +346,15 @@ pub fn image_url(id: usize) -> Url {
         .expect("Generated an invalid image URL")
 }
 
+/// `req` as if it had been sent to the resource URL `url`, for checking against cache policies. Policies are always
+/// made against the resource URL, so entries stay usable whatever `api_base` requests were actually sent to.
+fn canonical_request(req: &Request, url: &Url) -> Request {
+    // .try_clone().unwrap() is safe because there's no request body
+    let mut req = req.try_clone().unwrap();
+    *req.url_mut() = url.clone();
+    req
+}
+
 /// Turn a relative path for an API endpoint into a fully-qualified URL.
 fn api_url(path: &str) -> Url {
     Url::parse(API_BASE)
@@ -228,3 +362,140 @@ fn api_url(path: &str) -> Url {
         .join(path)
         .expect("Invalid API URL")
 }
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::pokedex::fixtures::{Fixture, ReplayServer};
+
+    /// Cache-Control for entries that stay fresh for the whole test.
+    const FRESH: &str = "max-age=3600";
+    /// Cache-Control for entries that are stale as soon as they're stored, so every later request revalidates.
+    const STALE: &str = "max-age=0";
+
+    /// Only the name of a region, standing in for an older model than [`Region`] that cached fewer fields.
+    #[derive(Debug, Serialize, Deserialize)]
+    struct RegionName {
+        name: String,
+    }
+
+    /// A fixture directory and a cache directory.
+    struct Dirs {
+        _root: TempDir,
+        fixtures: PathBuf,
+        cache: PathBuf,
+    }
+
+    impl Dirs {
+        fn new() -> Dirs {
+            let root = tempfile::tempdir().unwrap();
+            Dirs {
+                fixtures: root.path().join("fixtures"),
+                cache: root.path().join("cache"),
+                _root: root,
+            }
+        }
+
+        /// Makes the API answer region 1 with `name`, using the given `Cache-Control` and `ETag` if any.
+        fn serve_region(&self, name: &str, cache_control: &str, etag: Option<&str>) {
+            let mut headers = vec![
+                ("content-type".to_string(), "application/json".to_string()),
+                ("cache-control".to_string(), cache_control.to_string()),
+            ];
+            if let Some(etag) = etag {
+                headers.push(("etag".to_string(), format!("\"{}\"", etag)));
+            }
+            let fixture = Fixture {
+                path: "/api/v2/region/1/".to_string(),
+                status: 200,
+                headers,
+                body: format!(r#"{{"id": 1, "name": "{}", "names": []}}"#, name),
+            };
+            std::fs::create_dir_all(&self.fixtures).unwrap();
+            std::fs::write(self.fixtures.join("region_1.json"), serde_json::to_vec(&fixture).unwrap()).unwrap();
+        }
+
+        /// Fetches region 1 as `T` through the cache, from a replay server started with the current fixtures.
+        async fn fetch<T: Serialize + DeserializeOwned>(&self) -> T {
+            let server = ReplayServer::start(&self.fixtures).await.unwrap();
+            let mut pokedex = Pokedex::with_cache(Cache::with_dir(&self.cache)).with_api_base(server.api_base());
+            pokedex.get(api_url("region/1/")).await.unwrap()
+        }
+
+        async fn fetch_name(&self) -> String {
+            self.fetch::<Region>().await.name
+        }
+    }
+
+    #[tokio::test]
+    async fn fresh_entries_are_served_from_cache() {
+        let dirs = Dirs::new();
+        dirs.serve_region("kanto", FRESH, None);
+        assert_eq!(dirs.fetch_name().await, "kanto");
+
+        dirs.serve_region("johto", FRESH, None);
+        assert_eq!(dirs.fetch_name().await, "kanto");
+    }
+
+    #[tokio::test]
+    async fn stale_entries_use_cached_body_when_not_modified() {
+        let dirs = Dirs::new();
+        dirs.serve_region("kanto", STALE, Some("1"));
+        assert_eq!(dirs.fetch_name().await, "kanto");
+
+        // Same validator, so the server answers 304 and never sends the new body
+        dirs.serve_region("johto", STALE, Some("1"));
+        assert_eq!(dirs.fetch_name().await, "kanto");
+    }
+
+    #[tokio::test]
+    async fn stale_entries_are_replaced_when_modified() {
+        let dirs = Dirs::new();
+        dirs.serve_region("kanto", STALE, Some("1"));
+        assert_eq!(dirs.fetch_name().await, "kanto");
+
+        dirs.serve_region("johto", STALE, Some("2"));
+        assert_eq!(dirs.fetch_name().await, "johto");
+
+        // The new body and validator were stored, so revalidating against them gets a 304
+        dirs.serve_region("hoenn", STALE, Some("2"));
+        assert_eq!(dirs.fetch_name().await, "johto");
+    }
+
+    #[tokio::test]
+    async fn non_storable_responses_are_not_cached() {
+        let dirs = Dirs::new();
+        dirs.serve_region("kanto", "no-store", None);
+        assert_eq!(dirs.fetch_name().await, "kanto");
+        assert!(Cache::with_dir(&dirs.cache).keys().await.unwrap().is_empty());
+
+        dirs.serve_region("johto", "no-store", None);
+        assert_eq!(dirs.fetch_name().await, "johto");
+    }
+
+    #[tokio::test]
+    async fn fresh_entries_for_an_older_model_are_refetched() {
+        let dirs = Dirs::new();
+        dirs.serve_region("kanto", FRESH, None);
+        assert_eq!(dirs.fetch::<RegionName>().await.name, "kanto");
+
+        let region: Region = dirs.fetch().await;
+        assert_eq!((region.id, region.name.as_str()), (1, "kanto"));
+    }
+
+    #[tokio::test]
+    async fn stale_entries_for_an_older_model_are_refetched_when_not_modified() {
+        let dirs = Dirs::new();
+        dirs.serve_region("kanto", STALE, Some("1"));
+        assert_eq!(dirs.fetch::<RegionName>().await.name, "kanto");
+
+        // The server says the entry is current, but it's missing fields, so the refetch must be unconditional
+        let region: Region = dirs.fetch().await;
+        assert_eq!((region.id, region.name.as_str()), (1, "kanto"));
+        // The full model was stored, so revalidating it works from now on
+        assert_eq!(dirs.fetch::<Region>().await, region);
+    }
+}
@@ -1,19 +1,29 @@
 //! Types for deserializing PokeAPI responses.
 
-use std::{fmt::Debug, marker::PhantomData};
+use std::{
+    fmt::Debug,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+};
 
 use serde::{Deserialize, Serialize};
 use url::Url;
 
-use super::api_url;
+use super::{api_url, Error, PokedexSource};
 
 /// A resource in the PokeAPI. Types implementing this trait can be automatically looked up by name/id
 /// and paginated over.
+///
+/// The fields of a type implementing this trait are also its stored form: responses are cached re-serialized from
+/// the type, so anything the type doesn't declare is dropped before caching.
 // According to https://serde.rs/lifetimes.html, we have to use this `for`-qualified lifetime instead of
 // requiring DeserializeOwned or Deserialize<'static>
-pub trait ApiResource: Debug + Clone + PartialEq + Eq + for<'de> Deserialize<'de> + Serialize {
+pub trait ApiResource: Debug + Clone + PartialEq + Eq + Send + Sync + for<'de> Deserialize<'de> + Serialize {
     /// The base URL for this API resource type
     fn base_url() -> Url;
+
+    /// The identifier for this resource.
+    fn id(&self) -> usize;
 }
 
 /// A page of a resource list. See the [`NamedApiResourceList`](https://pokeapi.co/docs/v2#named) type.
@@ -34,7 +44,7 @@ pub struct Page<T: ApiResource> {
 
 /// A named PokeAPI resource. This is typed to indicate what kind of API resource it points to.
 /// See [`NamedAPIResource`](https://pokeapi.co/docs/v2#namedapiresource)/
-#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct NamedResource<T: ApiResource> {
     pub name: String,
     pub url: Url,
@@ -43,6 +53,70 @@ pub struct NamedResource<T: ApiResource> {
     _typ: PhantomData<fn() -> T>,
 }
 
+impl<T: ApiResource> NamedResource<T> {
+    /// The identifier of the referenced resource, parsed from its URL. This allows sorting and deduplicating
+    /// references without fetching them. Returns `None` if the URL doesn't end in an ID.
+    pub fn id(&self) -> Option<usize> {
+        self.url
+            .path_segments()?
+            .rfind(|segment| !segment.is_empty())?
+            .parse()
+            .ok()
+    }
+
+    /// Fetch the referenced resource.
+    pub async fn resolve<P: PokedexSource>(&self, pokedex: &mut P) -> Result<T, Error> {
+        pokedex.get_by_ref(self).await
+    }
+}
+
+// Equality and hashing are implemented by hand because deriving them would require `T: Hash`. References are equal
+// if they point to the same URL.
+impl<T: ApiResource> PartialEq for NamedResource<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.url == other.url
+    }
+}
+
+impl<T: ApiResource> Eq for NamedResource<T> {}
+
+impl<T: ApiResource> Hash for NamedResource<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.url.hash(state);
+    }
+}
+
+/// A PokeAPI resource that is referenced by URL only, such as an evolution chain. See
+/// [`APIResource`](https://pokeapi.co/docs/v2#apiresource).
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct UnnamedResource<T: ApiResource> {
+    pub url: Url,
+    /// Tells the compiler that this type acts like it points to a `T`
+    #[serde(skip_serializing, default)]
+    _typ: PhantomData<fn() -> T>,
+}
+
+impl<T: ApiResource> UnnamedResource<T> {
+    /// Fetch the referenced resource.
+    pub async fn resolve<P: PokedexSource>(&self, pokedex: &mut P) -> Result<T, Error> {
+        // Lookups only use the URL, so the name can be left empty
+        let reference = NamedResource {
+            name: String::new(),
+            url: self.url.clone(),
+            _typ: PhantomData,
+        };
+        pokedex.get_by_ref(&reference).await
+    }
+}
+
+impl<T: ApiResource> PartialEq for UnnamedResource<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.url == other.url
+    }
+}
+
+impl<T: ApiResource> Eq for UnnamedResource<T> {}
+
 /// A localized name for a resource. See [`Name`](https://pokeapi.co/docs/v2#name)
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
 pub struct Name {
@@ -82,6 +156,163 @@ pub struct Pokemon {
     pub order: i32,
     /// The species this Pokémon belongs to.
     pub species: NamedResource<PokemonSpecies>,
+    /// A list of forms this Pokémon can take on.
+    pub forms: Vec<NamedResource<PokemonForm>>,
+    /// A set of sprites used to depict this Pokémon in the game.
+    pub sprites: PokemonSprites,
+    /// A list of details showing types this Pokémon has.
+    pub types: Vec<PokemonType>,
+    /// A list of base stat values for this Pokémon.
+    #[serde(default)]
+    pub stats: Vec<PokemonStat>,
+    /// A list of moves along with learn methods and level details pertaining to specific version groups.
+    #[serde(default)]
+    pub moves: Vec<PokemonMove>,
+}
+
+/// See [`PokemonStat`](https://pokeapi.co/docs/v2#pokemonstat)
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PokemonStat {
+    /// The stat the Pokémon has.
+    pub stat: NamedResource<Stat>,
+    /// The effort points (EV) the Pokémon has in the stat.
+    pub effort: u32,
+    /// The base value of the stat.
+    pub base_stat: u32,
+}
+
+/// See [`PokemonMove`](https://pokeapi.co/docs/v2#pokemonmove)
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PokemonMove {
+    /// The move the Pokémon can learn.
+    #[serde(rename = "move")]
+    pub mov: NamedResource<Move>,
+    /// The details of the version in which the Pokémon can learn the move.
+    pub version_group_details: Vec<PokemonMoveVersion>,
+}
+
+/// See [`PokemonMoveVersion`](https://pokeapi.co/docs/v2#pokemonmoveversion)
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PokemonMoveVersion {
+    /// The method by which the move is learned.
+    pub move_learn_method: NamedResource<MoveLearnMethod>,
+    /// The version group in which the move is learned.
+    pub version_group: NamedResource<VersionGroup>,
+    /// The minimum level to learn the move.
+    pub level_learned_at: u32,
+}
+
+/// See [`PokemonType`](https://pokeapi.co/docs/v2#pokemontype)
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PokemonType {
+    /// The order the Pokémon's types are listed in.
+    pub slot: u8,
+    /// The type the referenced Pokémon has.
+    #[serde(rename = "type")]
+    pub typ: NamedResource<Type>,
+}
+
+/// Images of a Pokemon. See [`PokemonSprites`](https://pokeapi.co/docs/v2#pokemonsprites).
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PokemonSprites {
+    /// The default in-game sprites.
+    #[serde(flatten)]
+    pub game: SpriteSet,
+    /// Artwork from outside the main series sprites.
+    #[serde(default)]
+    pub other: OtherSprites,
+    /// Sprites from specific game versions.
+    #[serde(default)]
+    pub versions: VersionSprites,
+}
+
+/// One set of images of a Pokemon. Any of these may be missing, since not every source has every variant.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct SpriteSet {
+    /// The default depiction of this Pokémon from the front in battle.
+    pub front_default: Option<Url>,
+    /// The shiny depiction of this Pokémon from the front in battle.
+    pub front_shiny: Option<Url>,
+    /// The female depiction of this Pokémon from the front in battle.
+    pub front_female: Option<Url>,
+    /// The shiny female depiction of this Pokémon from the front in battle.
+    pub front_shiny_female: Option<Url>,
+    /// The default depiction of this Pokémon from the back in battle.
+    pub back_default: Option<Url>,
+    /// The shiny depiction of this Pokémon from the back in battle.
+    pub back_shiny: Option<Url>,
+    /// The female depiction of this Pokémon from the back in battle.
+    pub back_female: Option<Url>,
+    /// The shiny female depiction of this Pokémon from the back in battle.
+    pub back_shiny_female: Option<Url>,
+}
+
+/// Sprites from outside the main series games, under `sprites.other`.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct OtherSprites {
+    /// Official Sugimori-style artwork.
+    #[serde(rename = "official-artwork")]
+    pub official_artwork: SpriteSet,
+    /// Renders from Pokémon HOME.
+    pub home: SpriteSet,
+    /// Vector art from the Dream World.
+    pub dream_world: SpriteSet,
+}
+
+/// Sprites from specific game versions, under `sprites.versions`. Only the versions we use are included.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct VersionSprites {
+    /// Sprites from Black and White.
+    #[serde(rename = "generation-v")]
+    pub generation_v: GenerationVSprites,
+}
+
+/// See [`VersionSprites`].
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct GenerationVSprites {
+    #[serde(rename = "black-white")]
+    pub black_white: BlackWhiteSprites,
+}
+
+/// See [`VersionSprites`].
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct BlackWhiteSprites {
+    /// Animated GIF sprites, the most recent main series animated sprites in the API.
+    pub animated: SpriteSet,
+}
+
+/// A form of a Pokemon. Forms are purely visual unless they are battle-only, and each form belongs to exactly one
+/// Pokemon variety. See [the API](https://pokeapi.co/docs/v2#pokemonform).
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PokemonForm {
+    /// The identifier for this resource.
+    pub id: usize,
+    /// The name for this resource.
+    pub name: String,
+    /// The order in which forms should be sorted within all forms. Multiple forms may have equal order, in which case
+    /// they should fall back on sorting by name.
+    pub order: i32,
+    /// The order in which forms should be sorted within a species' forms.
+    pub form_order: i32,
+    /// True for exactly one form used as the default for each Pokémon.
+    pub is_default: bool,
+    /// Whether or not this form can only happen during battle.
+    pub is_battle_only: bool,
+    /// Whether or not this form requires mega evolution.
+    pub is_mega: bool,
+    /// The name of this form.
+    pub form_name: String,
+    /// The Pokémon that can take on this form.
+    pub pokemon: NamedResource<Pokemon>,
+    /// The form specific full name of this Pokémon form, or empty if the form does not have a specific name.
+    pub names: Vec<Name>,
+    /// The form specific form name of this Pokémon form, or empty if the form does not have a specific name.
+    pub form_names: Vec<Name>,
 }
 
 /// A species of Pokemon. See [the API](https://pokeapi.co/docs/v2#pokemonspecies)
@@ -113,14 +344,29 @@ pub struct PokemonSpecies {
     pub has_gender_differences: bool,
     /// Whether or not this Pokémon has multiple forms and can switch between them.
     pub forms_switchable: bool,
-    // The rate at which this Pokémon species gains levels.
-    // pub growth_rate: NamedResource,
+    /// The rate at which this Pokémon species gains levels.
+    pub growth_rate: NamedResource<GrowthRate>,
+    /// The Pokémon species that evolves into this Pokémon species.
+    pub evolves_from_species: Option<NamedResource<PokemonSpecies>>,
+    /// The evolution chain this Pokémon species is a member of.
+    pub evolution_chain: UnnamedResource<EvolutionChain>,
     /// The name of this resource listed in different languages.
     pub names: Vec<Name>,
     /// A list of flavor text entries for this Pokémon species.
     pub flavor_text_entries: Vec<FlavorText>,
     /// Descriptions of different forms Pokémon take on within the Pokémon species.
     pub form_descriptions: Vec<Description>,
+    /// A list of the Pokémon that exist within this Pokémon species, such as regional variants and mega evolutions.
+    pub varieties: Vec<PokemonSpeciesVariety>,
+}
+
+/// See [`PokemonSpeciesVariety`](https://pokeapi.co/docs/v2#pokemonspeciesvariety)
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct PokemonSpeciesVariety {
+    /// Whether this variety is the default variety.
+    pub is_default: bool,
+    /// The Pokémon variety.
+    pub pokemon: NamedResource<Pokemon>,
 }
 
 /// See [`FlavorText`](https://pokeapi.co/docs/v2#flavortext)
@@ -142,21 +388,320 @@ pub struct Description {
     pub language: NamedResource<Language>,
 }
 
+/// A Pokemon or move type. See [the API](https://pokeapi.co/docs/v2#types).
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Type {
+    /// The identifier for this resource.
+    pub id: usize,
+    /// The name for this resource.
+    pub name: String,
+    /// A detail of how effective this type is toward others and vice versa.
+    pub damage_relations: TypeRelations,
+    /// The name of this resource listed in different languages.
+    pub names: Vec<Name>,
+}
+
+/// See [`TypeRelations`](https://pokeapi.co/docs/v2#typerelations)
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct TypeRelations {
+    /// A list of types this type has no effect on.
+    pub no_damage_to: Vec<NamedResource<Type>>,
+    /// A list of types this type is not very effective against.
+    pub half_damage_to: Vec<NamedResource<Type>>,
+    /// A list of types this type is very effective against.
+    pub double_damage_to: Vec<NamedResource<Type>>,
+    /// A list of types that have no effect on this type.
+    pub no_damage_from: Vec<NamedResource<Type>>,
+    /// A list of types that are not very effective against this type.
+    pub half_damage_from: Vec<NamedResource<Type>>,
+    /// A list of types that are very effective against this type.
+    pub double_damage_from: Vec<NamedResource<Type>>,
+}
+
+/// How quickly a species levels up. See [the API](https://pokeapi.co/docs/v2#growth-rates).
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct GrowthRate {
+    /// The identifier for this resource.
+    pub id: usize,
+    /// The name for this resource.
+    pub name: String,
+    /// The formula used to calculate the rate at which the Pokémon species gains level.
+    pub formula: String,
+    /// A list of levels and the amount of experienced needed to atain them based on this growth rate.
+    pub levels: Vec<GrowthRateExperienceLevel>,
+    /// A list of Pokémon species that gain levels at this growth rate.
+    pub pokemon_species: Vec<NamedResource<PokemonSpecies>>,
+}
+
+/// See [`GrowthRateExperienceLevel`](https://pokeapi.co/docs/v2#growthrateexperiencelevel)
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct GrowthRateExperienceLevel {
+    /// The level gained.
+    pub level: u32,
+    /// The amount of experience required to reach the referenced level.
+    pub experience: u32,
+}
+
+/// The family of species that evolve into each other. See [the API](https://pokeapi.co/docs/v2#evolution-chains).
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct EvolutionChain {
+    /// The identifier for this resource.
+    pub id: usize,
+    /// The base chain link object. Each link contains evolution details for a Pokémon in the chain.
+    pub chain: ChainLink,
+}
+
+/// See [`ChainLink`](https://pokeapi.co/docs/v2#chainlink)
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChainLink {
+    /// Whether or not this link is for a baby Pokémon. This would only ever be true on the base link.
+    pub is_baby: bool,
+    /// The Pokémon species at this point in the evolution chain.
+    pub species: NamedResource<PokemonSpecies>,
+    /// All details regarding the specific details of the referenced Pokémon species evolution.
+    pub evolution_details: Vec<EvolutionDetail>,
+    /// A List of chain objects.
+    pub evolves_to: Vec<ChainLink>,
+}
+
+/// One way of evolving into a species. Every condition that is set must hold. See
+/// [`EvolutionDetail`](https://pokeapi.co/docs/v2#evolutiondetail)
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct EvolutionDetail {
+    /// The item required to cause evolution this into Pokémon species.
+    pub item: Option<NamedResource<Item>>,
+    /// The type of event that triggers evolution into this Pokémon species.
+    pub trigger: NamedResource<EvolutionTrigger>,
+    /// The id of the gender of the evolving Pokémon species must be in order to evolve into this Pokémon species.
+    pub gender: Option<u8>,
+    /// The item the evolving Pokémon species must be holding during the evolution trigger event to evolve into this
+    /// Pokémon species.
+    pub held_item: Option<NamedResource<Item>>,
+    /// The move that must be known by the evolving Pokémon species during the evolution trigger event in order to
+    /// evolve into this Pokémon species.
+    pub known_move: Option<NamedResource<Move>>,
+    /// The evolving Pokémon species must know a move with this type during the evolution trigger event in order to
+    /// evolve into this Pokémon species.
+    pub known_move_type: Option<NamedResource<Type>>,
+    /// The location the evolution must be triggered at.
+    pub location: Option<NamedResource<Location>>,
+    /// The minimum required level of the evolving Pokémon species to evolve into this Pokémon species.
+    pub min_level: Option<u32>,
+    /// The minimum required level of happiness the evolving Pokémon species to evolve into this Pokémon species.
+    pub min_happiness: Option<u8>,
+    /// The minimum required level of beauty the evolving Pokémon species to evolve into this Pokémon species.
+    pub min_beauty: Option<u8>,
+    /// The minimum required level of affection the evolving Pokémon species to evolve into this Pokémon species.
+    pub min_affection: Option<u8>,
+    /// Whether or not it must be raining in the overworld to cause evolution this Pokémon species.
+    pub needs_overworld_rain: bool,
+    /// The Pokémon species that must be in the players party in order for the evolving Pokémon species to evolve
+    /// into this Pokémon species.
+    pub party_species: Option<NamedResource<PokemonSpecies>>,
+    /// The player must have a Pokémon of this type in their party during the evolution trigger event in order for
+    /// the evolving Pokémon species to evolve into this Pokémon species.
+    pub party_type: Option<NamedResource<Type>>,
+    /// The required relation between the Pokémon's Attack and Defense stats. 1 means Attack > Defense. 0 means
+    /// Attack = Defense. -1 means Attack < Defense.
+    pub relative_physical_stats: Option<i8>,
+    /// The required time of day. Day or night, or empty for any time.
+    pub time_of_day: String,
+    /// Pokémon species for which this one must be traded.
+    pub trade_species: Option<NamedResource<PokemonSpecies>>,
+    /// Whether or not the 3DS needs to be turned upside-down as this Pokémon levels up.
+    pub turn_upside_down: bool,
+}
+
+/// An event that causes a Pokémon to evolve, such as leveling up or using an item. See
+/// [the API](https://pokeapi.co/docs/v2#evolution-triggers).
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct EvolutionTrigger {
+    /// The identifier for this resource.
+    pub id: usize,
+    /// The name for this resource.
+    pub name: String,
+    /// The name of this resource listed in different languages.
+    pub names: Vec<Name>,
+}
+
+/// An object that can be held by a Pokémon or used on it. See [the API](https://pokeapi.co/docs/v2#item).
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    /// The identifier for this resource.
+    pub id: usize,
+    /// The name for this resource.
+    pub name: String,
+    /// The price of this item in stores.
+    pub cost: u32,
+    /// The name of this resource listed in different languages.
+    pub names: Vec<Name>,
+}
+
+/// A place in the Pokémon world. See [the API](https://pokeapi.co/docs/v2#locations).
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    /// The identifier for this resource.
+    pub id: usize,
+    /// The name for this resource.
+    pub name: String,
+    /// The name of this resource listed in different languages.
+    pub names: Vec<Name>,
+}
+
+/// A skill Pokémon use in battle. See [the API](https://pokeapi.co/docs/v2#moves).
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Move {
+    /// The identifier for this resource.
+    pub id: usize,
+    /// The name for this resource.
+    pub name: String,
+    /// The percent value of how likely this move is to be successful, or `None` if it never misses.
+    pub accuracy: Option<u8>,
+    /// Power points. The number of times this move can be used.
+    pub pp: Option<u8>,
+    /// A value between -8 and 8. Sets the order in which moves are executed during battle.
+    pub priority: i8,
+    /// The base power of this move, or `None` if it doesn't deal damage based on power.
+    pub power: Option<u32>,
+    /// The type of damage the move inflicts on the target, e.g. physical.
+    pub damage_class: Option<NamedResource<MoveDamageClass>>,
+    /// Metadata about this move.
+    pub meta: Option<MoveMetaData>,
+    /// The elemental type of this move.
+    #[serde(rename = "type")]
+    pub typ: NamedResource<Type>,
+    /// The name of this resource listed in different languages.
+    pub names: Vec<Name>,
+}
+
+/// See [`MoveMetaData`](https://pokeapi.co/docs/v2#movemetadata)
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct MoveMetaData {
+    /// Critical hit rate bonus.
+    pub crit_rate: u8,
+}
+
+/// Whether a move deals physical or special damage, or none. See
+/// [the API](https://pokeapi.co/docs/v2#move-damage-classes).
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct MoveDamageClass {
+    /// The identifier for this resource.
+    pub id: usize,
+    /// The name for this resource.
+    pub name: String,
+    /// The name of this resource listed in different languages.
+    pub names: Vec<Name>,
+}
+
+/// A way a Pokémon can learn a move, such as leveling up. See
+/// [the API](https://pokeapi.co/docs/v2#move-learn-methods).
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct MoveLearnMethod {
+    /// The identifier for this resource.
+    pub id: usize,
+    /// The name for this resource.
+    pub name: String,
+    /// The name of this resource listed in different languages.
+    pub names: Vec<Name>,
+}
+
+/// Games that share most of their data. See [the API](https://pokeapi.co/docs/v2#version-groups).
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct VersionGroup {
+    /// The identifier for this resource.
+    pub id: usize,
+    /// The name for this resource.
+    pub name: String,
+    /// Order for sorting. Almost by date of release, except similar versions are grouped together.
+    pub order: u32,
+}
+
+/// A Pokémon stat, such as attack. See [the API](https://pokeapi.co/docs/v2#stats).
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Stat {
+    /// The identifier for this resource.
+    pub id: usize,
+    /// The name for this resource.
+    pub name: String,
+    /// The name of this resource listed in different languages.
+    pub names: Vec<Name>,
+}
+
+/// A nature, which raises one stat of a Pokémon and lowers another. See
+/// [the API](https://pokeapi.co/docs/v2#natures).
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Nature {
+    /// The identifier for this resource.
+    pub id: usize,
+    /// The name for this resource.
+    pub name: String,
+    /// The stat decreased by 10% in Pokémon with this nature.
+    pub decreased_stat: Option<NamedResource<Stat>>,
+    /// The stat increased by 10% in Pokémon with this nature.
+    pub increased_stat: Option<NamedResource<Stat>>,
+    /// The name of this resource listed in different languages.
+    pub names: Vec<Name>,
+}
+
+/// A group of games and the species introduced in them. See [the API](https://pokeapi.co/docs/v2#generations).
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Generation {
+    /// The identifier for this resource.
+    pub id: usize,
+    /// The name for this resource.
+    pub name: String,
+    /// The main region travelled in this generation.
+    pub main_region: NamedResource<Region>,
+    /// The name of this resource listed in different languages.
+    pub names: Vec<Name>,
+    /// A list of Pokémon species that were introduced in this generation.
+    pub pokemon_species: Vec<NamedResource<PokemonSpecies>>,
+}
+
+/// An area of the Pokémon world. See [the API](https://pokeapi.co/docs/v2#regions).
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Region {
+    /// The identifier for this resource.
+    pub id: usize,
+    /// The name for this resource.
+    pub name: String,
+    /// The name of this resource listed in different languages.
+    pub names: Vec<Name>,
+}
+
 impl ApiResource for Language {
     fn base_url() -> Url {
         api_url("language/")
     }
+
+    fn id(&self) -> usize {
+        self.id
+    }
 }
 
 impl ApiResource for Pokemon {
     fn base_url() -> Url {
         api_url("pokemon/")
     }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl ApiResource for PokemonForm {
+    fn base_url() -> Url {
+        api_url("pokemon-form/")
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
 }
 
 impl PartialOrd for Pokemon {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.order.partial_cmp(&other.order)
+        Some(self.cmp(other))
     }
 }
 
@@ -170,4 +715,148 @@ impl ApiResource for PokemonSpecies {
     fn base_url() -> Url {
         api_url("pokemon-species/")
     }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl ApiResource for Type {
+    fn base_url() -> Url {
+        api_url("type/")
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl ApiResource for GrowthRate {
+    fn base_url() -> Url {
+        api_url("growth-rate/")
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl ApiResource for Generation {
+    fn base_url() -> Url {
+        api_url("generation/")
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl ApiResource for Region {
+    fn base_url() -> Url {
+        api_url("region/")
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl ApiResource for EvolutionChain {
+    fn base_url() -> Url {
+        api_url("evolution-chain/")
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl ApiResource for EvolutionTrigger {
+    fn base_url() -> Url {
+        api_url("evolution-trigger/")
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl ApiResource for Item {
+    fn base_url() -> Url {
+        api_url("item/")
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl ApiResource for Location {
+    fn base_url() -> Url {
+        api_url("location/")
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl ApiResource for Move {
+    fn base_url() -> Url {
+        api_url("move/")
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl ApiResource for MoveDamageClass {
+    fn base_url() -> Url {
+        api_url("move-damage-class/")
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl ApiResource for MoveLearnMethod {
+    fn base_url() -> Url {
+        api_url("move-learn-method/")
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl ApiResource for VersionGroup {
+    fn base_url() -> Url {
+        api_url("version-group/")
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl ApiResource for Stat {
+    fn base_url() -> Url {
+        api_url("stat/")
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl ApiResource for Nature {
+    fn base_url() -> Url {
+        api_url("nature/")
+    }
+
+    fn id(&self) -> usize {
+        self.id
+    }
 }
@@ -1,28 +1,85 @@
-use std::{fmt::{self, Display, Write}, io::ErrorKind, path::PathBuf};
+use std::{
+    fmt::{self, Write},
+    path::PathBuf,
+    sync::Arc,
+};
 
+use async_trait::async_trait;
 use bytes::Bytes;
 use http_cache_semantics::CachePolicy;
+use once_cell::sync::{Lazy, OnceCell};
 use serde::{Deserialize, Serialize};
 use sha3::{Digest, Sha3_384};
-use tokio::fs;
 use url::Url;
 
 use super::Error;
 
-/// Filesystem-backed, URL-addressed cache for HTTP requests.
+mod format;
+mod fs;
+mod mongo;
+
+pub use format::Compression;
+pub use fs::FsStore;
+pub use mongo::MongoStore;
+
+/// URL-addressed cache for HTTP requests, on top of a pluggable [`Store`].
 ///
 /// ## TODOs:
-/// * In-memory cache on top of filesystem cache
-#[derive(Debug)]
+/// * In-memory cache on top of the backing store
+#[derive(Debug, Clone)]
 pub struct Cache {
-    dir: PathBuf,
+    store: Arc<dyn Store>,
+    /// How new entries are compressed. Entries are readable whatever this is set to.
+    compression: Compression,
+}
+
+/// Storage backend for a [`Cache`]. Stores map string keys, which may contain `/`-separated prefixes, to raw bytes.
+#[async_trait]
+pub trait Store: fmt::Debug + Send + Sync {
+    /// Read the value for `key`, or `None` if there isn't one.
+    async fn read(&self, key: &str) -> Result<Option<Vec<u8>>, Error>;
+
+    /// Create or replace the value for `key`.
+    async fn write(&self, key: &str, data: Vec<u8>) -> Result<(), Error>;
+
+    /// Remove the value for `key`. Removing a key that doesn't exist is not an error.
+    async fn remove(&self, key: &str) -> Result<(), Error>;
+
+    /// List all keys directly under `prefix`, e.g. `v2/1234` for the prefix `v2`.
+    async fn keys(&self, prefix: &str) -> Result<Vec<String>, Error>;
+
+    /// Count the values directly under `prefix` and their total size in bytes.
+    async fn usage(&self, prefix: &str) -> Result<(u64, u64), Error>;
 }
 
-/// Version prefix included in filenames to allow backwards-incompatible changes to the on-disk cache layout.
-const VERSION: &str = "v2";
+/// Store used by [`Cache::new`], if one has been configured with [`set_default_store`].
+static DEFAULT_STORE: OnceCell<Arc<dyn Store>> = OnceCell::new();
 
+/// Version prefix included in keys to allow backwards-incompatible changes to the stored cache layout.
+const VERSION: &str = "v6";
+
+/// Previous versions whose entries are migrated to the current layout when read, newest first.
+const LEGACY_VERSIONS: &[&str] = &["v2"];
+
+/// Previous versions whose entries can't be migrated, because they hold projections of outdated models. They're never
+/// read, and [`Cache::migrate`] removes them.
+const OBSOLETE_VERSIONS: &[&str] = &["v5", "v4", "v3"];
+
+/// Fingerprint of the models in [`api_models`](super::api_models), taken from their source.
+///
+/// Entries hold a model's projection of a response rather than the raw body, so an entry written by a build with
+/// different models may be missing fields, which `#[serde(default)]` would fill in without complaint. Entries are
+/// stamped with the fingerprint they were written with, and any other fingerprint is treated as a cache miss.
+static SCHEMA: Lazy<u64> = Lazy::new(|| {
+    let hash = Sha3_384::digest(include_bytes!("api_models.rs"));
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&hash[..8]);
+    u64::from_le_bytes(bytes)
+});
+
+/// Identifies the cache entry for a URL, independent of cache layout version.
 #[derive(Debug, PartialEq, Eq)]
-pub struct CacheKey(PathBuf);
+pub struct CacheKey(String);
 
 #[derive(Deserialize, Serialize)]
 pub struct Entry {
@@ -30,17 +87,42 @@ pub struct Entry {
     pub cache_policy: CachePolicy,
     /// The cached response body
     pub body: Bytes,
+    /// [`SCHEMA`] of the build that projected `body`.
+    schema: u64,
+}
+
+/// Make every [`Cache::new`] use `store`, e.g. to share one cache between several bot processes. This can only be
+/// set once, and returns `false` if a default store was already set.
+pub fn set_default_store(store: Arc<dyn Store>) -> bool {
+    DEFAULT_STORE.set(store).is_ok()
 }
 
 impl Cache {
-    /// Create a new cache that stores files in the default directory.
+    /// Create a new cache using the default store. Unless [`set_default_store`] was called, this stores files in the
+    /// default directory.
     pub fn new() -> Cache {
-        Cache::with_dir(".pokecache")
+        match DEFAULT_STORE.get() {
+            Some(store) => Cache::with_store(store.clone()),
+            None => Cache::with_dir(".pokecache"),
+        }
     }
 
     /// Create a new cache that stores files in the given directory.
     pub fn with_dir<P: Into<PathBuf>>(dir: P) -> Cache {
-        Cache { dir: dir.into() }
+        Cache::with_store(Arc::new(FsStore::new(dir)))
+    }
+
+    /// Create a new cache backed by `store`.
+    pub fn with_store(store: Arc<dyn Store>) -> Cache {
+        Cache {
+            store,
+            compression: Compression::Zstd,
+        }
+    }
+
+    /// Use `compression` for new entries written through this cache.
+    pub fn with_compression(self, compression: Compression) -> Cache {
+        Cache { compression, ..self }
     }
 
     /// Builds a cache key from a URL. This key can be used to retrieve and update the cache's stored result for that URL.
@@ -50,46 +132,177 @@ impl Cache {
         for byte in hash {
             let _ = write!(&mut name, "{:02x}", byte);
         }
-        CacheKey(self.dir.join(VERSION).join(name))
+        CacheKey(name)
     }
 
     /// Retrieve a raw cache entry
     pub async fn get(&mut self, key: &CacheKey) -> Result<Option<Entry>, Error> {
         log::debug!("Fetching {} from cache", key);
-        let data = match fs::read(&key.0).await {
-            Ok(data) => data,
-            Err(err) if err.kind() == ErrorKind::NotFound => {
-                log::trace!("Cache miss for {}", key);
-                return Ok(None)
-            },
+        let data = match self.store.read(&key.path(VERSION)).await {
+            Ok(Some(data)) => data,
+            Ok(None) => return self.migrate_entry(key).await,
             Err(err) => {
                 log::warn!("Cache read error for {}: {}", key, err);
-                return Err(err.into());
-            },
+                return Err(err);
+            }
         };
 
-        let entry: Entry = bincode::deserialize(&data)?;
+        let entry = format::decode(&data)?;
+        if entry.schema != *SCHEMA {
+            log::debug!("Cache entry {} was projected for other models, ignoring it", key);
+            return Ok(None);
+        }
         Ok(Some(entry))
     }
 
+    /// Look for an entry stored in an older layout, and move it to the current one if there is one.
+    async fn migrate_entry(&mut self, key: &CacheKey) -> Result<Option<Entry>, Error> {
+        for version in LEGACY_VERSIONS {
+            let path = key.path(version);
+            let data = match self.store.read(&path).await? {
+                Some(data) => data,
+                None => continue,
+            };
+
+            log::debug!("Migrating cache entry {} from {}", key, version);
+            let entry = format::decode_legacy(version, &data)?;
+            // Keep the old entry if the new one can't be written, so it isn't lost
+            match self.put(key, &entry).await {
+                Ok(()) => self.store.remove(&path).await?,
+                Err(err) => log::warn!("Cache migration for {} failed: {}", key, err),
+            }
+            return Ok(Some(entry));
+        }
+
+        log::trace!("Cache miss for {}", key);
+        Ok(None)
+    }
+
+    /// Migrate every entry stored in an older layout to the current one, returning how many were migrated. Entries
+    /// are also migrated individually as they're read, so this is never required. Obsolete entries are removed.
+    pub async fn migrate(&mut self) -> Result<usize, Error> {
+        for version in OBSOLETE_VERSIONS {
+            for path in self.store.keys(version).await? {
+                self.store.remove(&path).await?;
+            }
+        }
+
+        let mut migrated = 0;
+        for version in LEGACY_VERSIONS {
+            for path in self.store.keys(version).await? {
+                let key = CacheKey::from_path(&path);
+                // Skip anything that's already been migrated
+                if self.store.read(&key.path(VERSION)).await?.is_none() && self.migrate_entry(&key).await?.is_some() {
+                    migrated += 1;
+                }
+            }
+        }
+        Ok(migrated)
+    }
+
     /// Update a raw cache entry
     pub async fn put(&mut self, key: &CacheKey, value: &Entry) -> Result<(), Error> {
         log::debug!("Writing {}-byte cache entry for {}", value.body.len(), key);
-        let data = bincode::serialize(value)?;
-        fs::create_dir_all(&self.dir).await?;
-        fs::write(&key.0, data).await?;
-        Ok(())
+        let data = format::encode(value, self.compression)?;
+        self.store.write(&key.path(VERSION), data).await
+    }
+
+    /// Remove a cache entry
+    pub async fn remove(&mut self, key: &CacheKey) -> Result<(), Error> {
+        log::debug!("Removing cache entry {}", key);
+        self.store.remove(&key.path(VERSION)).await
+    }
+
+    /// Count the entries in the cache and their total size in bytes
+    pub async fn disk_usage(&self) -> Result<(u64, u64), Error> {
+        self.store.usage(VERSION).await
+    }
+
+    /// List the keys of all entries currently in the cache
+    pub async fn keys(&self) -> Result<Vec<CacheKey>, Error> {
+        let paths = self.store.keys(VERSION).await?;
+        Ok(paths.iter().map(|path| CacheKey::from_path(path)).collect())
+    }
+
+    /// Read auxiliary data stored alongside the cache entries under `name`, such as warm-up progress.
+    pub async fn get_meta(&self, name: &str) -> Result<Option<Vec<u8>>, Error> {
+        self.store.read(name).await
+    }
+
+    /// Create or replace auxiliary data stored alongside the cache entries.
+    pub async fn put_meta(&self, name: &str, data: Vec<u8>) -> Result<(), Error> {
+        self.store.write(name, data).await
+    }
+
+    /// Remove auxiliary data stored alongside the cache entries.
+    pub async fn remove_meta(&self, name: &str) -> Result<(), Error> {
+        self.store.remove(name).await
+    }
+}
+
+impl Default for Cache {
+    fn default() -> Cache {
+        Cache::new()
+    }
+}
+
+impl CacheKey {
+    /// The store key for this entry in layout `version`.
+    fn path(&self, version: &str) -> String {
+        format!("{}/{}", version, self.0)
+    }
+
+    /// Parses a key back out of a store key.
+    fn from_path(path: &str) -> CacheKey {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        CacheKey(name.to_string())
     }
 }
 
 impl fmt::Display for CacheKey {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        self.0.display().fmt(f)
+        self.path(VERSION).fmt(f)
     }
 }
 
 impl Entry {
+    /// An entry for `body` as projected by the current models.
     pub fn new(body: Bytes, cache_policy: CachePolicy) -> Entry {
-        Entry { cache_policy, body }
+        Entry {
+            cache_policy,
+            body,
+            schema: *SCHEMA,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::{Method, Request};
+
+    use super::*;
+
+    fn entry(body: &'static str) -> Entry {
+        let url = Url::parse("https://pokeapi.co/api/v2/region/1/").unwrap();
+        let req = Request::new(Method::GET, url);
+        let res = reqwest::Response::from(hyper::Response::new(body));
+        Entry::new(Bytes::from(body), CachePolicy::new(&req, &res))
+    }
+
+    #[tokio::test]
+    async fn entries_are_only_read_by_the_models_that_projected_them() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cache = Cache::with_dir(dir.path());
+        let key = cache.cache_key(&Url::parse("https://pokeapi.co/api/v2/region/1/").unwrap());
+
+        cache.put(&key, &entry(r#"{"name": "kanto"}"#)).await.unwrap();
+        assert_eq!(cache.get(&key).await.unwrap().unwrap().body, r#"{"name": "kanto"}"#);
+
+        let outdated = Entry {
+            schema: !*SCHEMA,
+            ..entry(r#"{"name": "johto"}"#)
+        };
+        cache.put(&key, &outdated).await.unwrap();
+        assert!(cache.get(&key).await.unwrap().is_none());
     }
 }
@@ -0,0 +1,104 @@
+//! The stored representation of cache entries.
+//!
+//! Since `v3`, every stored entry starts with a one-byte marker saying how the rest of it is compressed, followed by
+//! the (possibly compressed) bincode-encoded [`Entry`]. Older layouts are still readable so that they can be migrated.
+
+use std::io::{Read, Write};
+
+use bytes::Bytes;
+use flate2::{read::GzDecoder, write::GzEncoder};
+use http_cache_semantics::CachePolicy;
+use serde::Deserialize;
+
+use super::Entry;
+use crate::pokedex::Error;
+
+/// zstd compression level. Cache writes are infrequent, but this is about the point where higher levels stop making
+/// PokeAPI's JSON noticeably smaller.
+const ZSTD_LEVEL: i32 = 9;
+
+/// How cache entries are compressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    fn marker(self) -> u8 {
+        match self {
+            Compression::None => 0,
+            Compression::Gzip => 1,
+            Compression::Zstd => 2,
+        }
+    }
+
+    fn from_marker(marker: u8) -> Option<Compression> {
+        match marker {
+            0 => Some(Compression::None),
+            1 => Some(Compression::Gzip),
+            2 => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+}
+
+/// Encodes an entry in the current layout.
+pub fn encode(entry: &Entry, compression: Compression) -> Result<Vec<u8>, Error> {
+    let data = bincode::serialize(entry)?;
+    let mut out = vec![compression.marker()];
+    match compression {
+        Compression::None => out.extend_from_slice(&data),
+        Compression::Gzip => {
+            let mut encoder = GzEncoder::new(out, flate2::Compression::default());
+            encoder.write_all(&data).map_err(Error::Compression)?;
+            out = encoder.finish().map_err(Error::Compression)?;
+        }
+        Compression::Zstd => {
+            out.extend(zstd::encode_all(&data[..], ZSTD_LEVEL).map_err(Error::Compression)?);
+        }
+    }
+    Ok(out)
+}
+
+/// Decodes an entry stored in the current layout.
+pub fn decode(data: &[u8]) -> Result<Entry, Error> {
+    let (&marker, payload) = data.split_first().ok_or(Error::UnknownCacheFormat)?;
+    let decompressed;
+    let raw: &[u8] = match Compression::from_marker(marker).ok_or(Error::UnknownCacheFormat)? {
+        Compression::None => payload,
+        Compression::Gzip => {
+            let mut out = Vec::new();
+            GzDecoder::new(payload)
+                .read_to_end(&mut out)
+                .map_err(Error::Compression)?;
+            decompressed = out;
+            &decompressed
+        }
+        Compression::Zstd => {
+            decompressed = zstd::decode_all(payload).map_err(Error::Compression)?;
+            &decompressed
+        }
+    };
+    Ok(bincode::deserialize(raw)?)
+}
+
+/// An entry as stored in `v2`, which held raw response bodies.
+#[derive(Deserialize)]
+struct RawEntry {
+    cache_policy: CachePolicy,
+    body: Bytes,
+}
+
+/// Decodes an entry stored in an older layout.
+pub fn decode_legacy(version: &str, data: &[u8]) -> Result<Entry, Error> {
+    match version {
+        // Uncompressed bincode. Raw bodies hold every field, so they're valid projections for any model
+        "v2" => {
+            let raw: RawEntry = bincode::deserialize(data)?;
+            Ok(Entry::new(raw.body, raw.cache_policy))
+        }
+        _ => Err(Error::UnknownCacheFormat),
+    }
+}
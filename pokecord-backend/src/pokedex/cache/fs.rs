@@ -0,0 +1,77 @@
+use std::{io::ErrorKind, path::PathBuf};
+
+use async_trait::async_trait;
+use tokio::fs;
+
+use super::Store;
+use crate::pokedex::Error;
+
+/// Cache store that keeps each value in its own file, with key prefixes as subdirectories.
+#[derive(Debug)]
+pub struct FsStore {
+    dir: PathBuf,
+}
+
+impl FsStore {
+    /// Create a store that keeps files under `dir`.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> FsStore {
+        FsStore { dir: dir.into() }
+    }
+}
+
+#[async_trait]
+impl Store for FsStore {
+    async fn read(&self, key: &str) -> Result<Option<Vec<u8>>, Error> {
+        match fs::read(self.dir.join(key)).await {
+            Ok(data) => Ok(Some(data)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn write(&self, key: &str, data: Vec<u8>) -> Result<(), Error> {
+        let path = self.dir.join(key);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).await?;
+        }
+        fs::write(&path, data).await?;
+        Ok(())
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), Error> {
+        match fs::remove_file(self.dir.join(key)).await {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    async fn keys(&self, prefix: &str) -> Result<Vec<String>, Error> {
+        let mut keys = Vec::new();
+        let mut dir = match fs::read_dir(self.dir.join(prefix)).await {
+            Ok(dir) => dir,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(keys),
+            Err(err) => return Err(err.into()),
+        };
+        while let Some(file) = dir.next_entry().await? {
+            keys.push(format!("{}/{}", prefix, file.file_name().to_string_lossy()));
+        }
+        Ok(keys)
+    }
+
+    async fn usage(&self, prefix: &str) -> Result<(u64, u64), Error> {
+        let mut entries = 0;
+        let mut bytes = 0;
+        for key in self.keys(prefix).await? {
+            match fs::metadata(self.dir.join(key)).await {
+                Ok(metadata) => {
+                    entries += 1;
+                    bytes += metadata.len();
+                }
+                // The entry may have been removed since listing
+                Err(err) if err.kind() == ErrorKind::NotFound => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok((entries, bytes))
+    }
+}
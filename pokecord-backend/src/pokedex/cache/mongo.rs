@@ -0,0 +1,94 @@
+use async_trait::async_trait;
+use futures::stream::TryStreamExt;
+use mongodb::{
+    bson::{doc, spec::BinarySubtype, Binary, Bson, Document},
+    options::{FindOptions, ReplaceOptions},
+    Collection,
+};
+
+use super::Store;
+use crate::pokedex::Error;
+
+/// Cache store that keeps values in a MongoDB collection, so that several bot processes can share one cache. Each
+/// value is a document with the key as its `_id` and the bytes in `data`.
+#[derive(Debug)]
+pub struct MongoStore {
+    collection: Collection<Document>,
+}
+
+impl MongoStore {
+    /// Create a store that keeps values in `collection`.
+    pub fn new(collection: Collection<Document>) -> MongoStore {
+        MongoStore { collection }
+    }
+}
+
+/// Filter matching every key directly under `prefix`.
+fn prefix_filter(prefix: &str) -> mongodb::bson::Document {
+    // Cache key prefixes are plain alphanumeric version tags, so they don't need regex escaping
+    doc! { "_id": { "$regex": format!("^{}/[^/]*$", prefix) } }
+}
+
+/// Reads a count out of an aggregation result, which may be either 32 or 64 bits depending on its size.
+fn as_count(value: Option<&Bson>) -> u64 {
+    match value {
+        Some(Bson::Int32(n)) => *n as u64,
+        Some(Bson::Int64(n)) => *n as u64,
+        _ => 0,
+    }
+}
+
+#[async_trait]
+impl Store for MongoStore {
+    async fn read(&self, key: &str) -> Result<Option<Vec<u8>>, Error> {
+        match self.collection.find_one(doc! { "_id": key }, None).await? {
+            Some(document) => Ok(Some(document.get_binary_generic("data")?.clone())),
+            None => Ok(None),
+        }
+    }
+
+    async fn write(&self, key: &str, data: Vec<u8>) -> Result<(), Error> {
+        let document = doc! {
+            "_id": key,
+            "data": Binary { subtype: BinarySubtype::Generic, bytes: data },
+        };
+        let options = ReplaceOptions::builder().upsert(true).build();
+        self.collection
+            .replace_one(doc! { "_id": key }, document, options)
+            .await?;
+        Ok(())
+    }
+
+    async fn remove(&self, key: &str) -> Result<(), Error> {
+        self.collection.delete_one(doc! { "_id": key }, None).await?;
+        Ok(())
+    }
+
+    async fn keys(&self, prefix: &str) -> Result<Vec<String>, Error> {
+        let options = FindOptions::builder().projection(doc! { "_id": 1 }).build();
+        let mut cursor = self.collection.find(prefix_filter(prefix), options).await?;
+        let mut keys = Vec::new();
+        while let Some(document) = cursor.try_next().await? {
+            keys.push(document.get_str("_id")?.to_string());
+        }
+        Ok(keys)
+    }
+
+    async fn usage(&self, prefix: &str) -> Result<(u64, u64), Error> {
+        let pipeline = vec![
+            doc! { "$match": prefix_filter(prefix) },
+            doc! {
+                "$group": {
+                    "_id": Bson::Null,
+                    "entries": { "$sum": 1 },
+                    "bytes": { "$sum": { "$binarySize": "$data" } },
+                }
+            },
+        ];
+        let mut cursor = self.collection.aggregate(pipeline, None).await?;
+        match cursor.try_next().await? {
+            Some(totals) => Ok((as_count(totals.get("entries")), as_count(totals.get("bytes")))),
+            None => Ok((0, 0)),
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! Offline access to a local copy of [PokeAPI's static data](https://github.com/PokeAPI/api-data).
+
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tokio::fs;
+use url::Url;
+
+use super::{Error, API_BASE};
+
+/// Path of API responses within an `api-data` checkout.
+const DATA_DIR: &str = "data/api/v2";
+
+/// Resource URLs in the dump are relative to the API host, e.g. `/api/v2/pokemon/1/`.
+const DUMP_URL_PREFIX: &str = "/api/v2/";
+
+/// A PokeAPI `api-data` tree on disk. Every endpoint is stored as `<endpoint path>/index.json`, so any resource URL
+/// maps directly to a file. Resource lists hold every resource on a single page, so pagination parameters are ignored.
+#[derive(Debug)]
+pub struct Dump {
+    dir: PathBuf,
+}
+
+impl Dump {
+    /// Opens the `api-data` checkout at `root`.
+    pub fn open<P: Into<PathBuf>>(root: P) -> Result<Dump, Error> {
+        let dir = root.into().join(DATA_DIR);
+        if !dir.is_dir() {
+            return Err(Error::InvalidDump(dir));
+        }
+        log::info!("Serving PokeAPI data from {}", dir.display());
+        Ok(Dump { dir })
+    }
+
+    /// Reads the resource at `url` from the dump. Resources are only stored by ID, so a URL naming a resource is
+    /// resolved through its resource list first.
+    pub async fn get<T: DeserializeOwned>(&self, url: &Url) -> Result<T, Error> {
+        let url = self.resolve_name(url).await?;
+        let path = self.path(&url).ok_or_else(|| Error::NotInDump(url.clone()))?;
+        let value = self.read(&path).await?.ok_or(Error::NotInDump(url))?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Maps a by-name resource URL like `pokemon/bulbasaur/` to its by-ID URL, using the resource list. Any other
+    /// URL is returned unchanged.
+    async fn resolve_name(&self, url: &Url) -> Result<Url, Error> {
+        let endpoint = match url.as_str().strip_prefix(API_BASE) {
+            Some(endpoint) => endpoint.split('?').next().unwrap_or(endpoint),
+            None => return Ok(url.clone()),
+        };
+        let segments: Vec<_> = endpoint.split('/').filter(|segment| !segment.is_empty()).collect();
+        let (resource, name) = match segments.as_slice() {
+            [resource, name] if name.parse::<usize>().is_err() => (*resource, *name),
+            _ => return Ok(url.clone()),
+        };
+
+        let list_path = self.dir.join(resource).join("index.json");
+        let list = self.read(&list_path).await?.ok_or_else(|| Error::NotInDump(url.clone()))?;
+        let resolved = list["results"]
+            .as_array()
+            .and_then(|results| results.iter().find(|result| result["name"] == name))
+            .and_then(|result| result["url"].as_str())
+            .and_then(|resolved| Url::parse(resolved).ok());
+        resolved.ok_or_else(|| Error::NotInDump(url.clone()))
+    }
+
+    /// Reads and parses the file at `path`, with resource URLs made absolute, or `None` if there is no such file.
+    async fn read(&self, path: &Path) -> Result<Option<Value>, Error> {
+        log::debug!("Reading {}", path.display());
+        let data = match fs::read(path).await {
+            Ok(data) => data,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut value: Value = serde_json::from_slice(&data)?;
+        absolutize_urls(&mut value);
+        Ok(Some(value))
+    }
+
+    /// Maps an API URL to its file in the dump, if it is an API URL at all.
+    fn path(&self, url: &Url) -> Option<PathBuf> {
+        let endpoint = url.as_str().strip_prefix(API_BASE)?;
+        // Drop pagination parameters
+        let endpoint = endpoint.split('?').next()?;
+        let mut path = self.dir.clone();
+        path.extend(endpoint.split('/').filter(|segment| !segment.is_empty()));
+        Some(path.join("index.json"))
+    }
+}
+
+/// Rewrites every host-relative resource URL in `value` into a full API URL, matching what the live API returns.
+fn absolutize_urls(value: &mut Value) {
+    match value {
+        Value::String(s) => {
+            if let Some(endpoint) = s.strip_prefix(DUMP_URL_PREFIX) {
+                *s = format!("{}{}", API_BASE, endpoint);
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(absolutize_urls),
+        Value::Object(map) => map.values_mut().for_each(absolutize_urls),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::pokedex::{Pokedex, Region};
+
+    /// Writes a minimal `api-data` tree with two regions, stored by ID only like the real dump.
+    fn write_dump(root: &Path) {
+        let write = |endpoint: &str, json: &str| {
+            let dir = root.join(DATA_DIR).join(endpoint);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("index.json"), json).unwrap();
+        };
+        write(
+            "region",
+            r#"{"count": 2, "next": null, "previous": null, "results": [
+                {"name": "kanto", "url": "/api/v2/region/1/"},
+                {"name": "johto", "url": "/api/v2/region/2/"}
+            ]}"#,
+        );
+        write("region/1", r#"{"id": 1, "name": "kanto", "names": [], "locations": []}"#);
+        write("region/2", r#"{"id": 2, "name": "johto", "names": [], "locations": []}"#);
+    }
+
+    #[tokio::test]
+    async fn serves_resources_from_dump() {
+        let root = tempfile::tempdir().unwrap();
+        write_dump(root.path());
+        let mut pokedex = Pokedex::offline(root.path()).unwrap();
+
+        let kanto: Region = pokedex.get_by_id(1).await.unwrap();
+        assert_eq!(kanto.name, "kanto");
+        let johto: Region = pokedex.get_by_name("johto").await.unwrap();
+        assert_eq!(johto.id, 2);
+
+        let regions = pokedex.list::<Region>().await.unwrap();
+        let names: Vec<_> = regions.iter().map(|region| region.name.as_str()).collect();
+        assert_eq!(names, ["kanto", "johto"]);
+        // Host-relative URLs in the dump resolve like live ones
+        assert_eq!(regions[1].url.as_str(), "https://pokeapi.co/api/v2/region/2/");
+        assert_eq!(pokedex.get_by_ref(&regions[1]).await.unwrap(), johto);
+    }
+
+    #[tokio::test]
+    async fn missing_resources_are_errors() {
+        let root = tempfile::tempdir().unwrap();
+        write_dump(root.path());
+        let mut pokedex = Pokedex::offline(root.path()).unwrap();
+
+        assert!(matches!(pokedex.get_by_id::<Region>(3).await, Err(Error::NotInDump(_))));
+        assert!(matches!(pokedex.get_by_name::<Region>("hoenn").await, Err(Error::NotInDump(_))));
+    }
+
+    #[test]
+    fn rejects_non_dump_directories() {
+        let root = tempfile::tempdir().unwrap();
+        assert!(matches!(Dump::open(root.path()), Err(Error::InvalidDump(_))));
+    }
+}
@@ -12,6 +12,24 @@ pub enum Error {
     Json(#[from] serde_json::Error),
     #[error("Cache serialization failed")]
     CacheSerialization(#[from] bincode::Error),
+    #[error("Cache compression failed")]
+    Compression(#[source] std::io::Error),
+    #[error("Unknown cache entry format")]
+    UnknownCacheFormat,
+    #[error("MongoDB cache access failed")]
+    Mongo(#[from] mongodb::error::Error),
+    #[error("Malformed MongoDB cache document")]
+    MalformedCacheDocument(#[from] mongodb::bson::document::ValueAccessError),
+    #[error("Fixture replay server failed")]
+    Replay(#[from] hyper::Error),
+    #[error("{0} is not a PokeAPI data dump")]
+    InvalidDump(std::path::PathBuf),
+    #[error("{0} is not in the PokeAPI data dump")]
+    NotInDump(url::Url),
+    #[error("{0} is not in the in-memory Pokedex")]
+    NotInMemory(url::Url),
+    #[error("Incomplete PokeAPI data: {0}")]
+    Incomplete(String),
 }
 
 impl PyErrArguments for Error {
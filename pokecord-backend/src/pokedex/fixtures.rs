@@ -0,0 +1,212 @@
+//! Recording and replaying PokeAPI responses, so cache behaviour can be exercised deterministically and offline.
+//!
+//! A [`Recorder`] saves every response a [`Pokedex`](super::Pokedex) receives, headers included, as a fixture file.
+//! A [`ReplayServer`] serves a directory of fixtures over local HTTP, answering conditional requests with 304s the way
+//! PokeAPI does, so a `Pokedex` pointed at it goes through the same caching and revalidation logic as in production.
+
+use std::{collections::HashMap, convert::Infallible, net::SocketAddr, path::PathBuf, sync::Arc};
+
+use bytes::Bytes;
+use hyper::{
+    header::{HeaderName, HeaderValue, CONTENT_LENGTH, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED},
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use tokio::{fs, sync::oneshot};
+use url::Url;
+
+use super::Error;
+
+/// Headers that describe the original connection rather than the response, so they aren't replayed.
+const SKIPPED_HEADERS: &[&str] = &["connection", "content-length", "transfer-encoding", "keep-alive"];
+
+/// A recorded response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Fixture {
+    /// Path and query the response was for, e.g. `/api/v2/pokemon/1/`.
+    pub path: String,
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+/// Saves responses into a fixture directory.
+#[derive(Debug)]
+pub struct Recorder {
+    dir: PathBuf,
+}
+
+impl Recorder {
+    /// Create a recorder that writes fixtures into `dir`.
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Recorder {
+        Recorder { dir: dir.into() }
+    }
+
+    /// Save `res` as a fixture and return an equivalent response, since reading the body consumes the original.
+    /// Only successful responses are saved, so a 304 never replaces the full response it refers to.
+    pub async fn record(&self, res: reqwest::Response) -> Result<reqwest::Response, Error> {
+        let status = res.status();
+        let headers = res.headers().clone();
+        let path = fixture_path(res.url());
+        let body = res.bytes().await?;
+
+        if status.is_success() {
+            let fixture = Fixture {
+                path: path.clone(),
+                status: status.as_u16(),
+                headers: headers
+                    .iter()
+                    .filter(|(name, _)| !SKIPPED_HEADERS.contains(&name.as_str()))
+                    .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+                    .collect(),
+                body: String::from_utf8_lossy(&body).into_owned(),
+            };
+            fs::create_dir_all(&self.dir).await?;
+            let file = self.dir.join(fixture_file_name(&path));
+            log::debug!("Recording {} to {}", path, file.display());
+            fs::write(file, serde_json::to_vec_pretty(&fixture)?).await?;
+        }
+
+        let mut rebuilt = Response::builder().status(status);
+        for (name, value) in headers.iter() {
+            rebuilt = rebuilt.header(name, value);
+        }
+        let rebuilt = rebuilt.body(body).expect("Recorded response was invalid");
+        Ok(reqwest::Response::from(rebuilt))
+    }
+}
+
+/// A local HTTP server replaying a fixture directory. It shuts down when dropped.
+#[derive(Debug)]
+pub struct ReplayServer {
+    addr: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl ReplayServer {
+    /// Load every fixture in `dir` and start serving them on a free local port.
+    pub async fn start<P: Into<PathBuf>>(dir: P) -> Result<ReplayServer, Error> {
+        let fixtures = Arc::new(load_fixtures(dir.into()).await?);
+        let make_service = make_service_fn(move |_| {
+            let fixtures = fixtures.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let res = respond(&fixtures, req);
+                    async move { Ok::<_, Infallible>(res) }
+                }))
+            }
+        });
+
+        let server = Server::try_bind(&SocketAddr::from(([127, 0, 0, 1], 0)))?.serve(make_service);
+        let addr = server.local_addr();
+        let (shutdown, shutdown_rx) = oneshot::channel();
+        let server = server.with_graceful_shutdown(async move {
+            let _ = shutdown_rx.await;
+        });
+        tokio::spawn(async move {
+            if let Err(err) = server.await {
+                log::error!("Replay server failed: {}", err);
+            }
+        });
+
+        log::info!("Replaying fixtures on {}", addr);
+        Ok(ReplayServer {
+            addr,
+            shutdown: Some(shutdown),
+        })
+    }
+
+    /// The API base URL to point a [`Pokedex`](super::Pokedex) at.
+    pub fn api_base(&self) -> Url {
+        Url::parse(&format!("http://{}/api/v2/", self.addr)).expect("Replay server address was invalid")
+    }
+}
+
+impl Drop for ReplayServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
+
+/// The path and query of `url`, which identifies its fixture.
+fn fixture_path(url: &Url) -> String {
+    match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    }
+}
+
+/// A readable, filesystem-safe file name for the fixture of `path`.
+fn fixture_file_name(path: &str) -> String {
+    let name: String = path
+        .trim_start_matches("/api/v2/")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    format!("{}.json", name.trim_matches('_'))
+}
+
+async fn load_fixtures(dir: PathBuf) -> Result<HashMap<String, Fixture>, Error> {
+    let mut fixtures = HashMap::new();
+    let mut files = fs::read_dir(&dir).await?;
+    while let Some(file) = files.next_entry().await? {
+        let fixture: Fixture = serde_json::from_slice(&fs::read(file.path()).await?)?;
+        fixtures.insert(fixture.path.clone(), fixture);
+    }
+    log::debug!("Loaded {} fixtures from {}", fixtures.len(), dir.display());
+    Ok(fixtures)
+}
+
+/// Answers `req` from `fixtures`, honouring `If-None-Match` and `If-Modified-Since`.
+fn respond(fixtures: &HashMap<String, Fixture>, req: Request<Body>) -> Response<Body> {
+    let path = req
+        .uri()
+        .path_and_query()
+        .map(|p| p.as_str())
+        .unwrap_or_else(|| req.uri().path());
+    let fixture = match fixtures.get(path) {
+        Some(fixture) => fixture,
+        None => {
+            log::warn!("No fixture for {}", path);
+            let mut res = Response::new(Body::empty());
+            *res.status_mut() = StatusCode::NOT_FOUND;
+            return res;
+        }
+    };
+
+    let header = |name: &HeaderName| {
+        fixture
+            .headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name.as_str()))
+            .map(|(_, v)| v.as_str())
+    };
+    let matches = |req_header: &HeaderName, fixture_header: &HeaderName| {
+        match (req.headers().get(req_header), header(fixture_header)) {
+            (Some(requested), Some(current)) => requested.as_bytes() == current.as_bytes(),
+            _ => false,
+        }
+    };
+    let not_modified = matches(&IF_NONE_MATCH, &ETAG) || matches(&IF_MODIFIED_SINCE, &LAST_MODIFIED);
+
+    let mut res = if not_modified {
+        let mut res = Response::new(Body::empty());
+        *res.status_mut() = StatusCode::NOT_MODIFIED;
+        res
+    } else {
+        let mut res = Response::new(Body::from(Bytes::from(fixture.body.clone())));
+        *res.status_mut() = StatusCode::from_u16(fixture.status).unwrap_or(StatusCode::OK);
+        res
+    };
+    for (name, value) in &fixture.headers {
+        if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(value)) {
+            if name != CONTENT_LENGTH {
+                res.headers_mut().append(name, value);
+            }
+        }
+    }
+    res
+}
@@ -0,0 +1,173 @@
+//! Helpers for picking localized strings out of PokeAPI resources.
+//!
+//! PokeAPI returns every localized string it has for a resource, tagged with its language. Callers pass a chain of
+//! preferred language names (e.g. `["fr", "de"]`, using PokeAPI's [`Language`] names), which is tried in order before
+//! falling back to [`FALLBACK_LANGUAGE`].
+
+use super::{
+    Description, EvolutionTrigger, FlavorText, Generation, Item, Language, Location, Move, MoveDamageClass, Name,
+    NamedResource, Nature, PokemonForm, PokemonSpecies, Region, Stat, Type,
+};
+
+/// Language used when none of the preferred languages has an entry.
+pub const FALLBACK_LANGUAGE: &str = "en";
+
+/// A localized string from the API.
+pub trait Localized {
+    /// The language this string is in.
+    fn language(&self) -> &NamedResource<Language>;
+
+    /// The localized string itself.
+    fn text(&self) -> &str;
+}
+
+/// An API resource with localized names.
+pub trait Named {
+    /// The name of this resource listed in different languages.
+    fn names(&self) -> &[Name];
+
+    /// The name of this resource in the first available language of `languages`, falling back to English.
+    fn localized_name(&self, languages: &[String]) -> Option<&str> {
+        localize(self.names(), languages).map(Localized::text)
+    }
+}
+
+/// Picks the entry in the first available language of `languages`, falling back to English.
+pub fn localize<'a, T: Localized>(entries: &'a [T], languages: &[String]) -> Option<&'a T> {
+    languages
+        .iter()
+        .map(String::as_str)
+        .chain(std::iter::once(FALLBACK_LANGUAGE))
+        .find_map(|lang| entries.iter().find(|e| e.language().name == lang))
+}
+
+/// Cleans up flavor text for display. PokeAPI flavor text is copied verbatim from the games, so it contains the
+/// games' hard line breaks, page breaks (form feeds) and soft hyphens.
+pub fn normalize_flavor_text(text: &str) -> String {
+    text.replace("\u{ad}\n", "")
+        .replace("-\n", "-")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl PokemonSpecies {
+    /// The flavor text for this species in the first available language of `languages`, with game formatting
+    /// artifacts removed.
+    pub fn localized_flavor_text(&self, languages: &[String]) -> Option<String> {
+        localize(&self.flavor_text_entries, languages).map(|f| normalize_flavor_text(&f.flavor_text))
+    }
+
+    /// The form description for this species in the first available language of `languages`.
+    pub fn localized_form_description(&self, languages: &[String]) -> Option<&str> {
+        localize(&self.form_descriptions, languages).map(Localized::text)
+    }
+}
+
+impl Localized for Name {
+    fn language(&self) -> &NamedResource<Language> {
+        &self.language
+    }
+
+    fn text(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Localized for FlavorText {
+    fn language(&self) -> &NamedResource<Language> {
+        &self.language
+    }
+
+    fn text(&self) -> &str {
+        &self.flavor_text
+    }
+}
+
+impl Localized for Description {
+    fn language(&self) -> &NamedResource<Language> {
+        &self.language
+    }
+
+    fn text(&self) -> &str {
+        &self.description
+    }
+}
+
+impl Named for Language {
+    fn names(&self) -> &[Name] {
+        &self.names
+    }
+}
+
+impl Named for PokemonSpecies {
+    fn names(&self) -> &[Name] {
+        &self.names
+    }
+}
+
+impl Named for PokemonForm {
+    fn names(&self) -> &[Name] {
+        &self.names
+    }
+}
+
+impl Named for Type {
+    fn names(&self) -> &[Name] {
+        &self.names
+    }
+}
+
+impl Named for Generation {
+    fn names(&self) -> &[Name] {
+        &self.names
+    }
+}
+
+impl Named for Region {
+    fn names(&self) -> &[Name] {
+        &self.names
+    }
+}
+
+impl Named for EvolutionTrigger {
+    fn names(&self) -> &[Name] {
+        &self.names
+    }
+}
+
+impl Named for Item {
+    fn names(&self) -> &[Name] {
+        &self.names
+    }
+}
+
+impl Named for Location {
+    fn names(&self) -> &[Name] {
+        &self.names
+    }
+}
+
+impl Named for Move {
+    fn names(&self) -> &[Name] {
+        &self.names
+    }
+}
+
+impl Named for MoveDamageClass {
+    fn names(&self) -> &[Name] {
+        &self.names
+    }
+}
+
+impl Named for Stat {
+    fn names(&self) -> &[Name] {
+        &self.names
+    }
+}
+
+impl Named for Nature {
+    fn names(&self) -> &[Name] {
+        &self.names
+    }
+}
@@ -0,0 +1,105 @@
+//! An in-memory [`PokedexSource`] for testing game logic without network or disk access.
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use tokio::fs;
+use url::Url;
+
+use super::{fixtures::Fixture, ApiResource, Error, NamedResource, PokedexSource, API_BASE};
+
+/// Serves a fixed set of resources, looked up by the same URLs as PokeAPI uses. Resources can be seeded from
+/// recorded fixtures or inserted directly.
+#[derive(Debug, Default)]
+pub struct MemoryPokedex {
+    /// Resources by URL without the trailing slash. Each resource is stored under both its ID and name.
+    resources: BTreeMap<String, Value>,
+}
+
+impl MemoryPokedex {
+    /// Create an empty Pokedex.
+    pub fn new() -> MemoryPokedex {
+        MemoryPokedex::default()
+    }
+
+    /// Create a Pokedex holding every resource recorded in a fixture directory. Recorded list pages are skipped,
+    /// since lists are built from the individual resources.
+    pub async fn from_fixtures<P: Into<PathBuf>>(dir: P) -> Result<MemoryPokedex, Error> {
+        let mut pokedex = MemoryPokedex::new();
+        let mut files = fs::read_dir(dir.into()).await?;
+        while let Some(file) = files.next_entry().await? {
+            let fixture: Fixture = serde_json::from_slice(&fs::read(file.path()).await?)?;
+            if fixture.path.contains('?') {
+                continue;
+            }
+            let url = Url::parse(API_BASE).unwrap().join(&fixture.path).expect("Invalid fixture path");
+            pokedex.insert_json(&url, serde_json::from_str(&fixture.body)?);
+        }
+        Ok(pokedex)
+    }
+
+    /// Add a resource.
+    pub fn insert<T: ApiResource>(&mut self, resource: &T) -> Result<(), Error> {
+        let url = T::base_url().join(&resource.id().to_string()).expect("Malformed resource ID");
+        self.insert_json(&url, serde_json::to_value(resource)?);
+        Ok(())
+    }
+
+    /// Add a resource's JSON, as it would be served from `url`.
+    fn insert_json(&mut self, url: &Url, value: Value) {
+        let key = url.as_str().trim_end_matches('/');
+        let parent = &key[..key.rfind('/').unwrap_or(0)];
+        for alias in &[value.get("id"), value.get("name")] {
+            match alias {
+                Some(Value::Number(id)) => {
+                    self.resources.insert(format!("{}/{}", parent, id), value.clone());
+                }
+                Some(Value::String(name)) => {
+                    self.resources.insert(format!("{}/{}", parent, name), value.clone());
+                }
+                _ => {}
+            }
+        }
+        self.resources.insert(key.to_string(), value);
+    }
+
+    fn get<T: ApiResource>(&self, url: &Url) -> Result<T, Error> {
+        let value = self
+            .resources
+            .get(url.as_str().trim_end_matches('/'))
+            .ok_or_else(|| Error::NotInMemory(url.clone()))?;
+        Ok(serde_json::from_value(value.clone())?)
+    }
+}
+
+#[async_trait]
+impl PokedexSource for MemoryPokedex {
+    async fn get_by_name<T: ApiResource>(&mut self, name: &str) -> Result<T, Error> {
+        self.get(&T::base_url().join(name).expect("Malformed resource name"))
+    }
+
+    async fn get_by_id<T: ApiResource>(&mut self, id: usize) -> Result<T, Error> {
+        self.get(&T::base_url().join(&id.to_string()).expect("Malformed resource name"))
+    }
+
+    async fn get_by_ref<T: ApiResource>(&mut self, reference: &NamedResource<T>) -> Result<T, Error> {
+        self.get(&reference.url)
+    }
+
+    async fn list<T: ApiResource>(&mut self) -> Result<Vec<NamedResource<T>>, Error> {
+        let base = T::base_url();
+        let mut resources: Vec<(usize, NamedResource<T>)> = Vec::new();
+        for (key, value) in &self.resources {
+            // Only take each resource once, under its ID
+            let id = match key.strip_prefix(base.as_str()).and_then(|id| id.parse().ok()) {
+                Some(id) => id,
+                None => continue,
+            };
+            let reference = json!({ "name": value["name"], "url": format!("{}/", key) });
+            resources.push((id, serde_json::from_value(reference)?));
+        }
+        resources.sort_by_key(|(id, _)| *id);
+        Ok(resources.into_iter().map(|(_, reference)| reference).collect())
+    }
+}
@@ -0,0 +1,212 @@
+//! Fuzzy lookup of pokemon species by any of their names.
+
+use std::collections::HashSet;
+
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+use super::{Error, PokedexSource, PokemonSpecies};
+
+/// In-memory index over every species' API name and all of its localized names.
+#[derive(Debug, Default)]
+pub struct NameIndex {
+    entries: Vec<IndexEntry>,
+}
+
+#[derive(Debug)]
+struct IndexEntry {
+    /// The name after [`normalize`], which is what queries are matched against.
+    key: String,
+    /// The name as written in the API.
+    name: String,
+    /// The API name of the species this name belongs to.
+    species: String,
+}
+
+/// A species that matched a search query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchMatch {
+    /// The name that matched, as written in the API. This may be in any language.
+    pub name: String,
+    /// The API name of the matched species, usable with [`PokedexSource::get_by_name`].
+    pub species: String,
+    /// Edit distance between the query and `name`, ignoring case and accents. 0 is an exact match.
+    pub distance: usize,
+}
+
+impl NameIndex {
+    /// Builds an index of all species names. This fetches every species, so it is expensive on a cold cache.
+    pub async fn build<P: PokedexSource>(pokedex: &mut P) -> Result<NameIndex, Error> {
+        let mut index = NameIndex::default();
+        for species_ref in pokedex.list::<PokemonSpecies>().await? {
+            let species = pokedex.get_by_ref(&species_ref).await?;
+            index.insert(&species.name, &species.name);
+            for name in &species.names {
+                index.insert(&name.name, &species.name);
+            }
+        }
+        log::info!("Built species name index with {} names", index.entries.len());
+        Ok(index)
+    }
+
+    /// Adds a name for `species` to the index.
+    pub fn insert(&mut self, name: &str, species: &str) {
+        let key = normalize(name);
+        // Names are inserted a species at a time, so duplicates can only be among the most recent entries
+        let duplicate = self
+            .entries
+            .iter()
+            .rev()
+            .take_while(|e| e.species == species)
+            .any(|e| e.key == key);
+        if key.is_empty() || duplicate {
+            return;
+        }
+        self.entries.push(IndexEntry {
+            key,
+            name: name.to_string(),
+            species: species.to_string(),
+        });
+    }
+
+    /// Finds up to `limit` species whose names are close to `query`, best matches first. Each species appears at most
+    /// once, under whichever of its names matched best.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchMatch> {
+        let query = normalize(query);
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let max_distance = (query.chars().count() / 3).max(1);
+
+        let mut candidates: Vec<(usize, &IndexEntry)> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                let distance = if entry.key.starts_with(&query) && entry.key != query {
+                    // Treat a prefix as one edit away, so "char" suggests charmander without outranking exact matches
+                    1
+                } else {
+                    strsim::levenshtein(&entry.key, &query)
+                };
+                (distance, entry)
+            })
+            .filter(|(distance, _)| *distance <= max_distance)
+            .collect();
+        candidates.sort_by_key(|(distance, entry)| (*distance, entry.key.len()));
+
+        let mut seen = HashSet::new();
+        candidates
+            .into_iter()
+            .filter(|(_, entry)| seen.insert(entry.species.as_str()))
+            .take(limit)
+            .map(|(distance, entry)| SearchMatch {
+                name: entry.name.clone(),
+                species: entry.species.clone(),
+                distance,
+            })
+            .collect()
+    }
+}
+
+/// Normalizes a name for matching: lowercases it and strips accents, whitespace and punctuation, so that `Flabébé`,
+/// `flabebe` and `Mr. Mime`/`mr-mime` compare equal.
+pub fn normalize(name: &str) -> String {
+    name.nfd()
+        .filter(|c| !is_combining_mark(*c))
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index() -> NameIndex {
+        let mut index = NameIndex::default();
+        for &(name, species) in &[
+            ("charmander", "charmander"),
+            ("Charmander", "charmander"),
+            ("Glumanda", "charmander"),
+            ("charmeleon", "charmeleon"),
+            ("charizard", "charizard"),
+            ("mr-mime", "mr-mime"),
+            ("Mr. Mime", "mr-mime"),
+            ("flabebe", "flabebe"),
+            ("Flabébé", "flabebe"),
+            ("mew", "mew"),
+            ("mewtwo", "mewtwo"),
+        ] {
+            index.insert(name, species);
+        }
+        index
+    }
+
+    fn species(matches: &[SearchMatch]) -> Vec<&str> {
+        matches.iter().map(|m| m.species.as_str()).collect()
+    }
+
+    #[test]
+    fn normalizing_ignores_case_accents_and_punctuation() {
+        assert_eq!(normalize("Flabébé"), "flabebe");
+        assert_eq!(normalize("Mr. Mime"), "mrmime");
+        assert_eq!(normalize("mr-mime"), "mrmime");
+        assert_eq!(normalize("NIDORAN♀"), "nidoran");
+        assert_eq!(normalize(" - "), "");
+    }
+
+    #[test]
+    fn names_equal_after_normalizing_are_indexed_once() {
+        let index = index();
+        let charmanders = index.entries.iter().filter(|e| e.species == "charmander").count();
+        assert_eq!(charmanders, 2);
+    }
+
+    #[test]
+    fn accents_and_case_do_not_affect_matches() {
+        let matches = index().search("FLABEBE", 5);
+        assert_eq!(species(&matches), vec!["flabebe"]);
+        assert_eq!(matches[0].distance, 0);
+
+        let matches = index().search("mr mime", 5);
+        assert_eq!(matches[0].species, "mr-mime");
+        assert_eq!(matches[0].distance, 0);
+    }
+
+    #[test]
+    fn exact_matches_rank_before_prefixes_and_typos() {
+        let matches = index().search("mew", 5);
+        assert_eq!(species(&matches), vec!["mew", "mewtwo"]);
+        assert_eq!(matches[1].distance, 1);
+
+        let matches = index().search("charmandr", 5);
+        assert_eq!(matches[0].species, "charmander");
+        assert_eq!(matches[0].distance, 1);
+    }
+
+    #[test]
+    fn prefixes_suggest_every_completion_shortest_first() {
+        let matches = index().search("char", 5);
+        assert_eq!(species(&matches), vec!["charizard", "charmander", "charmeleon"]);
+    }
+
+    #[test]
+    fn species_appear_once_under_their_best_name() {
+        let matches = index().search("glumanda", 5);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].name, "Glumanda");
+        assert_eq!(matches[0].species, "charmander");
+    }
+
+    #[test]
+    fn results_are_limited() {
+        assert_eq!(index().search("char", 2).len(), 2);
+        assert!(index().search("char", 0).is_empty());
+    }
+
+    #[test]
+    fn distant_and_empty_queries_match_nothing() {
+        assert!(index().search("pikachu", 5).is_empty());
+        assert!(index().search("", 5).is_empty());
+        assert!(index().search("!?", 5).is_empty());
+    }
+}
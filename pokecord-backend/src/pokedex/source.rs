@@ -0,0 +1,55 @@
+//! Abstraction over where Pokemon data comes from, so game logic can run against something other than PokeAPI.
+
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+
+use super::{ApiResource, Error, NamedResource, Pokedex};
+
+/// A source of PokeAPI resources. [`Pokedex`] is the real implementation; [`MemoryPokedex`](super::MemoryPokedex)
+/// serves a fixed set of resources for tests.
+#[async_trait]
+pub trait PokedexSource: Send {
+    /// Get an API resource by name.
+    async fn get_by_name<T: ApiResource>(&mut self, name: &str) -> Result<T, Error>;
+
+    /// Get an API resource by ID.
+    async fn get_by_id<T: ApiResource>(&mut self, id: usize) -> Result<T, Error>;
+
+    /// Get an API resource using a reference from another resource.
+    async fn get_by_ref<T: ApiResource>(&mut self, reference: &NamedResource<T>) -> Result<T, Error>;
+
+    /// Read an entire resource list. This may be expensive.
+    async fn list<T: ApiResource>(&mut self) -> Result<Vec<NamedResource<T>>, Error>;
+
+    /// Get every API resource in `references`, in the same order. Each distinct resource is only fetched once.
+    async fn get_all_by_ref<T: ApiResource>(&mut self, references: &[NamedResource<T>]) -> Result<Vec<T>, Error> {
+        let mut resolved: HashMap<&NamedResource<T>, T> = HashMap::new();
+        for reference in references {
+            if !resolved.contains_key(reference) {
+                let value = self.get_by_ref(reference).await?;
+                resolved.insert(reference, value);
+            }
+        }
+        Ok(references.iter().map(|r| resolved[r].clone()).collect())
+    }
+}
+
+#[async_trait]
+impl PokedexSource for Pokedex {
+    async fn get_by_name<T: ApiResource>(&mut self, name: &str) -> Result<T, Error> {
+        Pokedex::get_by_name(self, name).await
+    }
+
+    async fn get_by_id<T: ApiResource>(&mut self, id: usize) -> Result<T, Error> {
+        Pokedex::get_by_id(self, id).await
+    }
+
+    async fn get_by_ref<T: ApiResource>(&mut self, reference: &NamedResource<T>) -> Result<T, Error> {
+        Pokedex::get_by_ref(self, reference).await
+    }
+
+    async fn list<T: ApiResource>(&mut self) -> Result<Vec<NamedResource<T>>, Error> {
+        Pokedex::list(self).await
+    }
+}
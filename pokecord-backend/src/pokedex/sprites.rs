@@ -0,0 +1,68 @@
+//! Picks the best image of a Pokemon out of its [`PokemonSprites`].
+
+use url::Url;
+
+use super::{image_url, Pokemon, PokemonSprites, SpriteSet};
+
+/// Which depiction of a Pokemon to show. Alternate forms don't need a flag here, since each variety has its own
+/// [`Pokemon`] resource with its own sprites.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SpriteVariant {
+    pub shiny: bool,
+    pub female: bool,
+    /// Seen from behind, as the player's own Pokemon is in battle.
+    pub back: bool,
+    /// Prefer animated sprites over still images.
+    pub animated: bool,
+}
+
+impl SpriteSet {
+    /// Gets exactly the requested variant, if this set has it.
+    fn get(&self, variant: SpriteVariant) -> Option<&Url> {
+        let url = match (variant.back, variant.shiny, variant.female) {
+            (false, false, false) => &self.front_default,
+            (false, true, false) => &self.front_shiny,
+            (false, false, true) => &self.front_female,
+            (false, true, true) => &self.front_shiny_female,
+            (true, false, false) => &self.back_default,
+            (true, true, false) => &self.back_shiny,
+            (true, false, true) => &self.back_female,
+            (true, true, true) => &self.back_shiny_female,
+        };
+        url.as_ref()
+    }
+}
+
+impl PokemonSprites {
+    /// Picks the best available image for `variant`. Sources are tried from highest to lowest quality, and if none
+    /// has the exact variant the request is relaxed: first female art is dropped (most species look the same either
+    /// way), then shininess, then the back view.
+    pub fn resolve(&self, variant: SpriteVariant) -> Option<&Url> {
+        let relaxed = [
+            variant,
+            SpriteVariant { female: false, ..variant },
+            SpriteVariant { female: false, shiny: false, ..variant },
+            SpriteVariant { female: false, shiny: false, back: false, ..variant },
+        ];
+        let sources: Vec<&SpriteSet> = if variant.animated {
+            vec![&self.versions.generation_v.black_white.animated, &self.game]
+        } else {
+            vec![&self.other.official_artwork, &self.other.home, &self.game]
+        };
+
+        relaxed
+            .iter()
+            .find_map(|v| sources.iter().find_map(|set| set.get(*v)))
+    }
+}
+
+impl Pokemon {
+    /// The URL of the best available image of this Pokemon for `variant`. This only falls back to [`image_url`] if
+    /// the API has no sprites at all for it.
+    pub fn sprite_url(&self, variant: SpriteVariant) -> Url {
+        self.sprites
+            .resolve(variant)
+            .cloned()
+            .unwrap_or_else(|| image_url(self.id))
+    }
+}
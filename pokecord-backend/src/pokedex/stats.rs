@@ -0,0 +1,68 @@
+//! Counters for how effective the PokeAPI cache is.
+//!
+//! Counters are process-wide rather than per-[`Pokedex`](super::Pokedex), since a new client is usually created for
+//! every request.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Cache counters since the process started, plus the on-disk footprint of the cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+    /// Requests served straight from a fresh cache entry.
+    pub fresh_hits: u64,
+    /// Stale entries the server confirmed were still current (HTTP 304).
+    pub revalidated: u64,
+    /// Stale entries that the server returned new data for.
+    pub modified: u64,
+    /// Requests with no usable cache entry.
+    pub misses: u64,
+    /// Responses that weren't allowed to be cached.
+    pub uncacheable: u64,
+    /// Failed cache updates.
+    pub write_failures: u64,
+    /// Number of entries in the cache directory.
+    pub entries: u64,
+    /// Total size of the cache entries in bytes.
+    pub disk_bytes: u64,
+}
+
+/// Live counters backing [`CacheStats`].
+#[derive(Debug, Default)]
+pub struct Counters {
+    pub fresh_hits: AtomicU64,
+    pub revalidated: AtomicU64,
+    pub modified: AtomicU64,
+    pub misses: AtomicU64,
+    pub uncacheable: AtomicU64,
+    pub write_failures: AtomicU64,
+}
+
+/// The process-wide cache counters.
+pub static COUNTERS: Counters = Counters {
+    fresh_hits: AtomicU64::new(0),
+    revalidated: AtomicU64::new(0),
+    modified: AtomicU64::new(0),
+    misses: AtomicU64::new(0),
+    uncacheable: AtomicU64::new(0),
+    write_failures: AtomicU64::new(0),
+};
+
+/// Increments one of the [`COUNTERS`].
+pub fn record(counter: &AtomicU64) {
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+impl Counters {
+    /// The current counter values. Disk usage is left at zero, since it has to be measured separately.
+    pub fn snapshot(&self) -> CacheStats {
+        CacheStats {
+            fresh_hits: self.fresh_hits.load(Ordering::Relaxed),
+            revalidated: self.revalidated.load(Ordering::Relaxed),
+            modified: self.modified.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            uncacheable: self.uncacheable.load(Ordering::Relaxed),
+            write_failures: self.write_failures.load(Ordering::Relaxed),
+            ..CacheStats::default()
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! Builders for PokeAPI resources in tests, filled in with plausible defaults for everything but the fields a test
+//! cares about. Build a [`MemoryPokedex`](super::MemoryPokedex) from them to test game logic.
+
+use serde_json::{json, Value};
+
+use super::{
+    EvolutionChain, EvolutionDetail, Generation, GrowthRate, Item, NamedResource, Pokemon, PokemonForm, PokemonSpecies,
+    Region, API_BASE,
+};
+
+/// A variety of a species: the ID and name of its Pokemon, and whether it's the default.
+pub type Variety<'a> = (usize, &'a str, bool);
+
+/// A reference to the resource at `endpoint`, e.g. `pokemon/1/`.
+fn reference(name: &str, endpoint: &str) -> Value {
+    json!({ "name": name, "url": format!("{}{}", API_BASE, endpoint) })
+}
+
+/// A list of names with a single English entry.
+fn english(name: &str) -> Value {
+    json!([{ "name": name, "language": reference("en", "language/9/") }])
+}
+
+/// A species named `name` (e.g. `vulpix`), displayed as `display_name` in English.
+pub fn species(id: usize, name: &str, display_name: &str, varieties: &[Variety]) -> PokemonSpecies {
+    let varieties: Vec<_> = varieties
+        .iter()
+        .map(|&(id, name, is_default)| {
+            json!({ "is_default": is_default, "pokemon": reference(name, &format!("pokemon/{}/", id)) })
+        })
+        .collect();
+    serde_json::from_value(json!({
+        "id": id,
+        "name": name,
+        "order": id,
+        "gender_rate": 4,
+        "capture_rate": 45,
+        "base_happiness": 50,
+        "is_baby": false,
+        "is_legendary": false,
+        "is_mythical": false,
+        "hatch_counter": 20,
+        "has_gender_differences": false,
+        "forms_switchable": false,
+        "growth_rate": reference("medium-slow", "growth-rate/4/"),
+        "evolves_from_species": null,
+        "evolution_chain": { "url": format!("{}evolution-chain/{}/", API_BASE, id) },
+        "names": english(display_name),
+        "flavor_text_entries": [],
+        "form_descriptions": [],
+        "varieties": varieties,
+    }))
+    .expect("Invalid test species")
+}
+
+/// A Pokemon variety of the species `species_id`, with the given types in slot order.
+pub fn pokemon(id: usize, name: &str, species_id: usize, types: &[&str]) -> Pokemon {
+    let types: Vec<_> = types
+        .iter()
+        .enumerate()
+        .map(|(slot, name)| json!({ "slot": slot + 1, "type": reference(name, &format!("type/{}/", name)) }))
+        .collect();
+    serde_json::from_value(json!({
+        "id": id,
+        "name": name,
+        "base_experience": 64,
+        "is_default": id == species_id,
+        "order": id,
+        "species": reference(name, &format!("pokemon-species/{}/", species_id)),
+        "forms": [reference(name, &format!("pokemon-form/{}/", id))],
+        "sprites": {},
+        "types": types,
+        "stats": [],
+        "moves": [],
+    }))
+    .expect("Invalid test pokemon")
+}
+
+/// The only form of the Pokemon `id`, which [`pokemon`] refers to by the same ID and name.
+pub fn form(id: usize, name: &str, is_battle_only: bool) -> PokemonForm {
+    serde_json::from_value(json!({
+        "id": id,
+        "name": name,
+        "order": id,
+        "form_order": 1,
+        "is_default": true,
+        "is_battle_only": is_battle_only,
+        "is_mega": false,
+        "form_name": "",
+        "pokemon": reference(name, &format!("pokemon/{}/", id)),
+        "names": [],
+        "form_names": [],
+    }))
+    .expect("Invalid test form")
+}
+
+/// The growth rate [`species`] refer to, where reaching `level` takes `experience(level)` experience in total.
+pub fn growth_rate(experience: impl Fn(u32) -> u32) -> GrowthRate {
+    let levels: Vec<_> = (1..=100)
+        .map(|level| json!({ "level": level, "experience": experience(level) }))
+        .collect();
+    serde_json::from_value(json!({
+        "id": 4,
+        "name": "medium-slow",
+        "formula": "",
+        "levels": levels,
+        "pokemon_species": [],
+    }))
+    .expect("Invalid test growth rate")
+}
+
+/// The evolution chain of species `base`, given as its ID and name, which evolves into each of `evolutions`. Its ID
+/// is the base species' ID, as [`species`] expects.
+pub fn evolution_chain(base: (usize, &str), evolutions: &[(usize, &str, EvolutionDetail)]) -> EvolutionChain {
+    let link = |(id, name): (usize, &str), details: Vec<&EvolutionDetail>, evolves_to: Vec<Value>| {
+        json!({
+            "is_baby": false,
+            "species": reference(name, &format!("pokemon-species/{}/", id)),
+            "evolution_details": details,
+            "evolves_to": evolves_to,
+        })
+    };
+    let evolves_to = evolutions
+        .iter()
+        .map(|(id, name, detail)| link((*id, *name), vec![detail], Vec::new()))
+        .collect();
+    serde_json::from_value(json!({ "id": base.0, "chain": link(base, Vec::new(), evolves_to) }))
+        .expect("Invalid test evolution chain")
+}
+
+/// An evolution by `trigger` (e.g. `level-up`) with no conditions. Set the conditions a test needs on the result.
+pub fn evolution_detail(trigger: &str) -> EvolutionDetail {
+    serde_json::from_value(json!({
+        "item": null,
+        "trigger": reference(trigger, &format!("evolution-trigger/{}/", trigger)),
+        "gender": null,
+        "held_item": null,
+        "known_move": null,
+        "known_move_type": null,
+        "location": null,
+        "min_level": null,
+        "min_happiness": null,
+        "min_beauty": null,
+        "min_affection": null,
+        "needs_overworld_rain": false,
+        "party_species": null,
+        "party_type": null,
+        "relative_physical_stats": null,
+        "time_of_day": "",
+        "trade_species": null,
+        "turn_upside_down": false,
+    }))
+    .expect("Invalid test evolution detail")
+}
+
+/// A reference to the species `name`, e.g. `charmander`.
+pub fn species_ref(id: usize, name: &str) -> NamedResource<PokemonSpecies> {
+    serde_json::from_value(reference(name, &format!("pokemon-species/{}/", id))).expect("Invalid test species")
+}
+
+/// A reference to the item named `name`, e.g. `fire-stone`.
+pub fn item(name: &str) -> NamedResource<Item> {
+    serde_json::from_value(reference(name, &format!("item/{}/", name))).expect("Invalid test item")
+}
+
+/// A generation introducing `species`, given as IDs and names, whose main region is `region_id`.
+pub fn generation(id: usize, name: &str, region_id: usize, species: &[(usize, &str)]) -> Generation {
+    let species: Vec<_> = species
+        .iter()
+        .map(|&(id, name)| reference(name, &format!("pokemon-species/{}/", id)))
+        .collect();
+    serde_json::from_value(json!({
+        "id": id,
+        "name": name,
+        "main_region": reference("", &format!("region/{}/", region_id)),
+        "names": [],
+        "pokemon_species": species,
+    }))
+    .expect("Invalid test generation")
+}
+
+/// A region displayed as `display_name` in English.
+pub fn region(id: usize, name: &str, display_name: &str) -> Region {
+    serde_json::from_value(json!({ "id": id, "name": name, "names": english(display_name) })).expect("Invalid test region")
+}
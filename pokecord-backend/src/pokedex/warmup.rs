@@ -0,0 +1,204 @@
+//! Prefetches whole resource lists into the cache, so gameplay never has to wait on a cold PokeAPI request.
+//!
+//! Progress is checkpointed to the cache as it goes, so an interrupted warm-up picks up where it left off
+//! the next time it runs.
+
+use std::{collections::HashMap, fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    cache::Cache, ApiResource, Error, Generation, GrowthRate, Move, Nature, Pokedex, Pokemon, PokemonForm, PokemonSpecies,
+    Type,
+};
+
+/// Name the checkpoint is stored under in the cache, unless the warm-up is for a particular shard.
+const CHECKPOINT_NAME: &str = "warmup.json";
+
+/// Number of resources to fetch between checkpoint writes.
+const CHECKPOINT_INTERVAL: usize = 25;
+
+/// A resource type that can be prefetched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Target {
+    Pokemon,
+    PokemonSpecies,
+    PokemonForm,
+    Type,
+    GrowthRate,
+    Generation,
+    Move,
+    Nature,
+}
+
+/// The resources needed for spawning, leveling, Pokedex completion and battles.
+pub const DEFAULT_TARGETS: &[Target] = &[
+    Target::PokemonSpecies,
+    Target::Pokemon,
+    Target::Type,
+    Target::GrowthRate,
+    Target::Generation,
+    Target::Move,
+    Target::Nature,
+];
+
+/// Warm-up progress through one target's resource list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    pub target: Target,
+    /// Number of resources fetched so far, including those fetched before resuming.
+    pub done: usize,
+    pub total: usize,
+}
+
+/// How far previous runs got through each target.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Checkpoint {
+    /// Name this checkpoint is stored under in the cache.
+    #[serde(skip)]
+    name: String,
+    done: HashMap<Target, usize>,
+}
+
+/// Fetches every resource of each of `targets` through `pokedex`'s cache, calling `on_progress` after each one.
+/// Resources that fail to fetch are logged and skipped.
+///
+/// Processes warming a shared cache at the same time should each pass their own `shard` name, so they keep separate
+/// checkpoints instead of resuming from each other's progress.
+pub async fn warm_up<F>(
+    pokedex: &mut Pokedex,
+    targets: &[Target],
+    shard: Option<&str>,
+    mut on_progress: F,
+) -> Result<(), Error>
+where
+    F: FnMut(Progress) + Send,
+{
+    let cache = pokedex.cache.clone();
+    let mut checkpoint = load_checkpoint(&cache, shard).await?;
+
+    for &target in targets {
+        match target {
+            Target::Pokemon => {
+                warm::<Pokemon, _>(pokedex, target, &mut checkpoint, &cache, &mut on_progress).await?
+            }
+            Target::PokemonSpecies => {
+                warm::<PokemonSpecies, _>(pokedex, target, &mut checkpoint, &cache, &mut on_progress)
+                    .await?
+            }
+            Target::PokemonForm => {
+                warm::<PokemonForm, _>(pokedex, target, &mut checkpoint, &cache, &mut on_progress).await?
+            }
+            Target::Type => {
+                warm::<Type, _>(pokedex, target, &mut checkpoint, &cache, &mut on_progress).await?
+            }
+            Target::GrowthRate => {
+                warm::<GrowthRate, _>(pokedex, target, &mut checkpoint, &cache, &mut on_progress).await?
+            }
+            Target::Generation => {
+                warm::<Generation, _>(pokedex, target, &mut checkpoint, &cache, &mut on_progress).await?
+            }
+            Target::Move => {
+                warm::<Move, _>(pokedex, target, &mut checkpoint, &cache, &mut on_progress).await?
+            }
+            Target::Nature => {
+                warm::<Nature, _>(pokedex, target, &mut checkpoint, &cache, &mut on_progress).await?
+            }
+        }
+    }
+
+    // Everything is warm, so the next run should start over and revalidate from the beginning
+    cache.remove_meta(&checkpoint.name).await
+}
+
+async fn warm<T: ApiResource, F: FnMut(Progress)>(
+    pokedex: &mut Pokedex,
+    target: Target,
+    checkpoint: &mut Checkpoint,
+    cache: &Cache,
+    on_progress: &mut F,
+) -> Result<(), Error> {
+    let references = pokedex.list::<T>().await?;
+    let total = references.len();
+    let resume_at = checkpoint.done.get(&target).copied().unwrap_or(0).min(total);
+    if resume_at > 0 {
+        log::info!("Resuming {} warm-up at {}/{}", target, resume_at, total);
+    }
+
+    for (i, reference) in references.iter().enumerate().skip(resume_at) {
+        if let Err(err) = pokedex.get_by_ref(reference).await {
+            log::warn!("Failed to warm {}: {}", reference.url, err);
+        }
+
+        let done = i + 1;
+        on_progress(Progress { target, done, total });
+        if done % CHECKPOINT_INTERVAL == 0 || done == total {
+            checkpoint.done.insert(target, done);
+            save_checkpoint(cache, checkpoint).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn load_checkpoint(cache: &Cache, shard: Option<&str>) -> Result<Checkpoint, Error> {
+    let name = match shard {
+        Some(shard) => format!("warmup-{}.json", shard),
+        None => CHECKPOINT_NAME.to_string(),
+    };
+    let checkpoint = match cache.get_meta(&name).await? {
+        Some(data) => serde_json::from_slice(&data)?,
+        None => Checkpoint::default(),
+    };
+    Ok(Checkpoint { name, ..checkpoint })
+}
+
+async fn save_checkpoint(cache: &Cache, checkpoint: &Checkpoint) -> Result<(), Error> {
+    cache.put_meta(&checkpoint.name, serde_json::to_vec(checkpoint)?).await
+}
+
+impl Target {
+    /// Every target, in the order they're best warmed in.
+    pub const ALL: &'static [Target] = &[
+        Target::PokemonSpecies,
+        Target::Pokemon,
+        Target::PokemonForm,
+        Target::Type,
+        Target::GrowthRate,
+        Target::Generation,
+        Target::Move,
+        Target::Nature,
+    ];
+
+    /// The API endpoint name for this target, e.g. `pokemon-species`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Target::Pokemon => "pokemon",
+            Target::PokemonSpecies => "pokemon-species",
+            Target::PokemonForm => "pokemon-form",
+            Target::Type => "type",
+            Target::GrowthRate => "growth-rate",
+            Target::Generation => "generation",
+            Target::Move => "move",
+            Target::Nature => "nature",
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Target, String> {
+        Target::ALL
+            .iter()
+            .copied()
+            .find(|target| target.name() == s)
+            .ok_or_else(|| format!("unknown resource type `{}`", s))
+    }
+}
@@ -1,5 +1,108 @@
+use crate::database::{
+    records::{OwnedPokemon, Player},
+    repository::{PlayerRepository, PokemonRepository, Transactional, UnitOfWork},
+    Error,
+};
 use crate::models::Pokemon;
 
-pub async fn is_player_registered(player_id: &str) {}
-pub async fn get_starter_pokemon_list(generation: Option<u8>) {}
-pub async fn register_player(player_id: &str, starter_pokemon: Pokemon) {}
+/// Level starters begin at.
+const STARTER_LEVEL: u32 = 5;
+
+pub async fn is_player_registered<R: PlayerRepository>(repo: &R, player_id: &str) -> Result<bool, Error> {
+    Ok(repo.get_player(player_id).await?.is_some())
+}
+
+/// Adds a player, giving them `starter_pokemon` if they chose one. Fails with [`Error::AlreadyExists`] if the player
+/// is already registered.
+pub async fn register_player<R: Transactional>(
+    repo: &R,
+    player_id: &str,
+    starter_pokemon: Option<Pokemon>,
+) -> Result<Player, Error> {
+    let mut player = Player::new(player_id);
+    let starter = starter_pokemon.map(|starter| OwnedPokemon {
+        types: starter.types.clone(),
+        legendary: starter.is_legendary,
+        ..OwnedPokemon::generate(
+            player_id,
+            starter.species_id as u32,
+            &starter.species_name,
+            starter.variety_id as u32,
+            STARTER_LEVEL,
+            &mut rand::thread_rng(),
+        )
+    });
+    if let Some(starter) = &starter {
+        player.starter = Some(starter.id);
+        player.selected = Some(starter.id);
+        player.party.push(starter.id);
+        player.seen_species.insert(starter.species_id);
+        player.caught_species.insert(starter.species_id);
+    }
+    let work = repo.begin().await?;
+    work.insert_player(&player).await?;
+    if let Some(starter) = &starter {
+        work.insert_pokemon(starter).await?;
+    }
+    work.commit().await?;
+    Ok(player)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::repository::MemoryRepository;
+
+    fn bulbasaur() -> Pokemon {
+        Pokemon {
+            species_id: 1,
+            species_name: "bulbasaur".to_string(),
+            variety_id: 1,
+            variety_name: "bulbasaur".to_string(),
+            is_default_variety: true,
+            display_name: "Bulbasaur".to_string(),
+            types: vec!["grass".to_string(), "poison".to_string()],
+            ..Pokemon::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn registering_gives_the_player_their_starter() {
+        let repo = MemoryRepository::new();
+        assert!(!is_player_registered(&repo, "ash").await.unwrap());
+
+        register_player(&repo, "ash", Some(bulbasaur())).await.unwrap();
+        assert!(is_player_registered(&repo, "ash").await.unwrap());
+
+        let owned = repo.pokemon_owned_by("ash").await.unwrap();
+        assert_eq!(owned.len(), 1);
+        let starter = &owned[0];
+        assert_eq!((starter.species_id, starter.variety_id, starter.level), (1, 1, STARTER_LEVEL));
+        assert_eq!(starter.types, ["grass", "poison"]);
+
+        let player = repo.get_player("ash").await.unwrap().unwrap();
+        assert_eq!(player.starter, Some(starter.id));
+        assert_eq!(player.selected, Some(starter.id));
+        assert_eq!(player.party, [starter.id]);
+        assert!(player.seen_species.contains(&1) && player.caught_species.contains(&1));
+    }
+
+    #[tokio::test]
+    async fn registering_without_a_starter() {
+        let repo = MemoryRepository::new();
+        let player = register_player(&repo, "ash", None).await.unwrap();
+        assert_eq!(player.starter, None);
+        assert!(player.party.is_empty());
+        assert!(repo.pokemon_owned_by("ash").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn registering_twice_fails_without_another_starter() {
+        let repo = MemoryRepository::new();
+        register_player(&repo, "ash", Some(bulbasaur())).await.unwrap();
+
+        let result = register_player(&repo, "ash", Some(bulbasaur())).await;
+        assert!(matches!(result, Err(Error::AlreadyExists(id)) if id == "ash"));
+        assert_eq!(repo.pokemon_owned_by("ash").await.unwrap().len(), 1);
+    }
+}
@@ -3,7 +3,10 @@
 //! joined. This module also contains functions for getting the list of starter
 //! pokemon.
 
+use crate::database::{self, MongoRepository};
+use crate::guilds;
 use crate::models::Pokemon;
+use pyo3::exceptions::PyKeyError;
 use pyo3::prelude::*;
 use pyo3_asyncio::tokio as pytokio;
 
@@ -20,11 +23,31 @@ pub fn init_submodule(module: &PyModule) -> PyResult<()> {
 
 /// Registers a player into the game for the first time.
 /// This function will return None on success and raise a
-/// `KeyError` on failure.
+/// `KeyError` if the player is already registered.
+///
+/// # Arguments
+///
+/// * `starter` - Optional starter `Pokemon` the player chose, which is added
+///   to their collection.
+/// * `guild_id` - Optional ID of the guild the player is registering in. If
+///   it keeps progress per guild, the player is only registered there.
 #[pyfunction]
-#[text_signature = "(player_id, /)"]
-fn register_player(py: Python, player_id: &str) -> PyResult<PyObject> {
-    pytokio::into_coroutine(py, async move { Python::with_gil(|py| Ok(py.None())) })
+#[text_signature = "(player_id, starter=None, guild_id=None, /)"]
+fn register_player(
+    py: Python,
+    player_id: String,
+    starter: Option<Pokemon>,
+    guild_id: Option<String>,
+) -> PyResult<PyObject> {
+    pytokio::into_coroutine(py, async move {
+        let repo = MongoRepository::new(database::get()?);
+        let key = guilds::player_key(&repo, guild_id.as_deref(), &player_id).await?;
+        match handlers::register_player(&repo, &key, starter).await {
+            Ok(_) => Ok(Python::with_gil(|py| py.None())),
+            Err(database::Error::AlreadyExists(id)) => Err(PyKeyError::new_err(id)),
+            Err(err) => Err(err.into()),
+        }
+    })
 }
 
 /// Fetches the starter pokemon list.
@@ -34,18 +57,24 @@ fn register_player(py: Python, player_id: &str) -> PyResult<PyObject> {
 /// A `list` of `Pokemon`
 #[pyfunction]
 #[text_signature = "(/)"]
-fn get_starter_pokemon_list(py: Python) -> PyResult<Vec<Pokemon>> {
+fn get_starter_pokemon_list(_py: Python) -> PyResult<Vec<Pokemon>> {
     log::error!("Here");
-    Ok(vec![Pokemon {}])
+    Ok(vec![Pokemon::default()])
 }
 
-/// Checks if a given player is registered for the game already.
+/// Checks if a given player is registered for the game already, in
+/// `guild_id` if given.
 ///
 /// # Returns
 ///
 /// `True` if the player is registered. `False` otherwise.
 #[pyfunction]
-#[text_signature = "(player_id, /)"]
-fn is_player_registered(py: Python, player_id: &str) -> PyResult<bool> {
-    Ok(false)
+#[text_signature = "(player_id, guild_id=None, /)"]
+fn is_player_registered(py: Python, player_id: String, guild_id: Option<String>) -> PyResult<PyObject> {
+    pytokio::into_coroutine(py, async move {
+        let repo = MongoRepository::new(database::get()?);
+        let key = guilds::player_key(&repo, guild_id.as_deref(), &player_id).await?;
+        let registered = handlers::is_player_registered(&repo, &key).await?;
+        Ok(Python::with_gil(|py| registered.into_py(py)))
+    })
 }
@@ -0,0 +1,378 @@
+use std::iter;
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::database::{
+    self,
+    records::OwnedPokemon,
+    repository::{PlayerRepository, PokemonRepository, SpawnRepository, Transactional, UnitOfWork},
+};
+use crate::error::Error;
+use crate::models;
+use crate::pokedex::{
+    self, normalize_name, Named, NamedResource, PokedexSource, Pokemon, PokemonSpecies, PokemonSpeciesVariety,
+    SpriteVariant,
+};
+
+/// Chance that a spawn picks one of its species' non-default varieties (regional
+/// variants, alternate forms, etc.) instead of the default variety.
+const VARIETY_SPAWN_CHANCE: f64 = 0.1;
+
+/// Picks a random species, and a variety of that species, to spawn. Names and
+/// flavor text are localized to the first available language of `languages`.
+pub async fn spawn_pokemon<P: PokedexSource>(
+    pokedex: &mut P,
+    languages: &[String],
+) -> Result<models::Pokemon, pokedex::Error> {
+    let all_species = pokedex.list::<PokemonSpecies>().await?;
+    let species_ref = all_species
+        .choose(&mut rand::thread_rng())
+        .ok_or_else(|| pokedex::Error::Incomplete("no species".to_string()))?
+        .clone();
+    let species = pokedex.get_by_ref(&species_ref).await?;
+
+    let varieties = spawnable_varieties(pokedex, &species).await?;
+    let variety = pick_variety(&varieties, &mut rand::thread_rng())
+        .ok_or_else(|| pokedex::Error::Incomplete(format!("{} has no varieties", species.name)))?;
+    let pokemon = pokedex.get_by_ref(&variety.pokemon).await?;
+
+    let species_display_name = species
+        .localized_name(languages)
+        .unwrap_or(species.name.as_str())
+        .to_string();
+    // Alternate varieties get their full name (e.g. "Alolan Vulpix") from their form
+    let display_name = match pokemon.forms.first() {
+        Some(form_ref) if !variety.is_default => pokedex
+            .get_by_ref(form_ref)
+            .await?
+            .localized_name(languages)
+            .map(str::to_string)
+            .unwrap_or(species_display_name),
+        _ => species_display_name,
+    };
+
+    Ok(models::Pokemon {
+        species_id: species.id,
+        flavor_text: species.localized_flavor_text(languages),
+        species_name: species.name,
+        variety_id: pokemon.id,
+        image_url: pokemon.sprite_url(SpriteVariant::default()).to_string(),
+        variety_name: pokemon.name,
+        is_default_variety: variety.is_default,
+        display_name,
+        types: pokemon.types.iter().map(|t| t.typ.name.clone()).collect(),
+        is_legendary: species.is_legendary || species.is_mythical,
+    })
+}
+
+/// The varieties of `species` that can appear in the wild: its default
+/// variety, and every other variety with a form outside of battle. This leaves
+/// out mega evolutions, Gigantamax forms and the like.
+async fn spawnable_varieties<P: PokedexSource>(
+    pokedex: &mut P,
+    species: &PokemonSpecies,
+) -> Result<Vec<PokemonSpeciesVariety>, pokedex::Error> {
+    let mut spawnable = Vec::with_capacity(species.varieties.len());
+    for variety in &species.varieties {
+        if variety.is_default || !is_battle_only(pokedex, &variety.pokemon).await? {
+            spawnable.push(variety.clone());
+        }
+    }
+    Ok(spawnable)
+}
+
+/// Whether every form of `pokemon` only exists during battle.
+async fn is_battle_only<P: PokedexSource>(
+    pokedex: &mut P,
+    pokemon: &NamedResource<Pokemon>,
+) -> Result<bool, pokedex::Error> {
+    let pokemon = pokedex.get_by_ref(pokemon).await?;
+    for form_ref in &pokemon.forms {
+        if !pokedex.get_by_ref(form_ref).await?.is_battle_only {
+            return Ok(false);
+        }
+    }
+    Ok(!pokemon.forms.is_empty())
+}
+
+/// Picks which of a species' `varieties` should spawn. This is almost always
+/// the default variety, but occasionally one of the others. Returns `None` if
+/// there are no varieties to pick from.
+pub fn pick_variety<'a, R: Rng + ?Sized>(
+    varieties: &'a [PokemonSpeciesVariety],
+    rng: &mut R,
+) -> Option<&'a PokemonSpeciesVariety> {
+    let (defaults, others): (Vec<_>, Vec<_>) = varieties.iter().partition(|v| v.is_default);
+    if !others.is_empty() && rng.gen_bool(VARIETY_SPAWN_CHANCE) {
+        if let Some(&other) = others.choose(rng) {
+            return Some(other);
+        }
+    }
+    defaults.first().or_else(|| others.first()).copied()
+}
+
+/// Outcome of a catch attempt.
+#[derive(Debug, Clone)]
+pub enum CatchResult {
+    Caught(Box<OwnedPokemon>),
+    /// The guess didn't name the species that spawned.
+    WrongGuess,
+    /// Nothing has spawned in the channel, or someone else caught it first.
+    NoSpawn,
+}
+
+/// Tries to catch the pokemon waiting in a channel by guessing its species' name in any language. The player sees the
+/// species whether or not the guess is right.
+pub async fn catch_pokemon<R: Transactional, P: PokedexSource>(
+    repo: &R,
+    pokedex: &mut P,
+    player_key: &str,
+    channel_id: &str,
+    guess: &str,
+) -> Result<CatchResult, Error> {
+    let spawn = match repo.active_spawn(channel_id).await? {
+        Some(spawn) => spawn,
+        None => return Ok(CatchResult::NoSpawn),
+    };
+    let species: PokemonSpecies = pokedex.get_by_id(spawn.species_id as usize).await?;
+    let guess = normalize_name(guess);
+    let correct = iter::once(species.name.as_str())
+        .chain(species.names.iter().map(|n| n.name.as_str()))
+        .any(|name| normalize_name(name) == guess);
+
+    let work = repo.begin().await?;
+    let mut player = work
+        .get_player(player_key)
+        .await?
+        .ok_or_else(|| database::Error::NotRegistered(player_key.to_string()))?;
+    player.seen_species.insert(spawn.species_id);
+    let result = if correct {
+        match work.take_spawn(channel_id).await? {
+            // Only the pokemon this guess was about can be caught, not one that spawned since
+            Some(taken) if taken.spawned_at == spawn.spawned_at => {
+                let pokemon = OwnedPokemon::caught_from(player_key, &taken, &mut rand::thread_rng());
+                work.insert_pokemon(&pokemon).await?;
+                player.total_caught += 1;
+                player.caught_species.insert(pokemon.species_id);
+                CatchResult::Caught(Box::new(pokemon))
+            }
+            // A new pokemon spawned since the guess was made, so leave it to be caught
+            Some(newer) => {
+                work.put_spawn(&newer).await?;
+                CatchResult::NoSpawn
+            }
+            // Someone else caught it first, but the player still saw it
+            None => CatchResult::NoSpawn,
+        }
+    } else {
+        CatchResult::WrongGuess
+    };
+    work.update_player(&player).await?;
+    work.commit().await?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::*;
+    use crate::database::{
+        records::{Player, Spawn},
+        repository::MemoryRepository,
+    };
+    use crate::pokedex::test_data::{form, pokemon, species, Variety};
+    use crate::pokedex::MemoryPokedex;
+
+    fn english() -> Vec<String> {
+        vec!["en".to_string()]
+    }
+
+    /// A Pokedex holding only Bulbasaur.
+    fn bulbasaur_dex() -> MemoryPokedex {
+        let mut pokedex = MemoryPokedex::new();
+        pokedex
+            .insert(&species(1, "bulbasaur", "Bulbasaur", &[(1, "bulbasaur", true)]))
+            .unwrap();
+        pokedex
+            .insert(&pokemon(1, "bulbasaur", 1, &["grass", "poison"]))
+            .unwrap();
+        pokedex
+    }
+
+    /// A repository with two registered players and a Bulbasaur waiting in `#route-1`.
+    async fn wild_bulbasaur(pokedex: &mut MemoryPokedex) -> MemoryRepository {
+        let repo = MemoryRepository::new();
+        repo.insert_player(&Player::new("ash")).await.unwrap();
+        repo.insert_player(&Player::new("misty")).await.unwrap();
+        let spawned = spawn_pokemon(pokedex, &english()).await.unwrap();
+        let spawn = Spawn::new("route-1", None, &spawned, &mut ChaCha8Rng::seed_from_u64(0));
+        repo.put_spawn(&spawn).await.unwrap();
+        repo
+    }
+
+    /// A Pokedex holding `species` and its varieties, whose forms are battle-only if listed in `battle_only`.
+    fn dex_with_varieties(species_id: usize, name: &str, varieties: &[Variety], battle_only: &[&str]) -> MemoryPokedex {
+        let mut pokedex = MemoryPokedex::new();
+        pokedex.insert(&species(species_id, name, name, varieties)).unwrap();
+        for &(id, name, _) in varieties {
+            pokedex.insert(&pokemon(id, name, species_id, &["grass"])).unwrap();
+            pokedex.insert(&form(id, name, battle_only.contains(&name))).unwrap();
+        }
+        pokedex
+    }
+
+    #[tokio::test]
+    async fn battle_only_varieties_never_spawn() {
+        let varieties = [
+            (3, "venusaur", true),
+            (10033, "venusaur-mega", false),
+            (10195, "venusaur-gmax", false),
+        ];
+        let mut pokedex = dex_with_varieties(3, "venusaur", &varieties, &["venusaur-mega", "venusaur-gmax"]);
+        let venusaur = pokedex.get_by_id::<PokemonSpecies>(3).await.unwrap();
+        let spawnable = spawnable_varieties(&mut pokedex, &venusaur).await.unwrap();
+        let names: Vec<_> = spawnable.iter().map(|v| v.pokemon.name.as_str()).collect();
+        assert_eq!(names, ["venusaur"]);
+    }
+
+    #[tokio::test]
+    async fn alternate_forms_outside_battle_can_spawn() {
+        let varieties = [(37, "vulpix", true), (10103, "vulpix-alola", false)];
+        let mut pokedex = dex_with_varieties(37, "vulpix", &varieties, &[]);
+        let vulpix = pokedex.get_by_id::<PokemonSpecies>(37).await.unwrap();
+        let spawnable = spawnable_varieties(&mut pokedex, &vulpix).await.unwrap();
+        assert_eq!(spawnable, vulpix.varieties);
+    }
+
+    #[tokio::test]
+    async fn default_varieties_spawn_even_if_battle_only() {
+        let varieties = [(888, "zacian", true)];
+        let mut pokedex = dex_with_varieties(888, "zacian", &varieties, &["zacian"]);
+        let zacian = pokedex.get_by_id::<PokemonSpecies>(888).await.unwrap();
+        assert_eq!(
+            spawnable_varieties(&mut pokedex, &zacian).await.unwrap(),
+            zacian.varieties
+        );
+    }
+
+    #[test]
+    fn pick_variety_occasionally_picks_other_varieties() {
+        let vulpix = species(
+            37,
+            "vulpix",
+            "Vulpix",
+            &[(37, "vulpix", true), (10103, "vulpix-alola", false)],
+        );
+        let mut rng = ChaCha8Rng::seed_from_u64(0);
+        let alolan = (0..1000)
+            .filter(|_| pick_variety(&vulpix.varieties, &mut rng).unwrap().pokemon.name == "vulpix-alola")
+            .count();
+        assert!(
+            alolan > 0 && alolan < 500,
+            "picked the alternate variety {} times",
+            alolan
+        );
+    }
+
+    #[test]
+    fn pick_variety_needs_varieties() {
+        assert_eq!(pick_variety(&[], &mut ChaCha8Rng::seed_from_u64(0)), None);
+    }
+
+    #[tokio::test]
+    async fn spawn_pokemon_fails_without_varieties() {
+        let mut pokedex = dex_with_varieties(0, "missingno", &[], &[]);
+        let result = spawn_pokemon(&mut pokedex, &english()).await;
+        assert!(matches!(result, Err(pokedex::Error::Incomplete(_))));
+    }
+
+    #[tokio::test]
+    async fn spawn_pokemon_describes_the_spawned_variety() {
+        let mut pokedex = bulbasaur_dex();
+        let spawned = spawn_pokemon(&mut pokedex, &english()).await.unwrap();
+        assert_eq!(spawned.species_id, 1);
+        assert_eq!(spawned.species_name, "bulbasaur");
+        assert_eq!(spawned.variety_id, 1);
+        assert!(spawned.is_default_variety);
+        assert_eq!(spawned.display_name, "Bulbasaur");
+        assert_eq!(spawned.types, ["grass", "poison"]);
+        assert!(!spawned.is_legendary);
+    }
+
+    #[tokio::test]
+    async fn spawn_pokemon_fails_without_species() {
+        let result = spawn_pokemon(&mut MemoryPokedex::new(), &english()).await;
+        assert!(matches!(result, Err(pokedex::Error::Incomplete(_))));
+    }
+
+    #[tokio::test]
+    async fn catching_with_the_right_name() {
+        let mut pokedex = bulbasaur_dex();
+        let repo = wild_bulbasaur(&mut pokedex).await;
+
+        let caught = match catch_pokemon(&repo, &mut pokedex, "ash", "route-1", "BULBASAUR")
+            .await
+            .unwrap()
+        {
+            CatchResult::Caught(caught) => caught,
+            other => panic!("expected a catch, got {:?}", other),
+        };
+        assert_eq!((caught.owner.as_str(), caught.species_id), ("ash", 1));
+        assert_eq!(caught.types, ["grass", "poison"]);
+        assert!(repo.active_spawn("route-1").await.unwrap().is_none());
+        assert_eq!(repo.pokemon_owned_by("ash").await.unwrap().len(), 1);
+
+        let player = repo.get_player("ash").await.unwrap().unwrap();
+        assert_eq!(player.total_caught, 1);
+        assert!(player.seen_species.contains(&1) && player.caught_species.contains(&1));
+    }
+
+    #[tokio::test]
+    async fn wrong_guesses_see_the_species_but_leave_it() {
+        let mut pokedex = bulbasaur_dex();
+        let repo = wild_bulbasaur(&mut pokedex).await;
+
+        let result = catch_pokemon(&repo, &mut pokedex, "ash", "route-1", "ivysaur")
+            .await
+            .unwrap();
+        assert!(matches!(result, CatchResult::WrongGuess));
+        assert!(repo.active_spawn("route-1").await.unwrap().is_some());
+        assert!(repo.pokemon_owned_by("ash").await.unwrap().is_empty());
+
+        let player = repo.get_player("ash").await.unwrap().unwrap();
+        assert_eq!(player.total_caught, 0);
+        assert!(player.seen_species.contains(&1) && !player.caught_species.contains(&1));
+    }
+
+    #[tokio::test]
+    async fn spawns_can_only_be_caught_once() {
+        let mut pokedex = bulbasaur_dex();
+        let repo = wild_bulbasaur(&mut pokedex).await;
+
+        let first = catch_pokemon(&repo, &mut pokedex, "ash", "route-1", "bulbasaur")
+            .await
+            .unwrap();
+        assert!(matches!(first, CatchResult::Caught(_)));
+        let second = catch_pokemon(&repo, &mut pokedex, "misty", "route-1", "bulbasaur")
+            .await
+            .unwrap();
+        assert!(matches!(second, CatchResult::NoSpawn));
+        assert!(repo.pokemon_owned_by("misty").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn catching_unregistered_players_fails() {
+        let mut pokedex = bulbasaur_dex();
+        let repo = wild_bulbasaur(&mut pokedex).await;
+
+        let result = catch_pokemon(&repo, &mut pokedex, "brock", "route-1", "bulbasaur").await;
+        assert!(matches!(
+            result,
+            Err(Error::Database(database::Error::NotRegistered(_)))
+        ));
+        assert!(repo.active_spawn("route-1").await.unwrap().is_some());
+    }
+}
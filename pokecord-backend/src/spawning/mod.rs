@@ -0,0 +1,85 @@
+//! The `spawning` module contains code for picking which wild pokemon appear
+//! in a channel, including which variety (regional variant, mega evolution,
+//! etc.) of its species a spawn is, and for catching them.
+
+use crate::database::{self, records::Spawn, repository::SpawnRepository, MongoRepository};
+use crate::guilds;
+use crate::models::OwnedPokemon;
+use crate::pokedex::Pokedex;
+use pyo3::prelude::*;
+use pyo3_asyncio::tokio as pytokio;
+
+mod handlers;
+
+// Adds all required functions into the module.
+pub fn init_submodule(module: &PyModule) -> PyResult<()> {
+    module.add_function(pyo3::wrap_pyfunction!(spawn_pokemon, module)?)?;
+    module.add_function(pyo3::wrap_pyfunction!(catch_pokemon, module)?)?;
+    Ok(())
+}
+
+/// Spawns a random wild pokemon.
+///
+/// # Arguments
+///
+/// * `languages` - Optional `list` of PokeAPI language names to localize the
+///   pokemon's name and flavor text in, most preferred first. English is used
+///   when none of them are available.
+/// * `guild_id` - Optional ID of the guild the pokemon spawns in. Its language
+///   setting is used when `languages` isn't given.
+/// * `channel_id` - Optional ID of the channel the pokemon spawns in. If
+///   given, the pokemon waits there to be caught with `catch_pokemon`,
+///   replacing any earlier spawn.
+///
+/// # Returns
+///
+/// The spawned `Pokemon`. This raises a `PokedexError` if pokemon data could
+/// not be fetched.
+#[pyfunction]
+#[text_signature = "(languages=None, guild_id=None, channel_id=None, /)"]
+fn spawn_pokemon(
+    py: Python,
+    languages: Option<Vec<String>>,
+    guild_id: Option<String>,
+    channel_id: Option<String>,
+) -> PyResult<PyObject> {
+    pytokio::into_coroutine(py, async move {
+        let mut pokedex = Pokedex::new();
+        let repo = MongoRepository::new(database::get()?);
+        let languages = guilds::languages(&repo, guild_id.as_deref(), languages).await?;
+        let pokemon = handlers::spawn_pokemon(&mut pokedex, &languages).await?;
+        if let Some(channel_id) = channel_id {
+            let spawn = Spawn::new(&channel_id, guild_id.as_deref(), &pokemon, &mut rand::thread_rng());
+            repo.put_spawn(&spawn).await?;
+        }
+        Ok(Python::with_gil(|py| pokemon.into_py(py)))
+    })
+}
+
+/// Tries to catch the pokemon waiting in a channel. The guess may be the
+/// species' name in any language, ignoring case and accents.
+///
+/// # Returns
+///
+/// The caught `OwnedPokemon`, or `None` if the guess was wrong or there was
+/// nothing to catch. Raises `KeyError` if the player isn't registered.
+#[pyfunction]
+#[text_signature = "(player_id, channel_id, guess, guild_id=None, /)"]
+fn catch_pokemon(
+    py: Python,
+    player_id: String,
+    channel_id: String,
+    guess: String,
+    guild_id: Option<String>,
+) -> PyResult<PyObject> {
+    pytokio::into_coroutine(py, async move {
+        let repo = MongoRepository::new(database::get()?);
+        let key = guilds::player_key(&repo, guild_id.as_deref(), &player_id).await?;
+        let result = handlers::catch_pokemon(&repo, &mut Pokedex::new(), &key, &channel_id, &guess).await?;
+        let caught = match result {
+            handlers::CatchResult::Caught(pokemon) => Some(OwnedPokemon::from(*pokemon)),
+            handlers::CatchResult::WrongGuess | handlers::CatchResult::NoSpawn => None,
+        };
+        Ok(Python::with_gil(|py| caught.into_py(py)))
+    })
+}
@@ -30,6 +30,7 @@ async def main():
     root.addHandler(handler)
 
     pokecord_backend.test_logging()
+    await pokecord_backend.connect()
 
     """Main entry point into the pokecord application."""
     a = pokecord_backend.registration.get_starter_pokemon_list()